@@ -19,7 +19,7 @@
 #![warn(missing_docs)]
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[cfg(feature = "ts")]
 use ts_rs::TS;
@@ -27,12 +27,25 @@ use ts_rs::TS;
 #[cfg(feature = "json-schema")]
 use schemars::JsonSchema;
 
+pub mod v1;
+
+/// The current version of the quiz format.
+///
+/// Version 1 is the format used before quizzes had a `version` key; see [`v1`].
+pub const CURRENT_VERSION: u32 = 2;
+
 /// A quiz is the top-level data structure in mdbook-quiz.
 /// It represents a sequence of questions.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(TS), ts(export))]
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 pub struct Quiz {
+  /// The version of the quiz format, which defaults to [`CURRENT_VERSION`] if omitted.
+  ///
+  /// Quizzes in older versions can be upgraded with `mdbook-quiz migrate`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub version: Option<u32>,
+
   /// The questions of the quiz.
   pub questions: Vec<Question>,
 
@@ -41,6 +54,64 @@ pub struct Quiz {
   /// Maps from a string key to a description of the question context.
   #[cfg_attr(feature = "ts", ts(optional))]
   pub multipart: Option<HashMap<String, Markdown>>,
+
+  /// Settings for spellchecking this quiz, if the book enables the spellchecker.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub spellcheck: Option<SpellcheckConfig>,
+
+  /// Pools of interchangeable questions, of which only some are asked in each attempt.
+  ///
+  /// Questions that are not in a pool are always asked.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub pools: Option<Vec<QuestionPool>>,
+}
+
+/// A pool of interchangeable questions in a [`Quiz`].
+///
+/// The pool contains every question with one of its `ids` or `tags`, or every question in
+/// the quiz if neither is given.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct QuestionPool {
+  /// The IDs of questions in the pool.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub ids: Option<Vec<String>>,
+
+  /// Tags of questions in the pool.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub tags: Option<Vec<String>>,
+
+  /// How many of the pool's questions are asked in each attempt.
+  pub draw: PoolDraw,
+}
+
+/// How many questions are drawn from a [`QuestionPool`].
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(untagged)]
+pub enum PoolDraw {
+  /// This many questions are drawn from the whole pool.
+  Count(usize),
+
+  /// For each tag, this many questions with that tag are drawn from the pool,
+  /// e.g. `{ easy = 2, hard = 1 }`.
+  PerTag(BTreeMap<String, usize>),
+}
+
+/// Settings for spellchecking a [`Quiz`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct SpellcheckConfig {
+  /// If false, then the spellchecker skips this quiz.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub enabled: Option<bool>,
+
+  /// Words that are always spelled correctly in this quiz, ignoring case.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub ignore: Option<Vec<String>>,
 }
 
 /// A [Markdown](https://commonmark.org/help/) string.
@@ -61,6 +132,61 @@ pub enum Question {
   Tracing(Tracing),
   /// A [`MultipleChoice`] question.
   MultipleChoice(MultipleChoice),
+  /// A [`FillInTheBlank`] question.
+  FillInTheBlank(FillInTheBlank),
+  /// An [`Ordering`] question.
+  Ordering(Ordering),
+  /// A [`Matching`] question.
+  Matching(Matching),
+  /// A [`CodeExercise`] question.
+  CodeExercise(CodeExercise),
+  /// A [`Numeric`] question.
+  Numeric(Numeric),
+}
+
+impl Question {
+  /// The [`QuestionFields::id`] of the question, if it has one.
+  pub fn id(&self) -> Option<&str> {
+    match self {
+      Question::ShortAnswer(q) => q.0.id.as_deref(),
+      Question::Tracing(q) => q.0.id.as_deref(),
+      Question::MultipleChoice(q) => q.0.id.as_deref(),
+      Question::FillInTheBlank(q) => q.0.id.as_deref(),
+      Question::Ordering(q) => q.0.id.as_deref(),
+      Question::Matching(q) => q.0.id.as_deref(),
+      Question::CodeExercise(q) => q.0.id.as_deref(),
+      Question::Numeric(q) => q.0.id.as_deref(),
+    }
+  }
+
+  /// The [`QuestionFields::fingerprint`] of the question, if it has one.
+  pub fn fingerprint(&self) -> Option<&str> {
+    match self {
+      Question::ShortAnswer(q) => q.0.fingerprint.as_deref(),
+      Question::Tracing(q) => q.0.fingerprint.as_deref(),
+      Question::MultipleChoice(q) => q.0.fingerprint.as_deref(),
+      Question::FillInTheBlank(q) => q.0.fingerprint.as_deref(),
+      Question::Ordering(q) => q.0.fingerprint.as_deref(),
+      Question::Matching(q) => q.0.fingerprint.as_deref(),
+      Question::CodeExercise(q) => q.0.fingerprint.as_deref(),
+      Question::Numeric(q) => q.0.fingerprint.as_deref(),
+    }
+  }
+
+  /// The [`QuestionFields::tags`] of the question.
+  pub fn tags(&self) -> &[String] {
+    let tags = match self {
+      Question::ShortAnswer(q) => &q.0.tags,
+      Question::Tracing(q) => &q.0.tags,
+      Question::MultipleChoice(q) => &q.0.tags,
+      Question::FillInTheBlank(q) => &q.0.tags,
+      Question::Ordering(q) => &q.0.tags,
+      Question::Matching(q) => &q.0.tags,
+      Question::CodeExercise(q) => &q.0.tags,
+      Question::Numeric(q) => &q.0.tags,
+    };
+    tags.as_deref().unwrap_or_default()
+  }
 }
 
 /// Fields common to all question types.
@@ -75,6 +201,11 @@ pub struct QuestionFields<Prompt, Answer> {
   #[cfg_attr(feature = "ts", ts(optional))]
   pub id: Option<String>,
 
+  /// A summary of the question's prompt when its ID was derived from its content,
+  /// used to tell whether the prompt has changed substantially since then.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub fingerprint: Option<String>,
+
   /// If this key exists, then this question is part of a multipart group.
   /// The key must be contained in the [`Quiz::multipart`] map.
   #[cfg_attr(feature = "ts", ts(optional))]
@@ -97,6 +228,15 @@ pub struct QuestionFields<Prompt, Answer> {
   /// Useful for getting a qualitative sense of why users respond a particular way.
   #[cfg_attr(feature = "ts", ts(optional))]
   pub prompt_explanation: Option<bool>,
+
+  /// If false, then the spellchecker skips this question.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub spellcheck: Option<bool>,
+
+  /// Labels for the question, so a chapter can embed only the questions with a given tag
+  /// using `{{#quiz <file> tags=<tag>}}`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub tags: Option<Vec<String>>,
 }
 
 /// The kind of response format (and subsequent input method) that accompanies
@@ -148,13 +288,62 @@ pub struct ShortAnswerAnswer {
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 pub struct ShortAnswer(pub QuestionFields<ShortAnswerPrompt, ShortAnswerAnswer>);
 
+/// Settings for compiling the program of a [`Tracing`] question.
+///
+/// Each setting overrides the book's `[preprocessor.quiz.rust]` settings.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct RustConfig {
+  /// The Rust edition of the program, e.g. `"2021"`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub edition: Option<String>,
+
+  /// The rustup toolchain used to compile the program, e.g. `"nightly"`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub toolchain: Option<String>,
+
+  /// Configuration options passed to rustc as `--cfg`, in addition to the book's.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub cfg: Option<Vec<String>>,
+
+  /// Other flags passed to rustc, in addition to the book's.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub flags: Option<Vec<String>>,
+}
+
 /// A prompt for a [`Tracing`] question.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(TS), ts(export))]
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
 pub struct TracingPrompt {
   /// The contents of the program to trace.
+  ///
+  /// If the program has other [`files`](TracingPrompt::files), then this is its entrypoint.
+  ///
+  /// Like in rustdoc, lines starting with `# ` are compiled but hidden from readers.
   pub program: String,
+
+  /// The path of [`program`](TracingPrompt::program), `"main.rs"` by default.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub entrypoint: Option<String>,
+
+  /// The other files of the program, keyed by their path relative to the crate root.
+  ///
+  /// Modules are laid out as in a Cargo project, e.g. `mod garden;` in `main.rs` refers
+  /// to `garden.rs`. If there is a `lib.rs` beside the entrypoint, then it is compiled as a
+  /// library crate that the entrypoint can use.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub files: Option<BTreeMap<String, String>>,
+
+  /// The name of the library crate compiled from `lib.rs`, `"quiz"` by default.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub crate_name: Option<String>,
+
+  /// How to compile the program, if different from the rest of the book.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub rust: Option<RustConfig>,
 }
 
 /// An answer for a [`Tracing`] question.
@@ -166,13 +355,43 @@ pub struct TracingAnswer {
   /// True if the program should pass the compiler
   pub does_compile: bool,
 
-  /// If doesCompile=true, then the contents of stdout after running the program
+  /// If doesCompile=true, then the contents of stdout after running the program.
+  ///
+  /// If the program panics, then the contents of stdout printed before the panic.
   #[cfg_attr(feature = "ts", ts(optional))]
   pub stdout: Option<String>,
 
-  /// If doesCompile=false, then the line number of the code causing the error
+  /// If doesCompile=true, then how the program panics, if it should.
+  ///
+  /// Use an empty table, e.g. `answer.panic = {}`, for a panic with any message.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub panic: Option<TracingPanic>,
+
+  /// If doesCompile=false, then the line number of the code causing the error.
+  ///
+  /// Must be the line of an error reported by rustc. Hidden lines are not counted.
   #[cfg_attr(feature = "ts", ts(optional))]
   pub line_number: Option<usize>,
+
+  /// If doesCompile=false, then the path of the file containing `lineNumber`,
+  /// if it is not the entrypoint.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub file: Option<String>,
+}
+
+/// The expected panic of the program of a [`Tracing`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct TracingPanic {
+  /// The panic message, e.g. `"index out of bounds: the len is 3 but the index is 5"`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub message: Option<String>,
+
+  /// The exit code of the program. Defaults to 101, the exit code of a panic in `main`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub exit_code: Option<i32>,
 }
 
 /// A question where users guess the output of a program.
@@ -230,6 +449,247 @@ pub struct MultipleChoiceAnswer {
 #[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 pub struct MultipleChoice(pub QuestionFields<MultipleChoicePrompt, MultipleChoiceAnswer>);
 
+/// How the text of a [`FillInTheBlank`] question is rendered.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum FillInTheBlankFormat {
+  /// The text is Markdown, and blanks are rendered inline.
+  Markdown,
+
+  /// The text is a Rust program, and blanks are rendered within the code.
+  Code,
+}
+
+/// A prompt for a [`FillInTheBlank`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct FillInTheBlankPrompt {
+  /// The text of the prompt.
+  pub prompt: Markdown,
+
+  /// The text containing the blanks, which are written as numbers in double braces,
+  /// e.g. `{{1}}`. A blank may appear more than once.
+  pub text: String,
+
+  /// Format of the text. Defaults to Markdown.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub format: Option<FillInTheBlankFormat>,
+}
+
+/// An acceptable response for one blank of a [`FillInTheBlank`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(untagged)]
+pub enum BlankAnswer {
+  /// The exact string, ignoring leading and trailing whitespace.
+  Exact(String),
+
+  /// A regular expression that must match the entire response.
+  Pattern {
+    /// The regular expression.
+    regex: String,
+  },
+}
+
+/// An answer for a [`FillInTheBlank`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct FillInTheBlankAnswer {
+  /// Maps the number of each blank to its acceptable responses.
+  pub blanks: HashMap<String, Vec<BlankAnswer>>,
+}
+
+/// A question where users fill in the blanks of a text or program.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct FillInTheBlank(pub QuestionFields<FillInTheBlankPrompt, FillInTheBlankAnswer>);
+
+/// The kind of fragments in an [`Ordering`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OrderingFormat {
+  /// Each fragment is Markdown.
+  Markdown,
+
+  /// Each fragment is one or more lines of a Rust program.
+  Code,
+}
+
+/// A prompt for an [`Ordering`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct OrderingPrompt {
+  /// The text of the prompt.
+  pub prompt: Markdown,
+
+  /// Fragments that are not part of the answer, shuffled in with the correct fragments.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub distractors: Option<Vec<String>>,
+
+  /// Format of the fragments. Defaults to Markdown.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub format: Option<OrderingFormat>,
+
+  /// If true, users must also indent each fragment to match its indentation in the answer.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub grade_indentation: Option<bool>,
+}
+
+/// An answer for an [`Ordering`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct OrderingAnswer {
+  /// The fragments in the correct order.
+  ///
+  /// Leading whitespace is the fragment's indentation, which is only graded if
+  /// [`OrderingPrompt::grade_indentation`] is true.
+  pub fragments: Vec<String>,
+}
+
+/// A question where users put fragments of a text or program into the correct order.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct Ordering(pub QuestionFields<OrderingPrompt, OrderingAnswer>);
+
+/// A prompt for a [`Matching`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct MatchingPrompt {
+  /// The text of the prompt.
+  pub prompt: Markdown,
+
+  /// The items in the left column, each of which is matched with an item on the right.
+  pub left: Vec<Markdown>,
+
+  /// The items in the right column. Items that are not matched with any item
+  /// on the left are distractors.
+  pub right: Vec<Markdown>,
+}
+
+/// An answer for a [`Matching`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct MatchingAnswer {
+  /// Pairs of a left item and its matching right item.
+  ///
+  /// Every left item must appear exactly once, while a right item may appear any number of times.
+  pub pairs: Vec<(Markdown, Markdown)>,
+}
+
+/// A question where users match each item in one column with an item in another.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct Matching(pub QuestionFields<MatchingPrompt, MatchingAnswer>);
+
+/// A prompt for a [`CodeExercise`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct CodeExercisePrompt {
+  /// The text of the prompt.
+  pub prompt: Markdown,
+
+  /// The program that users start from, which must not pass the tests.
+  pub starter: String,
+}
+
+/// An answer for a [`CodeExercise`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct CodeExerciseAnswer {
+  /// A reference solution, which must pass the tests.
+  pub solution: String,
+
+  /// The body of a test module that is appended to a program to check it, which is
+  /// not shown to users. Items of the program are in scope via `use super::*`.
+  pub tests: String,
+}
+
+/// A question where users write a program that is checked by hidden tests.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct CodeExercise(pub QuestionFields<CodeExercisePrompt, CodeExerciseAnswer>);
+
+/// A prompt for a [`Numeric`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct NumericPrompt {
+  /// The text of the prompt.
+  pub prompt: Markdown,
+
+  /// Units shown after the input, e.g. `bytes`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub units: Option<String>,
+}
+
+/// A way of writing an integer besides decimal, for a [`Numeric`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum NumericRepresentation {
+  /// Hexadecimal with a `0x` prefix, e.g. `0xff`.
+  Hex,
+
+  /// Binary with a `0b` prefix, e.g. `0b1010`.
+  Binary,
+}
+
+/// An answer for a [`Numeric`] question.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct NumericAnswer {
+  /// The exact answer. Exactly one of `value` and `range` must be defined.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub value: Option<f64>,
+
+  /// An inclusive range `[min, max]` of correct answers.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub range: Option<(f64, f64)>,
+
+  /// How far a response may be from the answer and still be correct.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub tolerance: Option<f64>,
+
+  /// Like `tolerance`, but as a fraction of the answer, e.g. `0.01` for 1%.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub relative_tolerance: Option<f64>,
+
+  /// If true, then only integers are accepted as responses.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub integer: Option<bool>,
+
+  /// Representations accepted in addition to decimal. Requires `integer = true`.
+  #[cfg_attr(feature = "ts", ts(optional))]
+  pub representations: Option<Vec<NumericRepresentation>>,
+}
+
+/// A question where users type in a number.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(TS), ts(export))]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
+pub struct Numeric(pub QuestionFields<NumericPrompt, NumericAnswer>);
+
 #[cfg(test)]
 mod test {
   use super::*;
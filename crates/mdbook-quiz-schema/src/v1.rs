@@ -0,0 +1,199 @@
+//! Version 1 of the quiz format, used before quizzes had a `version` key.
+//!
+//! It differs from the current format only in [`MultipleChoice`] questions, which listed every
+//! choice in `prompt.choices` and gave the index of the correct choice in `answer.answer`:
+//!
+//! ```toml
+//! [[questions]]
+//! type = "MultipleChoice"
+//! prompt.prompt = "What is 1 + 1?"
+//! prompt.choices = ["1", "2", "3"]
+//! answer.answer = 1
+//! ```
+//!
+//! A version 1 [`Quiz`] can be upgraded into the current [`crate::Quiz`] with [`TryFrom`].
+
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt};
+
+use crate::{Markdown, QuestionFields, ShortAnswer, Tracing};
+
+/// A quiz in version 1 of the format.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Quiz {
+  /// The version of the quiz format. If present, must be 1.
+  pub version: Option<u32>,
+
+  /// The questions of the quiz.
+  pub questions: Vec<Question>,
+
+  /// Context for multipart questions.
+  pub multipart: Option<HashMap<String, Markdown>>,
+}
+
+/// An individual question in version 1 of the format.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
+pub enum Question {
+  /// A [`ShortAnswer`] question, unchanged from version 1.
+  ShortAnswer(ShortAnswer),
+  /// A [`Tracing`] question, unchanged from version 1.
+  Tracing(Tracing),
+  /// A [`MultipleChoice`] question.
+  MultipleChoice(MultipleChoice),
+}
+
+/// A prompt for a version 1 [`MultipleChoice`] question.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultipleChoicePrompt {
+  /// The text of the prompt.
+  pub prompt: Markdown,
+
+  /// Every choice, in the order they are shown.
+  pub choices: Vec<Markdown>,
+}
+
+/// An answer for a version 1 [`MultipleChoice`] question.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultipleChoiceAnswer {
+  /// The index of the correct choice in [`MultipleChoicePrompt::choices`].
+  pub answer: usize,
+}
+
+/// A version 1 question where users select among several possible answers.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultipleChoice(pub QuestionFields<MultipleChoicePrompt, MultipleChoiceAnswer>);
+
+/// An error from upgrading a version 1 quiz that cannot be expressed in the current format.
+#[derive(Debug)]
+pub struct UpgradeError {
+  /// The index of the offending question.
+  pub question: usize,
+
+  /// A description of the problem.
+  pub message: String,
+}
+
+impl fmt::Display for UpgradeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "question {}: {}", self.question + 1, self.message)
+  }
+}
+
+impl std::error::Error for UpgradeError {}
+
+impl TryFrom<MultipleChoice> for crate::MultipleChoice {
+  type Error = String;
+
+  fn try_from(q: MultipleChoice) -> Result<Self, Self::Error> {
+    let QuestionFields {
+      id,
+      fingerprint,
+      multipart,
+      prompt,
+      answer,
+      context,
+      prompt_explanation,
+      spellcheck,
+      tags,
+    } = q.0;
+    let mut distractors = prompt.choices;
+    let index = answer.answer;
+    if index >= distractors.len() {
+      return Err(format!(
+        "answer index {index} is out of bounds for {} choices",
+        distractors.len()
+      ));
+    }
+    let correct = distractors.remove(index);
+    Ok(crate::MultipleChoice(QuestionFields {
+      id,
+      fingerprint,
+      multipart,
+      prompt: crate::MultipleChoicePrompt {
+        prompt: prompt.prompt,
+        distractors,
+        answer_index: Some(index),
+        sort_answers: None,
+      },
+      answer: crate::MultipleChoiceAnswer {
+        answer: crate::MultipleChoiceAnswerFormat::Single(correct),
+      },
+      context,
+      prompt_explanation,
+      spellcheck,
+      tags,
+    }))
+  }
+}
+
+impl TryFrom<Quiz> for crate::Quiz {
+  type Error = UpgradeError;
+
+  fn try_from(quiz: Quiz) -> Result<Self, Self::Error> {
+    let questions = quiz
+      .questions
+      .into_iter()
+      .enumerate()
+      .map(|(i, q)| {
+        Ok(match q {
+          Question::ShortAnswer(q) => crate::Question::ShortAnswer(q),
+          Question::Tracing(q) => crate::Question::Tracing(q),
+          Question::MultipleChoice(q) => {
+            crate::Question::MultipleChoice(q.try_into().map_err(|message| UpgradeError {
+              question: i,
+              message,
+            })?)
+          }
+        })
+      })
+      .collect::<Result<_, _>>()?;
+    Ok(crate::Quiz {
+      version: Some(crate::CURRENT_VERSION),
+      questions,
+      multipart: quiz.multipart,
+      spellcheck: None,
+      pools: None,
+    })
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_upgrade_multiple_choice() {
+    let contents = r#"
+[[questions]]
+type = "MultipleChoice"
+prompt.prompt = "What is 1 + 1?"
+prompt.choices = ["1", "2", "3"]
+answer.answer = 1
+"#;
+    let quiz: Quiz = toml::from_str(contents).unwrap();
+    let quiz = crate::Quiz::try_from(quiz).unwrap();
+    let crate::Question::MultipleChoice(q) = &quiz.questions[0] else {
+      panic!("Invalid quiz")
+    };
+    assert_eq!(q.0.prompt.answer_index, Some(1));
+    assert_eq!(
+      q.0
+        .prompt
+        .distractors
+        .iter()
+        .map(|d| &d.0)
+        .collect::<Vec<_>>(),
+      ["1", "3"]
+    );
+    assert!(matches!(
+      &q.0.answer.answer,
+      crate::MultipleChoiceAnswerFormat::Single(a) if a.0 == "2"
+    ));
+
+    let out_of_bounds = contents.replace("answer.answer = 1", "answer.answer = 3");
+    let quiz: Quiz = toml::from_str(&out_of_bounds).unwrap();
+    assert!(crate::Quiz::try_from(quiz).is_err());
+  }
+}
@@ -0,0 +1,106 @@
+//! A content-addressed cache for the results of compiling and running quiz programs.
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+  collections::HashMap,
+  fs,
+  io::Write,
+  path::Path,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, OnceLock,
+  },
+};
+use uuid::Uuid;
+
+/// Namespace for cache keys.
+const CACHE_NAMESPACE: Uuid = Uuid::from_u128(0x8f3e_0d2a_7c41_4b9e_a6d5_1e2f_3c4b_5a69);
+
+/// Derives a cache key from every input that can affect a result.
+pub(crate) fn key(parts: &[&str]) -> Uuid {
+  Uuid::new_v5(&CACHE_NAMESPACE, parts.join("\0").as_bytes())
+}
+
+/// Results computed by this process, serialized as JSON.
+fn memory() -> &'static Mutex<HashMap<Uuid, String>> {
+  static MEMORY: OnceLock<Mutex<HashMap<Uuid, String>>> = OnceLock::new();
+  MEMORY.get_or_init(Default::default)
+}
+
+/// Returns the result of `f`, reusing the result for the same `key` if one has been computed
+/// by this process or stored in `dir`.
+///
+/// Errors from `f` are not cached, nor are results for which `should_cache` returns false.
+pub(crate) fn cached<T: Serialize + DeserializeOwned>(
+  dir: Option<&Path>,
+  key: Uuid,
+  f: impl FnOnce() -> Result<T>,
+  should_cache: impl FnOnce(&T) -> bool,
+) -> Result<T> {
+  let path = dir.map(|dir| dir.join(format!("{key}.json")));
+
+  let in_memory = memory().lock().unwrap().get(&key).cloned();
+  let stored = in_memory.or_else(|| path.as_ref().and_then(|path| fs::read_to_string(path).ok()));
+  if let Some(value) = stored.and_then(|json| serde_json::from_str(&json).ok()) {
+    return Ok(value);
+  }
+
+  let value = f()?;
+  if !should_cache(&value) {
+    return Ok(value);
+  }
+  let json = serde_json::to_string(&value)?;
+  if let (Some(dir), Some(path)) = (dir, &path) {
+    // The disk cache only saves time, so a read-only or full disk shouldn't fail the build.
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    if let Err(e) = store(dir, path, &json) {
+      if !WARNED.swap(true, Ordering::Relaxed) {
+        eprintln!(
+          "Warning: Failed to write to the quiz cache in {}: {e}",
+          dir.display()
+        );
+      }
+    }
+  }
+  memory().lock().unwrap().insert(key, json);
+  Ok(value)
+}
+
+/// Writes `json` to `path` in `dir`.
+fn store(dir: &Path, path: &Path, json: &str) -> std::io::Result<()> {
+  // Write to a temporary file first so that concurrent builds never see a partial entry.
+  fs::create_dir_all(dir)?;
+  let mut file = tempfile::NamedTempFile::new_in(dir)?;
+  file.write_all(json.as_bytes())?;
+  file.persist(path)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_cached() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let key = key(&["test_cached", &Uuid::new_v4().to_string()]);
+    assert_eq!(cached(Some(dir.path()), key, || Ok(1), |_| true)?, 1);
+    assert_eq!(cached(Some(dir.path()), key, || Ok(2), |_| true)?, 1);
+    assert!(dir.path().join(format!("{key}.json")).exists());
+
+    // Failed and uncacheable computations are retried.
+    let key = super::key(&["test_cached", &Uuid::new_v4().to_string()]);
+    assert!(cached::<i32>(None, key, || anyhow::bail!("failed"), |_| true).is_err());
+    assert_eq!(cached(None, key, || Ok(3), |_| false)?, 3);
+    assert_eq!(cached(None, key, || Ok(4), |_| true)?, 4);
+
+    // A cache directory that can't be written to is skipped.
+    let file = dir.path().join("file");
+    fs::write(&file, "")?;
+    let key = super::key(&["test_cached", &Uuid::new_v4().to_string()]);
+    assert_eq!(cached(Some(&file), key, || Ok(5), |_| true)?, 5);
+    assert_eq!(cached(Some(&file), key, || Ok(6), |_| true)?, 5);
+    Ok(())
+  }
+}
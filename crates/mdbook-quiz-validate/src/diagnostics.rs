@@ -0,0 +1,223 @@
+//! Structured, machine-readable representations of validation diagnostics.
+
+use std::{
+  fmt,
+  path::{Path, PathBuf},
+};
+
+use miette::Diagnostic;
+use serde::Serialize;
+
+use crate::QuestionRef;
+
+/// How serious a [`ValidationDiagnostic`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  /// The quiz is invalid and should not be built.
+  Error,
+
+  /// The quiz is valid but probably contains a mistake, e.g. a spelling error.
+  Warning,
+}
+
+/// A 1-based line and column in a quiz file. Columns are counted in Unicode code points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+  /// The line number, starting at 1.
+  pub line: usize,
+
+  /// The column number, starting at 1.
+  pub column: usize,
+}
+
+impl Position {
+  fn new(contents: &str, offset: usize) -> Self {
+    let before = &contents[..offset.min(contents.len())];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    Position {
+      line: before.matches('\n').count() + 1,
+      column: before[line_start..].chars().count() + 1,
+    }
+  }
+}
+
+/// The region of a quiz file that a [`ValidationDiagnostic`] refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+  /// Byte offset of the start of the span.
+  pub offset: usize,
+
+  /// Length of the span in bytes.
+  pub length: usize,
+
+  /// Position of the first character of the span.
+  pub start: Position,
+
+  /// Position just after the last character of the span.
+  pub end: Position,
+}
+
+impl Span {
+  fn new(contents: &str, offset: usize, length: usize) -> Self {
+    Span {
+      offset,
+      length,
+      start: Position::new(contents, offset),
+      end: Position::new(contents, offset + length),
+    }
+  }
+}
+
+/// A single problem found while validating a quiz.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationDiagnostic {
+  /// Whether the problem prevents the quiz from being built.
+  pub severity: Severity,
+
+  /// A human-readable description of the problem.
+  pub message: String,
+
+  /// The name of the check that produced the problem, e.g. `quiz::duplicate_id`.
+  pub rule: Option<String>,
+
+  /// The quiz file containing the problem.
+  pub file: PathBuf,
+
+  /// The location of the problem in the quiz file, if known.
+  pub span: Option<Span>,
+
+  /// The 0-based index of the question containing the problem, if any.
+  pub question_index: Option<usize>,
+
+  /// The ID of the question containing the problem, if it has one.
+  pub question_id: Option<String>,
+}
+
+impl ValidationDiagnostic {
+  pub(crate) fn new(
+    error: &dyn Diagnostic,
+    fatal: bool,
+    path: &Path,
+    contents: &str,
+    question: Option<QuestionRef>,
+  ) -> Self {
+    let span = error
+      .labels()
+      .and_then(|mut labels| labels.next())
+      .map(|label| Span::new(contents, label.offset(), label.len()));
+    ValidationDiagnostic {
+      severity: if fatal {
+        Severity::Error
+      } else {
+        Severity::Warning
+      },
+      message: error.to_string(),
+      rule: error.code().map(|code| code.to_string()),
+      file: path.to_owned(),
+      span,
+      question_index: question.as_ref().map(|q| q.index),
+      question_id: question.and_then(|q| q.id),
+    }
+  }
+}
+
+/// Every diagnostic produced by validating a single quiz, see [`diagnose`](crate::diagnose).
+///
+/// The `Debug` implementation renders each diagnostic as a human-readable report.
+pub struct ValidationReport {
+  diagnostics: Vec<ValidationDiagnostic>,
+  rendered: String,
+}
+
+impl ValidationReport {
+  pub(crate) fn new(diagnostics: Vec<ValidationDiagnostic>, rendered: String) -> Self {
+    ValidationReport {
+      diagnostics,
+      rendered,
+    }
+  }
+
+  /// Appends the diagnostics of `other` to this report.
+  pub(crate) fn extend(&mut self, other: ValidationReport) {
+    self.diagnostics.extend(other.diagnostics);
+    self.rendered.push_str(&other.rendered);
+  }
+
+  /// The diagnostics in the order they were found.
+  pub fn diagnostics(&self) -> &[ValidationDiagnostic] {
+    &self.diagnostics
+  }
+
+  /// Consumes the report, returning its diagnostics.
+  pub fn into_diagnostics(self) -> Vec<ValidationDiagnostic> {
+    self.diagnostics
+  }
+
+  /// Returns true if any diagnostic is an error.
+  pub fn is_fatal(&self) -> bool {
+    self
+      .diagnostics
+      .iter()
+      .any(|d| d.severity == Severity::Error)
+  }
+}
+
+impl fmt::Debug for ValidationReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.rendered)
+  }
+}
+
+#[test]
+fn diagnose_duplicate_ids() {
+  let contents = r#"
+[[questions]]
+id = "hello"
+type = "ShortAnswer"
+prompt.prompt = ""
+answer.answer = ""
+
+[[questions]]
+id = "hello"
+type = "ShortAnswer"
+prompt.prompt = ""
+answer.answer = ""
+"#;
+  let report = crate::diagnose(
+    Path::new("dummy.toml"),
+    contents,
+    &crate::IdSet::default(),
+    &Default::default(),
+  );
+  let [diagnostic] = report.diagnostics() else {
+    panic!("expected one diagnostic, got {report:?}")
+  };
+  assert_eq!(diagnostic.severity, Severity::Error);
+  assert_eq!(diagnostic.rule.as_deref(), Some("quiz::duplicate_id"));
+  assert_eq!(diagnostic.question_index, Some(1));
+  assert_eq!(diagnostic.question_id.as_deref(), Some("hello"));
+  let span = diagnostic.span.as_ref().unwrap();
+  assert_eq!(
+    &contents[span.offset..span.offset + span.length],
+    "\"hello\""
+  );
+  assert_eq!(span.start, Position { line: 9, column: 6 });
+}
+
+#[test]
+fn diagnose_parse_error() {
+  let contents = "[[questions]]\ntype = \"ShortAnswer\n";
+  let report = crate::diagnose(
+    Path::new("dummy.toml"),
+    contents,
+    &crate::IdSet::default(),
+    &Default::default(),
+  );
+  let [diagnostic] = report.diagnostics() else {
+    panic!("expected one diagnostic, got {report:?}")
+  };
+  assert_eq!(diagnostic.rule.as_deref(), Some("quiz::parse"));
+  assert_eq!(diagnostic.span.as_ref().unwrap().start.line, 2);
+}
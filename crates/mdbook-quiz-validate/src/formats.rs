@@ -0,0 +1,670 @@
+//! The formats a quiz file can be written in besides TOML.
+//!
+//! JSON and YAML quizzes have the same structure as TOML quizzes. They are converted into the
+//! TOML format before they are validated, along with a [`SourceMap`] back to the original file.
+
+use std::{path::Path, str::Chars};
+
+use json_spanned_value::spanned;
+use miette::{Diagnostic, SourceSpan};
+use yaml_rust2::{
+  parser::{Event, Parser},
+  scanner::TScalarStyle,
+};
+
+use crate::{
+  is_markdown_quiz,
+  source_map::{Kind, SourceMap, Table, Value},
+  MarkdownQuiz, ParseError,
+};
+
+/// A format that a quiz file can be written in, chosen by the name of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuizFormat {
+  /// The TOML format, for any file without the extension of another format.
+  Toml,
+
+  /// The Markdown quiz format, for files ending in `.quiz.md`. See [`MarkdownQuiz`].
+  Markdown,
+
+  /// JSON, for files ending in `.json`.
+  Json,
+
+  /// YAML, for files ending in `.yaml` or `.yml`.
+  Yaml,
+}
+
+impl QuizFormat {
+  /// Returns the format of the quiz at `path`.
+  pub fn from_path(path: &Path) -> Self {
+    if is_markdown_quiz(path) {
+      return QuizFormat::Markdown;
+    }
+    match path.extension().and_then(|ext| ext.to_str()) {
+      Some("json") => QuizFormat::Json,
+      Some("yaml" | "yml") => QuizFormat::Yaml,
+      _ => QuizFormat::Toml,
+    }
+  }
+
+  /// Converts a quiz with `contents` in this format into the TOML format.
+  ///
+  /// TOML quizzes are returned unchanged, so they may still need to be [`migrate`](crate::migrate)d.
+  pub fn to_toml(self, contents: &str) -> anyhow::Result<String> {
+    match self.convert(contents) {
+      Ok(Some((toml, _))) => Ok(toml),
+      Ok(None) => Ok(contents.to_string()),
+      Err(error) => Err(anyhow::anyhow!("{error}")),
+    }
+  }
+
+  /// Adds `ids[i]` as the `id` of the `i`th question of a JSON or YAML quiz with `contents`
+  /// if it doesn't have one, along with `fingerprints[i]` as its `fingerprint` if given,
+  /// preserving the formatting of the file.
+  ///
+  /// Returns the new contents of the quiz if any IDs were added.
+  pub fn insert_ids(
+    self,
+    contents: &str,
+    ids: &[String],
+    fingerprints: Option<&[String]>,
+  ) -> anyhow::Result<Option<String>> {
+    let quiz = match self {
+      QuizFormat::Json => json_quiz(contents),
+      QuizFormat::Yaml => YamlReader::new(contents).quiz(),
+      _ => anyhow::bail!("IDs can only be inserted into JSON and YAML quizzes"),
+    };
+    let quiz = quiz.map_err(|error| anyhow::anyhow!("{error}"))?;
+    let Some(Kind::Array(questions)) = quiz
+      .iter()
+      .find(|(key, _)| key == "questions")
+      .map(|(_, value)| &value.kind)
+    else {
+      anyhow::bail!("Must contain questions");
+    };
+
+    let mut insertions = Vec::new();
+    for (i, (question, id)) in questions.iter().zip(ids).enumerate() {
+      let Kind::Table(entries) = &question.kind else {
+        continue;
+      };
+      if entries.iter().any(|(key, _)| key == "id") {
+        continue;
+      }
+      let mut fields = vec![("id", id)];
+      if let Some(fingerprints) = fingerprints {
+        fields.push(("fingerprint", &fingerprints[i]));
+      }
+      let fields = fields
+        .into_iter()
+        .map(|(key, value)| {
+          let key = match self {
+            QuizFormat::Json => format!("\"{key}\""),
+            _ => key.to_string(),
+          };
+          Ok(format!("{key}: {}", serde_json::to_string(value)?))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+      let start = question.span.start;
+      let insertion = if contents[start..].starts_with('{') {
+        // Put the ID first, separated from the next entry like the entry is from the brace.
+        let rest = &contents[start + 1..];
+        let separator = &rest[..rest.len() - rest.trim_start().len()];
+        let comma = match (entries.is_empty(), separator.is_empty()) {
+          (true, _) => "",
+          (false, true) => ", ",
+          (false, false) => ",",
+        };
+        let between = match separator {
+          "" => ", ".to_string(),
+          separator => format!(",{separator}"),
+        };
+        let fields = fields.join(&between);
+        (start + 1, format!("{separator}{fields}{comma}"))
+      } else {
+        // A block mapping in YAML starts at its first key.
+        let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+        let indent = " ".repeat(contents[line_start..start].chars().count());
+        let fields = fields
+          .iter()
+          .map(|field| format!("{field}\n{indent}"))
+          .collect::<String>();
+        (start, fields)
+      };
+      insertions.push(insertion);
+    }
+
+    let mut new_contents = contents.to_string();
+    for (position, text) in insertions.iter().rev() {
+      new_contents.insert_str(*position, text);
+    }
+    Ok((!insertions.is_empty()).then_some(new_contents))
+  }
+
+  /// Converts a quiz with `contents` in this format into the TOML format along with a map back
+  /// to `contents`, or returns `None` if the quiz is already in the TOML format.
+  pub(crate) fn convert(
+    self,
+    contents: &str,
+  ) -> Result<Option<(String, SourceMap)>, Box<dyn Diagnostic + Send + Sync>> {
+    let (format, quiz) = match self {
+      QuizFormat::Toml => return Ok(None),
+      QuizFormat::Markdown => {
+        let quiz = MarkdownQuiz::parse(contents)?;
+        let toml = quiz.toml().to_string();
+        return Ok(Some((toml, quiz.into_source_map())));
+      }
+      QuizFormat::Json => ("JSON", json_quiz(contents)?),
+      QuizFormat::Yaml => ("YAML", YamlReader::new(contents).quiz()?),
+    };
+    Ok(Some(SourceMap::generate(contents, format, &quiz)))
+  }
+}
+
+fn error(format: &'static str, cause: impl Into<String>, offset: usize) -> ParseError {
+  ParseError {
+    format,
+    cause: cause.into(),
+    span: Some(SourceSpan::from((offset, 0))),
+  }
+}
+
+/// Adds `key` to `table`, which must not already contain it.
+fn push(
+  format: &'static str,
+  table: &mut Table,
+  key: String,
+  key_start: usize,
+  value: Value,
+) -> Result<(), ParseError> {
+  if table.iter().any(|(k, _)| *k == key) {
+    return Err(error(
+      format,
+      format!("`{key}` is given more than once"),
+      key_start,
+    ));
+  }
+  table.push((key, value));
+  Ok(())
+}
+
+/// Reads a JSON quiz into a table.
+fn json_quiz(contents: &str) -> Result<Table, ParseError> {
+  let value = json_spanned_value::from_str::<spanned::Value>(contents).map_err(|e| {
+    // serde_json only reports the position of an error as a (line, column) pair.
+    let line_start: usize = contents
+      .split_inclusive('\n')
+      .take(e.line().saturating_sub(1))
+      .map(str::len)
+      .sum();
+    let mut cause = e.to_string();
+    if let Some(position) = cause.rfind(" at line ") {
+      cause.truncate(position);
+    }
+    error("JSON", cause, line_start + e.column().saturating_sub(1))
+  })?;
+
+  fn convert(value: &spanned::Value) -> Result<Option<Value>, ParseError> {
+    use json_spanned_value::Value as Json;
+    let span = value.range();
+    Ok(Some(match value.get_ref() {
+      Json::Null => return Ok(None),
+      Json::Bool(b) => Value::scalar(*b, span),
+      Json::Number(n) => match n.as_i64() {
+        Some(n) => Value::scalar(n, span),
+        None => Value::scalar(n.as_f64().unwrap_or(f64::NAN), span),
+      },
+      Json::String(s) => Value {
+        kind: Kind::String(s.clone(), vec![(0, span.start + 1)]),
+        span,
+      },
+      Json::Array(items) => {
+        let items = items
+          .iter()
+          .map(|item| {
+            convert(item)?.ok_or_else(|| error("JSON", "Arrays cannot contain null", item.start()))
+          })
+          .collect::<Result<_, _>>()?;
+        Value::array(items, span)
+      }
+      Json::Object(entries) => {
+        // Objects are sorted by key, so put the entries back in the order they were written.
+        let mut entries = entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(key, _)| key.start());
+        let mut table = Table::new();
+        for (key, value) in entries {
+          // A null field is the same as a missing one.
+          if let Some(value) = convert(value)? {
+            table.push((key.get_ref().clone(), value));
+          }
+        }
+        Value::table(table, span)
+      }
+    }))
+  }
+
+  match convert(&value)? {
+    Some(Value {
+      kind: Kind::Table(table),
+      ..
+    }) => Ok(table),
+    _ => Err(error(
+      "JSON",
+      "A JSON quiz must be an object",
+      value.start(),
+    )),
+  }
+}
+
+/// The value of a plain (i.e. unquoted) YAML scalar under the YAML 1.2 core schema.
+fn plain_scalar(s: &str) -> Option<Kind> {
+  let scalar = match s {
+    "" | "~" | "null" | "Null" | "NULL" => return None,
+    "true" | "True" | "TRUE" => toml::Value::Boolean(true),
+    "false" | "False" | "FALSE" => toml::Value::Boolean(false),
+    ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => toml::Value::Float(f64::INFINITY),
+    "-.inf" | "-.Inf" | "-.INF" => toml::Value::Float(f64::NEG_INFINITY),
+    ".nan" | ".NaN" | ".NAN" => toml::Value::Float(f64::NAN),
+    _ => {
+      let radix = [("0x", 16), ("0o", 8)]
+        .into_iter()
+        .find_map(|(prefix, radix)| Some((s.strip_prefix(prefix)?, radix)));
+      let is_float = s.chars().any(|c| c.is_ascii_digit())
+        && s
+          .chars()
+          .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+      if let Some(Ok(n)) = radix.map(|(digits, radix)| i64::from_str_radix(digits, radix)) {
+        toml::Value::Integer(n)
+      } else if let Ok(n) = s.parse::<i64>() {
+        toml::Value::Integer(n)
+      } else if let (true, Ok(f)) = (is_float, s.parse::<f64>()) {
+        toml::Value::Float(f)
+      } else {
+        return Some(Kind::String(s.to_string(), Vec::new()));
+      }
+    }
+  };
+  Some(Kind::Scalar(scalar))
+}
+
+/// Reads a YAML quiz into a table from the events of a YAML parser.
+struct YamlReader<'a> {
+  source: &'a str,
+  parser: Parser<Chars<'a>>,
+}
+
+impl<'a> YamlReader<'a> {
+  fn new(source: &'a str) -> Self {
+    YamlReader {
+      source,
+      parser: Parser::new_from_str(source),
+    }
+  }
+
+  /// Returns the next event and the offset it starts at.
+  fn next(&mut self) -> Result<(Event, usize), ParseError> {
+    let (event, marker) = self.parser.next_token().map_err(|e| {
+      let offset = e.marker().index().min(self.source.len());
+      error("YAML", e.info(), offset)
+    })?;
+    Ok((event, marker.index().min(self.source.len())))
+  }
+
+  /// Returns the offset that the next event starts at.
+  fn peek_offset(&mut self) -> usize {
+    match self.parser.peek() {
+      Ok((_, marker)) => marker.index().min(self.source.len()),
+      Err(_) => self.source.len(),
+    }
+  }
+
+  fn quiz(mut self) -> Result<Table, ParseError> {
+    let mut quiz = None;
+    loop {
+      match self.next()? {
+        (Event::StreamEnd, _) => break,
+        (Event::StreamStart | Event::DocumentStart | Event::DocumentEnd, _) => {}
+        (_, offset) if quiz.is_some() => {
+          return Err(error(
+            "YAML",
+            "A YAML quiz must be a single document",
+            offset,
+          ))
+        }
+        (event, offset) => quiz = Some((self.node(event, offset)?, offset)),
+      }
+    }
+
+    match quiz {
+      None => Ok(Table::new()),
+      Some((
+        Some(Value {
+          kind: Kind::Table(table),
+          ..
+        }),
+        _,
+      )) => Ok(table),
+      Some((_, offset)) => Err(error("YAML", "A YAML quiz must be a mapping", offset)),
+    }
+  }
+
+  /// Reads the node starting with `event`, or returns `None` if it is null.
+  fn node(&mut self, event: Event, offset: usize) -> Result<Option<Value>, ParseError> {
+    let unsupported = |feature: &str| {
+      Err(error(
+        "YAML",
+        format!("{feature} are not supported in quizzes"),
+        offset,
+      ))
+    };
+    Ok(Some(match event {
+      Event::Alias(_) => return unsupported("Aliases"),
+      Event::Scalar(_, _, anchor, _)
+      | Event::SequenceStart(anchor, _)
+      | Event::MappingStart(anchor, _)
+        if anchor != 0 =>
+      {
+        return unsupported("Anchors")
+      }
+      Event::Scalar(_, _, _, Some(_))
+      | Event::SequenceStart(_, Some(_))
+      | Event::MappingStart(_, Some(_)) => return unsupported("Tags"),
+      Event::Scalar(s, style, _, _) => return Ok(self.scalar(s, style, offset)),
+      Event::SequenceStart(..) => {
+        let mut items = Vec::new();
+        let end = loop {
+          match self.next()? {
+            (Event::SequenceEnd, end) => break self.container_end(end, ']', items.last()),
+            (event, offset) => match self.node(event, offset)? {
+              Some(item) => items.push(item),
+              None => return Err(error("YAML", "Sequences cannot contain null", offset)),
+            },
+          }
+        };
+        let start = items
+          .first()
+          .map_or(offset, |item| item.span.start.min(offset));
+        Value::array(items, start..end.max(start))
+      }
+      Event::MappingStart(..) => {
+        let mut table = Table::new();
+        let mut start = offset;
+        let end = loop {
+          let (key, key_start) = match self.next()? {
+            (Event::MappingEnd, end) => {
+              break self.container_end(end, '}', table.last().map(|(_, value)| value))
+            }
+            (Event::Scalar(key, ..), key_start) => (key, key_start),
+            (_, key_start) => return Err(error("YAML", "Keys must be strings", key_start)),
+          };
+          start = start.min(key_start);
+          let (event, offset) = self.next()?;
+          // A null field is the same as a missing one.
+          if let Some(value) = self.node(event, offset)? {
+            push("YAML", &mut table, key, key_start, value)?;
+          }
+        };
+        Value::table(table, start..end.max(start))
+      }
+      Event::Nothing
+      | Event::StreamStart
+      | Event::StreamEnd
+      | Event::DocumentStart
+      | Event::DocumentEnd
+      | Event::SequenceEnd
+      | Event::MappingEnd => unreachable!("not the start of a node"),
+    }))
+  }
+
+  /// Returns the end of a sequence or mapping, given the offset of its end event.
+  fn container_end(&self, offset: usize, close: char, last: Option<&Value>) -> usize {
+    if self.source[offset..].starts_with(close) {
+      offset + 1
+    } else {
+      last.map_or(offset, |value| value.span.end)
+    }
+  }
+
+  fn scalar(&mut self, s: String, style: TScalarStyle, start: usize) -> Option<Value> {
+    let rest = &self.source[start..];
+    match style {
+      TScalarStyle::Plain => {
+        let end = match rest.starts_with(&s) {
+          true => start + s.len(),
+          false => self.trimmed_end(start),
+        };
+        match plain_scalar(&s)? {
+          Kind::String(s, _) => Some(Value::text(self.source, start, start..end, s)),
+          kind => Some(Value {
+            kind,
+            span: start..end,
+          }),
+        }
+      }
+      TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted => {
+        let quote = if style == TScalarStyle::SingleQuoted {
+          '\''
+        } else {
+          '"'
+        };
+        let mut chars = rest.char_indices().skip(1).peekable();
+        let mut end = self.trimmed_end(start);
+        while let Some((i, c)) = chars.next() {
+          if c == quote && !(quote == '\'' && chars.next_if(|(_, c)| *c == '\'').is_some()) {
+            end = start + i + 1;
+            break;
+          } else if c == '\\' && quote == '"' {
+            chars.next();
+          }
+        }
+        Some(Value {
+          kind: Kind::String(s, vec![(0, start + 1)]),
+          span: start..end,
+        })
+      }
+      TScalarStyle::Literal | TScalarStyle::Folded => {
+        let end = self.trimmed_end(start);
+        Some(Value::text(self.source, start, start..end, s))
+      }
+    }
+  }
+
+  /// Returns the end of a value starting at `start` when all that is known is that it ends
+  /// before the next event.
+  fn trimmed_end(&mut self, start: usize) -> usize {
+    let next = self.peek_offset().max(start);
+    let text = self.source[start..next].trim_end_matches(|c: char| c.is_whitespace() || c == ',');
+    start + text.len()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{diagnose, IdSet, ValidationConfig};
+  use mdbook_quiz_schema::Question;
+
+  const JSON_QUIZ: &str = r#"{
+  "questions": [
+    {
+      "type": "ShortAnswer",
+      "id": "first",
+      "prompt": { "prompt": "What is the keyword for declaring a variable?" },
+      "answer": { "answer": "let", "alternatives": ["let mut"] },
+      "context": null
+    },
+    {
+      "type": "Numeric",
+      "prompt": { "prompt": "What is 1 + 1?" },
+      "answer": { "value": 2, "tolerance": 0.5 }
+    }
+  ]
+}
+"#;
+
+  const YAML_QUIZ: &str = r#"# A comment
+questions:
+  - type: ShortAnswer
+    id: first
+    prompt:
+      prompt: "What is the keyword for declaring a variable?"
+    answer:
+      answer: let
+      alternatives: ['let mut']
+    context:
+  - type: Numeric
+    prompt:
+      prompt: |
+        What is
+        1 + 1?
+    answer: {value: 2, tolerance: 0.5}
+"#;
+
+  #[test]
+  fn test_format_from_path() {
+    assert_eq!(QuizFormat::from_path(Path::new("a.toml")), QuizFormat::Toml);
+    assert_eq!(
+      QuizFormat::from_path(Path::new("a.quiz.md")),
+      QuizFormat::Markdown
+    );
+    assert_eq!(QuizFormat::from_path(Path::new("a.json")), QuizFormat::Json);
+    assert_eq!(QuizFormat::from_path(Path::new("a.yaml")), QuizFormat::Yaml);
+    assert_eq!(QuizFormat::from_path(Path::new("a.yml")), QuizFormat::Yaml);
+  }
+
+  #[test]
+  fn test_convert_json_and_yaml() -> anyhow::Result<()> {
+    for (format, contents) in [(QuizFormat::Json, JSON_QUIZ), (QuizFormat::Yaml, YAML_QUIZ)] {
+      let (quiz, _) = crate::parse_quiz(&format.to_toml(contents)?)?;
+      assert_eq!(quiz.questions.len(), 2);
+      let Question::ShortAnswer(mdbook_quiz_schema::ShortAnswer(question)) = &quiz.questions[0]
+      else {
+        panic!(
+          "expected a ShortAnswer question, got {:?}",
+          quiz.questions[0]
+        );
+      };
+      assert_eq!(question.id.as_deref(), Some("first"));
+      assert_eq!(question.answer.answer, "let");
+      assert_eq!(
+        question.answer.alternatives.as_deref(),
+        Some(&["let mut".to_string()][..])
+      );
+      assert!(question.context.is_none());
+      assert!(matches!(quiz.questions[1], Question::Numeric(_)));
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn test_plain_scalars() {
+    let scalar = |s| match plain_scalar(s) {
+      Some(Kind::Scalar(value)) => Some(value),
+      Some(Kind::String(s, _)) => Some(toml::Value::String(s)),
+      _ => None,
+    };
+    assert_eq!(scalar("~"), None);
+    assert_eq!(scalar("True"), Some(toml::Value::Boolean(true)));
+    assert_eq!(scalar("-12"), Some(toml::Value::Integer(-12)));
+    assert_eq!(scalar("0x1F"), Some(toml::Value::Integer(31)));
+    assert_eq!(scalar("1.5e3"), Some(toml::Value::Float(1500.)));
+    assert_eq!(scalar("nan"), Some(toml::Value::String("nan".into())));
+    assert_eq!(scalar("1.2.3"), Some(toml::Value::String("1.2.3".into())));
+  }
+
+  #[test]
+  fn validate_json_and_yaml_spans() {
+    let config = ValidationConfig::default();
+    let json = JSON_QUIZ.replace("\"value\": 2", "\"range\": [3, 1]");
+    let yaml = YAML_QUIZ.replace("value: 2", "range: [3, 1]");
+    for (path, contents) in [("quiz.json", json.as_str()), ("quiz.yaml", yaml.as_str())] {
+      let report = diagnose(Path::new(path), contents, &IdSet::default(), &config);
+      let [diagnostic] = report.diagnostics() else {
+        panic!("expected one diagnostic, got {report:?}")
+      };
+      assert_eq!(diagnostic.rule.as_deref(), Some("numeric::range"));
+      let span = diagnostic.span.as_ref().unwrap();
+      assert_eq!(span.offset, contents.find("[3, 1]").unwrap());
+      assert_eq!(span.length, "[3, 1]".len());
+    }
+  }
+
+  #[test]
+  fn test_insert_ids() -> anyhow::Result<()> {
+    let ids = ["a".to_string(), "b".to_string()];
+    let json = QuizFormat::Json.insert_ids(JSON_QUIZ, &ids, None)?.unwrap();
+    assert!(json.contains("    {\n      \"id\": \"b\",\n      \"type\": \"Numeric\""));
+    let yaml = QuizFormat::Yaml.insert_ids(YAML_QUIZ, &ids, None)?.unwrap();
+    assert!(yaml.contains("  - id: \"b\"\n    type: Numeric"));
+    for (format, contents) in [(QuizFormat::Json, json), (QuizFormat::Yaml, yaml)] {
+      let (quiz, _) = crate::parse_quiz(&format.to_toml(&contents)?)?;
+      let ids = quiz.questions.iter().map(|q| q.id().map(String::from));
+      assert_eq!(
+        ids.collect::<Vec<_>>(),
+        [Some("first".into()), Some("b".into())]
+      );
+      assert_eq!(
+        format.insert_ids(&contents, &["c".into(), "d".into()], None)?,
+        None
+      );
+    }
+
+    let flow = QuizFormat::Yaml.insert_ids("questions: [{type: Numeric}, {}]", &ids, None)?;
+    assert_eq!(
+      flow.as_deref(),
+      Some("questions: [{id: \"a\", type: Numeric}, {id: \"b\"}]")
+    );
+
+    let fingerprints = ["0a".to_string(), "1b".to_string()];
+    let json = QuizFormat::Json.insert_ids(JSON_QUIZ, &ids, Some(&fingerprints))?;
+    assert!(json.unwrap().contains(
+      "    {\n      \"id\": \"b\",\n      \"fingerprint\": \"1b\",\n      \"type\": \"Numeric\""
+    ));
+    let yaml = QuizFormat::Yaml.insert_ids(YAML_QUIZ, &ids, Some(&fingerprints))?;
+    assert!(yaml
+      .unwrap()
+      .contains("  - id: \"b\"\n    fingerprint: \"1b\"\n    type: Numeric"));
+    let flow = "questions: [{type: Numeric}]";
+    assert_eq!(
+      QuizFormat::Yaml
+        .insert_ids(flow, &ids, Some(&fingerprints))?
+        .as_deref(),
+      Some("questions: [{id: \"a\", fingerprint: \"0a\", type: Numeric}]")
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_json_and_yaml_errors() {
+    let cases = [
+      ("quiz.json", "{\"questions\": [1,]}", "JSON parse error"),
+      ("quiz.json", "[]", "must be an object"),
+      (
+        "quiz.json",
+        "{\"questions\": [null]}",
+        "cannot contain null",
+      ),
+      ("quiz.yaml", "questions: [1", "YAML parse error"),
+      ("quiz.yaml", "- 1", "must be a mapping"),
+      ("quiz.yaml", "a: 1\na: 2", "given more than once"),
+      ("quiz.yaml", "a: &x 1\nb: *x", "not supported"),
+      ("quiz.yaml", "a: 1\n---\nb: 2", "single document"),
+    ];
+    for (path, contents, expected) in cases {
+      let report = diagnose(
+        Path::new(path),
+        contents,
+        &IdSet::default(),
+        &ValidationConfig::default(),
+      );
+      let [diagnostic] = report.diagnostics() else {
+        panic!("expected one diagnostic for {contents:?}, got {report:?}")
+      };
+      assert!(
+        diagnostic.message.contains(expected),
+        "expected {expected:?} in {:?}",
+        diagnostic.message
+      );
+    }
+  }
+}
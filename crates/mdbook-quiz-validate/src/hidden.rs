@@ -0,0 +1,75 @@
+//! Hidden lines in programs, written like in rustdoc: a line starting with `# ` (or just `#`)
+//! is compiled but not shown, and a line starting with `##` is shown with a single `#`.
+
+/// Parses a line of a program into its source and whether it is hidden.
+fn parse_line(line: &str) -> (String, bool) {
+  let trimmed = line.trim_start();
+  let indent = &line[..line.len() - trimmed.len()];
+  if trimmed == "#" {
+    (String::new(), true)
+  } else if let Some(rest) = trimmed.strip_prefix("# ") {
+    (format!("{indent}{rest}"), true)
+  } else if let Some(rest) = trimmed.strip_prefix("##") {
+    (format!("{indent}#{rest}"), false)
+  } else {
+    (line.to_string(), false)
+  }
+}
+
+/// Joins `lines` into a program, ending with a newline if `program` does.
+fn join(program: &str, lines: impl Iterator<Item = String>) -> String {
+  let mut joined = lines.collect::<Vec<_>>().join("\n");
+  if program.ends_with('\n') {
+    joined.push('\n');
+  }
+  joined
+}
+
+/// Returns the program shown to readers, without its hidden lines.
+pub fn visible_program(program: &str) -> String {
+  let lines = program.lines().map(parse_line);
+  join(
+    program,
+    lines.filter(|(_, hidden)| !hidden).map(|(line, _)| line),
+  )
+}
+
+/// Returns the program that is compiled, including its hidden lines.
+pub(crate) fn full_program(program: &str) -> String {
+  join(program, program.lines().map(|line| parse_line(line).0))
+}
+
+/// Converts a 1-based line number in the [`full_program`] into a line number in the
+/// [`visible_program`], or `None` if the line is hidden.
+pub(crate) fn visible_line(program: &str, line: usize) -> Option<usize> {
+  let lines = program.lines().map(parse_line).collect::<Vec<_>>();
+  let (_, hidden) = lines.get(line.checked_sub(1)?)?;
+  if *hidden {
+    return None;
+  }
+  Some(lines[..line].iter().filter(|(_, hidden)| !hidden).count())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_hidden_lines() {
+    let program =
+      "# use std::fmt;\n#![allow(unused)]\nfn main() {\n  # let x = 1;\n  ## not a comment\n}\n";
+    assert_eq!(
+      visible_program(program),
+      "#![allow(unused)]\nfn main() {\n  # not a comment\n}\n"
+    );
+    assert_eq!(
+      full_program(program),
+      "use std::fmt;\n#![allow(unused)]\nfn main() {\n  let x = 1;\n  # not a comment\n}\n"
+    );
+    assert_eq!(visible_line(program, 1), None);
+    assert_eq!(visible_line(program, 3), Some(2));
+    assert_eq!(visible_line(program, 4), None);
+    assert_eq!(visible_line(program, 6), Some(4));
+    assert_eq!(visible_line(program, 7), None);
+  }
+}
@@ -0,0 +1,312 @@
+//! Strategies for generating question IDs.
+
+use std::{collections::HashMap, fmt, path::Path, str::FromStr};
+
+use mdbook_quiz_schema::Quiz;
+use uuid::Uuid;
+
+/// Namespace for IDs generated by [`IdStrategy::UuidV5Content`].
+const CONTENT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x5c1d_52b1_5d6e_4d2c_9e0b_6c4f_0a8b_a5e3);
+
+/// How to generate an ID for a question that does not have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdStrategy {
+  /// A random UUID.
+  #[default]
+  UuidV4,
+
+  /// A UUID derived from the quiz path and the question's prompt, so that the same
+  /// question gets the same ID when it is added independently in two branches.
+  UuidV5Content,
+
+  /// The quiz path and the index of the question, e.g. `quizzes/ch01.toml#3`.
+  PathIndex,
+}
+
+impl IdStrategy {
+  /// Generates an ID for every question in `quiz`, whether or not it already has one.
+  ///
+  /// `quiz_key` identifies the quiz within its book, see [`quiz_key`].
+  pub fn generate(self, quiz_key: &str, quiz: &Quiz) -> Vec<String> {
+    match self {
+      IdStrategy::UuidV4 => quiz
+        .questions
+        .iter()
+        .map(|_| Uuid::new_v4().to_string())
+        .collect(),
+      IdStrategy::UuidV5Content => content_ids(quiz_key, quiz)
+        .into_iter()
+        .map(|id| id.to_string())
+        .collect(),
+      IdStrategy::PathIndex => (0..quiz.questions.len())
+        .map(|i| format!("{quiz_key}#{i}"))
+        .collect(),
+    }
+  }
+
+  /// Generates a fingerprint to store next to the ID of every question in `quiz`, if the
+  /// strategy derives IDs from content, see [`content_fingerprint`].
+  pub fn fingerprints(self, quiz: &Quiz) -> Option<Vec<String>> {
+    (self == IdStrategy::UuidV5Content)
+      .then(|| quiz.questions.iter().map(content_fingerprint).collect())
+  }
+
+  /// Returns true if the strategy generates the same IDs for the same quiz every time.
+  pub fn is_deterministic(self) -> bool {
+    !matches!(self, IdStrategy::UuidV4)
+  }
+}
+
+impl FromStr for IdStrategy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "uuid-v4" => Ok(IdStrategy::UuidV4),
+      "uuid-v5-content" => Ok(IdStrategy::UuidV5Content),
+      "path-index" => Ok(IdStrategy::PathIndex),
+      _ => Err(format!(
+        "Invalid ID strategy: `{s}`. Expected `uuid-v4`, `uuid-v5-content`, or `path-index`."
+      )),
+    }
+  }
+}
+
+impl fmt::Display for IdStrategy {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      IdStrategy::UuidV4 => "uuid-v4",
+      IdStrategy::UuidV5Content => "uuid-v5-content",
+      IdStrategy::PathIndex => "path-index",
+    })
+  }
+}
+
+/// Identifies the quiz at `quiz_path` by its path relative to `book_root`, using `/` separators.
+///
+/// Falls back to the path as given if it is not inside the book.
+pub fn quiz_key(book_root: &Path, quiz_path: &Path) -> String {
+  let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_owned());
+  let quiz_path = canonical(quiz_path);
+  let rel_path = quiz_path
+    .strip_prefix(canonical(book_root))
+    .unwrap_or(&quiz_path);
+  rel_path.to_string_lossy().replace('\\', "/")
+}
+
+/// Reduces a question's prompt to its words, so that changes to formatting, punctuation,
+/// or capitalization don't count as changes to its content.
+fn normalized_prompt(question: &mdbook_quiz_schema::Question) -> String {
+  fn collect_strings(value: &toml::Value, strings: &mut Vec<String>) {
+    match value {
+      toml::Value::String(s) => strings.push(s.clone()),
+      toml::Value::Array(values) => values.iter().for_each(|v| collect_strings(v, strings)),
+      toml::Value::Table(table) => table.values().for_each(|v| collect_strings(v, strings)),
+      _ => {}
+    }
+  }
+
+  let mut strings = Vec::new();
+  let value = toml::Value::try_from(question).expect("questions are serializable");
+  collect_strings(&value["type"], &mut strings);
+  if let Some(prompt) = value.get("prompt") {
+    collect_strings(prompt, &mut strings);
+  }
+
+  let text = strings.join(" ").to_lowercase();
+  text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|word| !word.is_empty())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// The IDs generated by [`IdStrategy::UuidV5Content`] for each question in `quiz`.
+///
+/// Questions with identical prompts are disambiguated by their order in the quiz.
+pub(crate) fn content_ids(quiz_key: &str, quiz: &Quiz) -> Vec<Uuid> {
+  let mut occurrences = HashMap::new();
+  quiz
+    .questions
+    .iter()
+    .map(|question| {
+      let prompt = normalized_prompt(question);
+      let count = occurrences.entry(prompt.clone()).or_insert(0);
+      let mut name = format!("{quiz_key}\n{prompt}");
+      if *count > 0 {
+        name.push_str(&format!("\n{count}"));
+      }
+      *count += 1;
+      Uuid::new_v5(&CONTENT_ID_NAMESPACE, name.as_bytes())
+    })
+    .collect()
+}
+
+/// Number of hex digits in a fingerprint, each the low 4 bits of one MinHash.
+const FINGERPRINT_LEN: usize = 32;
+
+/// Estimated similarity below which a question's prompt counts as rewritten.
+const STALE_SIMILARITY: f64 = 0.5;
+
+/// The 64-bit FNV-1a hash of `bytes`, which is stable across platforms and releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+    (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+  })
+}
+
+/// Scrambles `x` with the SplitMix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+  x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  x ^ (x >> 31)
+}
+
+/// A MinHash of the words and pairs of adjacent words of a question's prompt, so that the
+/// similarity of two prompts can be estimated from their fingerprints.
+pub fn content_fingerprint(question: &mdbook_quiz_schema::Question) -> String {
+  let prompt = normalized_prompt(question);
+  let words = prompt
+    .split(' ')
+    .filter(|word| !word.is_empty())
+    .collect::<Vec<_>>();
+  let shingles = words
+    .iter()
+    .map(|word| fnv1a(word.as_bytes()))
+    .chain(
+      words
+        .windows(2)
+        .map(|pair| fnv1a(pair.join(" ").as_bytes())),
+    )
+    .collect::<Vec<_>>();
+  (0..FINGERPRINT_LEN as u64)
+    .map(|i| {
+      let seed = mix(i.wrapping_add(1));
+      let min = shingles.iter().map(|hash| mix(hash ^ seed)).min();
+      let nibble = min.unwrap_or(u64::MAX) & 0xf;
+      char::from_digit(nibble as u32, 16).unwrap()
+    })
+    .collect()
+}
+
+/// Returns true if `question` seems to ask something different from the question with
+/// `fingerprint`, or `None` if `fingerprint` is not a fingerprint.
+pub(crate) fn is_rewritten(
+  question: &mdbook_quiz_schema::Question,
+  fingerprint: &str,
+) -> Option<bool> {
+  let is_fingerprint = fingerprint.len() == FINGERPRINT_LEN
+    && fingerprint
+      .chars()
+      .all(|c| matches!(c, '0'..='9' | 'a'..='f'));
+  if !is_fingerprint {
+    return None;
+  }
+
+  let current = content_fingerprint(question);
+  let matches = (current.chars().zip(fingerprint.chars()))
+    .filter(|(a, b)| a == b)
+    .count();
+
+  // Unrelated prompts still agree on 1 in 16 digits by chance.
+  let agreement = matches as f64 / FINGERPRINT_LEN as f64;
+  let similarity = (agreement - 1. / 16.) / (15. / 16.);
+  Some(similarity < STALE_SIMILARITY)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn parse(contents: &str) -> Quiz {
+    toml::from_str(contents).unwrap()
+  }
+
+  #[test]
+  fn content_ids_ignore_formatting() {
+    let a = parse(
+      r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "What is the keyword for declaring a variable?"
+answer.answer = "let"
+"#,
+    );
+    let b = parse(
+      r#"
+[[questions]]
+id = "ignored"
+type = "ShortAnswer"
+prompt.prompt = """
+What is the **keyword** for declaring a  variable
+"""
+answer.answer = "const"
+"#,
+    );
+    let c = parse(
+      r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "What is the keyword for declaring a constant?"
+answer.answer = "const"
+"#,
+    );
+    let strategy = IdStrategy::UuidV5Content;
+    assert_eq!(
+      strategy.generate("a.toml", &a),
+      strategy.generate("a.toml", &b)
+    );
+    assert_ne!(
+      strategy.generate("a.toml", &a),
+      strategy.generate("b.toml", &a)
+    );
+    assert_ne!(
+      strategy.generate("a.toml", &a),
+      strategy.generate("a.toml", &c)
+    );
+  }
+
+  #[test]
+  fn content_ids_are_unique() {
+    let quiz = parse(
+      r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "Same"
+answer.answer = "a"
+
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "Same"
+answer.answer = "b"
+"#,
+    );
+    let ids = IdStrategy::UuidV5Content.generate("a.toml", &quiz);
+    assert_ne!(ids[0], ids[1]);
+
+    let ids = IdStrategy::PathIndex.generate("a.toml", &quiz);
+    assert_eq!(ids, ["a.toml#0", "a.toml#1"]);
+  }
+
+  #[test]
+  fn fingerprints_tolerate_small_edits() {
+    let question = |prompt: &str| {
+      let quiz = parse(&format!(
+        "[[questions]]\ntype = \"ShortAnswer\"\nprompt.prompt = \"{prompt}\"\nanswer.answer = \"\"\n"
+      ));
+      quiz.questions.into_iter().next().unwrap()
+    };
+    let original = question("Which keyword declares a variable that can be reassigned later?");
+    let fingerprint = content_fingerprint(&original);
+    assert_eq!(fingerprint.len(), FINGERPRINT_LEN);
+    assert_eq!(is_rewritten(&original, &fingerprint), Some(false));
+
+    let typo = question("Which keyword declares a variable that can be re-assigned later?");
+    assert_eq!(is_rewritten(&typo, &fingerprint), Some(false));
+
+    let rewritten = question("What does the borrow checker prevent?");
+    assert_eq!(is_rewritten(&rewritten, &fingerprint), Some(true));
+
+    assert_eq!(is_rewritten(&original, "not a fingerprint"), None);
+  }
+}
@@ -0,0 +1,216 @@
+use std::process::Command;
+
+use miette::miette;
+use tempfile::TempDir;
+
+use super::tracing::compile;
+use crate::{
+  cxensure,
+  limits::{self, Status},
+  tomlcast, ResourceLimits, RustSettings, SpannedValue, SpannedValueExt, Validate,
+  ValidationContext,
+};
+use mdbook_quiz_schema::*;
+
+/// The result of running the tests of a [`CodeExercise`] against a program.
+enum TestOutcome {
+  /// The program and tests do not compile, with rustc's stderr.
+  DoesNotCompile(String),
+
+  /// At least one test failed, with the test harness' stdout.
+  Failed(String),
+
+  /// Every test passed, and there were this many tests.
+  Passed(usize),
+}
+
+/// Compiles `program` together with `tests` as a test module, and runs the tests.
+fn run_tests(
+  program: &str,
+  tests: &str,
+  settings: &RustSettings,
+  limits: &ResourceLimits,
+) -> anyhow::Result<TestOutcome> {
+  let dir = TempDir::new()?;
+  let program = format!("{program}\n\n#[cfg(test)]\nmod tests {{\nuse super::*;\n\n{tests}\n}}\n");
+  let rustc_output = compile(dir.path(), &program, &["--test"], settings)?;
+  if !rustc_output.status.success() {
+    let rustc_stderr = String::from_utf8(rustc_output.stderr)?;
+    return Ok(TestOutcome::DoesNotCompile(rustc_stderr));
+  }
+
+  let mut cmd = Command::new(dir.path().join("main"));
+  cmd.current_dir(dir.path());
+  let test_output = limits::run(cmd, limits)?;
+  let test_stdout = test_output.stdout;
+  match test_output.status {
+    Status::Success => {}
+    Status::Failure => return Ok(TestOutcome::Failed(test_stdout)),
+    Status::TimedOut => {
+      return Ok(TestOutcome::Failed(format!(
+        "{test_stdout}\ntests timed out after {:?}",
+        limits.timeout
+      )))
+    }
+    Status::OutputLimit => {
+      return Ok(TestOutcome::Failed(format!(
+        "{test_stdout}\ntests wrote more than {} bytes of output",
+        limits.max_output
+      )))
+    }
+  }
+
+  // The test harness starts by printing e.g. "running 3 tests".
+  let num_tests = test_stdout
+    .lines()
+    .find_map(|line| {
+      line
+        .strip_prefix("running ")?
+        .split(' ')
+        .next()?
+        .parse()
+        .ok()
+    })
+    .unwrap_or(0);
+  Ok(TestOutcome::Passed(num_tests))
+}
+
+impl Validate for CodeExercise {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.0.validate(cx, value);
+
+    let QuestionFields { prompt, answer, .. } = &self.0;
+    let answer_val = tomlcast!(value.table["answer"]);
+    let solution_val = tomlcast!(answer_val.table["solution"]);
+    let tests_val = tomlcast!(answer_val.table["tests"]);
+    let starter_val = tomlcast!(value.table["prompt"].table["starter"]);
+
+    let settings = cx.config.rust.clone();
+    let limits = cx.config.limits.clone();
+    match run_tests(&answer.solution, &answer.tests, &settings, &limits) {
+      Ok(TestOutcome::DoesNotCompile(rustc_stderr)) => cx.error(miette!(
+        code = "code_exercise::solution_fails",
+        labels = vec![solution_val.labeled_span()],
+        "solution does not compile with the tests. rustc stderr:\n{}",
+        textwrap::indent(&rustc_stderr, "  ")
+      )),
+      Ok(TestOutcome::Failed(test_stdout)) => cx.error(miette!(
+        code = "code_exercise::solution_fails",
+        labels = vec![solution_val.labeled_span()],
+        "solution does not pass the tests. test output:\n{}",
+        textwrap::indent(&test_stdout, "  ")
+      )),
+      Ok(TestOutcome::Passed(num_tests)) => cxensure!(
+        cx,
+        num_tests > 0,
+        code = "code_exercise::no_tests",
+        labels = vec![tests_val.labeled_span()],
+        "tests do not contain any #[test] functions"
+      ),
+      Err(e) => cx.error(miette!(
+        code = "code_exercise::solution_fails",
+        labels = vec![solution_val.labeled_span()],
+        "failed to run tests: {e}"
+      )),
+    }
+
+    match run_tests(&prompt.starter, &answer.tests, &settings, &limits) {
+      Ok(outcome) => cxensure!(
+        cx,
+        !matches!(outcome, TestOutcome::Passed(n) if n > 0),
+        code = "code_exercise::starter_passes",
+        labels = vec![starter_val.labeled_span()],
+        "starter program already passes the tests"
+      ),
+      Err(e) => cx.error(miette!(
+        code = "code_exercise::starter_passes",
+        labels = vec![starter_val.labeled_span()],
+        "failed to run tests: {e}"
+      )),
+    }
+  }
+}
+
+impl Validate for CodeExercisePrompt {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.prompt.validate(cx, tomlcast!(value.table["prompt"]));
+  }
+}
+
+impl Validate for CodeExerciseAnswer {
+  fn validate(&self, _cx: &mut ValidationContext, _value: &SpannedValue) {}
+}
+
+#[test]
+fn validate_code_exercise_passes() {
+  let contents = r#"
+[[questions]]
+type = "CodeExercise"
+prompt.prompt = "Implement `add`."
+prompt.starter = """
+fn add(a: i32, b: i32) -> i32 {
+  todo!()
+}
+"""
+answer.solution = """
+fn add(a: i32, b: i32) -> i32 {
+  a + b
+}
+"""
+answer.tests = """
+#[test]
+fn test_add() {
+  assert_eq!(add(1, 2), 3);
+}
+"""
+"#;
+  assert!(crate::harness(contents).is_ok());
+}
+
+#[test]
+fn validate_code_exercise_solution_fails() {
+  let contents = r#"
+[[questions]]
+type = "CodeExercise"
+prompt.prompt = ""
+prompt.starter = ""
+answer.solution = """
+fn add(a: i32, b: i32) -> i32 {
+  a - b
+}
+"""
+answer.tests = """
+#[test]
+fn test_add() {
+  assert_eq!(add(1, 2), 3);
+}
+"""
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_code_exercise_starter_passes() {
+  let contents = r#"
+[[questions]]
+type = "CodeExercise"
+prompt.prompt = ""
+prompt.starter = """
+fn add(a: i32, b: i32) -> i32 {
+  3
+}
+"""
+answer.solution = """
+fn add(a: i32, b: i32) -> i32 {
+  a + b
+}
+"""
+answer.tests = """
+#[test]
+fn test_add() {
+  assert_eq!(add(1, 2), 3);
+}
+"""
+"#;
+  assert!(crate::harness(contents).is_err());
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+
+use regex::Regex;
+
+use crate::{cxensure, tomlcast, SpannedValue, SpannedValueExt, Validate, ValidationContext};
+use mdbook_quiz_schema::*;
+
+/// Returns the number of each blank in `text`, e.g. `1` for `{{1}}`.
+fn blanks(text: &str) -> BTreeSet<String> {
+  let pattern = Regex::new(r"\{\{(\d+)\}\}").unwrap();
+  pattern
+    .captures_iter(text)
+    .map(|cap| cap[1].to_string())
+    .collect()
+}
+
+impl Validate for FillInTheBlank {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.0.validate(cx, value);
+
+    let text_val = tomlcast!(value.table["prompt"].table["text"]);
+    let blanks_val = tomlcast!(value.table["answer"].table["blanks"]);
+    let blanks = blanks(&self.0.prompt.text);
+
+    cxensure!(
+      cx,
+      !blanks.is_empty(),
+      code = "fill_in_the_blank::no_blanks",
+      labels = vec![text_val.labeled_span()],
+      "Text must contain at least one blank, e.g. {{{{1}}}}"
+    );
+
+    for blank in &blanks {
+      cxensure!(
+        cx,
+        self.0.answer.blanks.contains_key(blank),
+        code = "fill_in_the_blank::missing_answer",
+        labels = vec![text_val.labeled_span()],
+        "Blank {{{{{blank}}}}} does not have an answer"
+      );
+    }
+
+    for key in self.0.answer.blanks.keys() {
+      cxensure!(
+        cx,
+        blanks.contains(key),
+        code = "fill_in_the_blank::unknown_blank",
+        labels = vec![blanks_val.get_ref().as_table().unwrap()[key.as_str()].labeled_span()],
+        "Text does not contain blank {{{{{key}}}}}"
+      );
+    }
+  }
+}
+
+impl Validate for FillInTheBlankPrompt {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.prompt.validate(cx, tomlcast!(value.table["prompt"]));
+
+    if !matches!(self.format, Some(FillInTheBlankFormat::Code)) {
+      Markdown(self.text.clone()).validate(cx, tomlcast!(value.table["text"]));
+    }
+  }
+}
+
+impl Validate for FillInTheBlankAnswer {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    let blanks_val = tomlcast!(value.table["blanks"].table);
+    for (key, answers) in &self.blanks {
+      let answers_val = &blanks_val[key.as_str()];
+      cxensure!(
+        cx,
+        !answers.is_empty(),
+        code = "fill_in_the_blank::no_answer",
+        labels = vec![answers_val.labeled_span()],
+        "Blank {{{{{key}}}}} must have at least one acceptable answer"
+      );
+      for (answer, answer_val) in answers.iter().zip(tomlcast!(answers_val.array)) {
+        answer.validate(cx, answer_val);
+      }
+    }
+  }
+}
+
+impl Validate for BlankAnswer {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    if let BlankAnswer::Pattern { regex } = self {
+      let result = Regex::new(&format!("^(?:{regex})$"));
+      cxensure!(
+        cx,
+        result.is_ok(),
+        code = "fill_in_the_blank::invalid_regex",
+        labels = vec![tomlcast!(value.table["regex"]).labeled_span()],
+        "Invalid regex: {}",
+        result.unwrap_err()
+      );
+    }
+  }
+}
+
+#[test]
+fn validate_fill_in_the_blank_passes() {
+  let contents = r#"
+[[questions]]
+type = "FillInTheBlank"
+prompt.prompt = "Complete the program so that it compiles."
+prompt.text = "let {{1}} x = {{2}};\nx += {{2}};"
+prompt.format = "code"
+answer.blanks.1 = ["mut"]
+answer.blanks.2 = ["1", { regex = "\\d+" }]
+"#;
+  assert!(crate::harness(contents).is_ok());
+}
+
+#[test]
+fn validate_fill_in_the_blank_missing_answer() {
+  let contents = r#"
+[[questions]]
+type = "FillInTheBlank"
+prompt.prompt = ""
+prompt.text = "The {{1}} keyword declares a {{2}}."
+answer.blanks.1 = ["let"]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_fill_in_the_blank_unknown_blank() {
+  let contents = r#"
+[[questions]]
+type = "FillInTheBlank"
+prompt.prompt = ""
+prompt.text = "The {{1}} keyword declares a variable."
+answer.blanks.1 = ["let"]
+answer.blanks.2 = ["variable"]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_fill_in_the_blank_invalid_regex() {
+  let contents = r#"
+[[questions]]
+type = "FillInTheBlank"
+prompt.prompt = ""
+prompt.text = "The {{1}} keyword declares a variable."
+answer.blanks.1 = [{ regex = "(let" }]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
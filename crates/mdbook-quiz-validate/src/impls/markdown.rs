@@ -1,11 +1,21 @@
-use crate::{SpannedValue, Validate, ValidationContext};
-use markdown::{mdast::Node, ParseOptions};
+use crate::{
+  limits::Status, spellcheck::Dictionaries, SpannedValue, SpannedValueExt, Validate,
+  ValidationContext,
+};
+use markdown::{
+  mdast::{Code, Node},
+  ParseOptions,
+};
 use mdbook_quiz_schema::*;
-use miette::{Diagnostic, SourceSpan};
+use miette::{miette, Diagnostic, LabeledSpan, SourceSpan};
+use std::{ops::Range, path::Path};
 use thiserror::Error;
 
+use super::tracing::{execute, rustc_errors};
+
 #[derive(Error, Diagnostic, Debug)]
 #[error("Spelling error: `{word}`")]
+#[diagnostic(code(markdown::spelling))]
 struct SpellingError {
   word: String,
 
@@ -13,6 +23,252 @@ struct SpellingError {
   span: SourceSpan,
 }
 
+/// The comment that disables the spellchecker for the rest of its paragraph, or for the next
+/// block if it is on its own line.
+const SPELLCHECK_IGNORE: &str = "<!-- spellcheck-ignore -->";
+
+/// Returns the ranges of `root`'s source that are skipped by the spellchecker because of
+/// [`SPELLCHECK_IGNORE`] comments.
+fn spellcheck_ignored_ranges(root: &Node) -> Vec<Range<usize>> {
+  let mut ranges = Vec::new();
+  let mut queue = vec![root];
+  while let Some(node) = queue.pop() {
+    let Some(children) = node.children() else {
+      continue;
+    };
+    queue.extend(children);
+
+    let is_block = matches!(
+      node,
+      Node::Root(_) | Node::BlockQuote(_) | Node::List(_) | Node::ListItem(_)
+    );
+    for (i, child) in children.iter().enumerate() {
+      let (Node::Html(html), Some(pos)) = (child, child.position()) else {
+        continue;
+      };
+      if html.value.trim() != SPELLCHECK_IGNORE {
+        continue;
+      }
+      let end = if is_block {
+        children.get(i + 1).and_then(|next| next.position())
+      } else {
+        node.position()
+      };
+      if let Some(end) = end {
+        ranges.push(pos.end.offset..end.end.offset);
+      }
+    }
+  }
+  ranges
+}
+
+/// How a Rust code block is tested, following rustdoc's attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CodeBlockMode {
+  /// The block is compiled and run, and must succeed.
+  Run,
+
+  /// `no_run`: the block is compiled but not run.
+  NoRun,
+
+  /// `should_panic`: the block is compiled and run, and must fail.
+  ShouldPanic,
+
+  /// `compile_fail`: the block must not compile.
+  CompileFail,
+}
+
+/// How to test a Rust code block, parsed from its info string.
+struct CodeBlockAttributes {
+  mode: CodeBlockMode,
+  edition: Option<String>,
+}
+
+impl CodeBlockAttributes {
+  /// Parses the info string of a code block like rustdoc, e.g. `rust,should_panic`.
+  ///
+  /// Returns `None` if the block is not Rust or is marked `ignore`.
+  fn parse(code: &Code) -> Option<Self> {
+    let info = format!(
+      "{} {}",
+      code.lang.as_deref()?,
+      code.meta.as_deref().unwrap_or_default()
+    );
+    let mut attributes = info
+      .split(|c: char| c == ',' || c.is_whitespace())
+      .filter(|attr| !attr.is_empty());
+    if attributes.next() != Some("rust") {
+      return None;
+    }
+
+    let mut parsed = CodeBlockAttributes {
+      mode: CodeBlockMode::Run,
+      edition: None,
+    };
+    for attr in attributes {
+      match attr {
+        "ignore" => return None,
+        "no_run" => parsed.mode = CodeBlockMode::NoRun,
+        "should_panic" => parsed.mode = CodeBlockMode::ShouldPanic,
+        "compile_fail" => parsed.mode = CodeBlockMode::CompileFail,
+        _ => {
+          if let Some(edition) = attr.strip_prefix("edition") {
+            parsed.edition = Some(edition.to_string());
+          }
+        }
+      }
+    }
+    Some(parsed)
+  }
+}
+
+/// Returns the byte range of the `line`th (0-based) line of the contents of the fenced code
+/// block that starts at `start` in `markdown`.
+fn code_line_range(markdown: &str, start: usize, line: usize) -> Option<Range<usize>> {
+  let contents_start = start + markdown[start..].find('\n')? + 1;
+  let mut offset = contents_start;
+  for (i, text) in markdown[contents_start..].split_inclusive('\n').enumerate() {
+    if i == line {
+      return Some(offset..offset + text.trim_end().len());
+    }
+    offset += text.len();
+  }
+  None
+}
+
+/// Compiles and runs a Rust code block like rustdoc, reporting failures at `span`, the range of
+/// the block in the quiz, and compiler errors at their line within the block.
+fn check_code_block(
+  cx: &mut ValidationContext,
+  code: &Code,
+  attributes: &CodeBlockAttributes,
+  span: Range<usize>,
+  line_span: impl Fn(usize) -> Option<Range<usize>>,
+) {
+  // Like rustdoc, a block without a `main` function is wrapped in one.
+  let wrapped = !code.value.contains("fn main");
+  let program = if wrapped {
+    format!("fn main() {{\n{}\n}}\n", code.value)
+  } else {
+    code.value.clone()
+  };
+  let prompt = TracingPrompt {
+    program,
+    entrypoint: None,
+    files: None,
+    crate_name: None,
+    rust: None,
+  };
+  let mut settings = cx.config.rust.clone();
+  if let Some(edition) = &attributes.edition {
+    settings.edition = Some(edition.clone());
+  }
+  let run_program = matches!(
+    attributes.mode,
+    CodeBlockMode::Run | CodeBlockMode::ShouldPanic
+  );
+
+  let config = cx.config.clone();
+  let execution = match execute(&prompt, &settings, &config, run_program) {
+    Ok(execution) => execution,
+    Err(e) => {
+      return cx.error(miette!(
+        code = "markdown::code_block_failed_to_run",
+        labels = vec![LabeledSpan::new_with_span(None, span)],
+        "failed to compile or run code block: {e:#}"
+      ))
+    }
+  };
+
+  if !execution.compiled {
+    if attributes.mode == CodeBlockMode::CompileFail {
+      return;
+    }
+    let errors = rustc_errors(&execution.rustc_stderr);
+    let rendered = errors
+      .iter()
+      .filter_map(|error| error.rendered.as_deref())
+      .collect::<String>();
+    let labels = errors
+      .iter()
+      .flat_map(|error| &error.spans)
+      .filter(|span| span.is_primary && Path::new(&span.file_name) == Path::new("main.rs"))
+      .filter_map(|span| line_span((span.line_start - 1).checked_sub(usize::from(wrapped))?))
+      .map(|span| LabeledSpan::new_with_span(None, span))
+      .collect::<Vec<_>>();
+    let labels = if labels.is_empty() {
+      vec![LabeledSpan::new_with_span(None, span)]
+    } else {
+      labels
+    };
+    return cx.error(miette!(
+      code = "markdown::code_block_does_not_compile",
+      labels = labels,
+      "code block does not compile. rustc stderr:\n{}",
+      textwrap::indent(&rendered, "  ")
+    ));
+  }
+
+  let label = vec![LabeledSpan::new_with_span(None, span)];
+  match (attributes.mode, &execution.run) {
+    (CodeBlockMode::CompileFail, _) => cx.error(miette!(
+      code = "markdown::code_block_compiles",
+      labels = label,
+      "code block is marked compile_fail but compiles"
+    )),
+    (CodeBlockMode::Run, Some(run)) if run.status != Status::Success => cx.error(miette!(
+      code = "markdown::code_block_fails",
+      labels = label,
+      "code block fails when executed. If it should panic, mark it should_panic. stderr:\n{}",
+      textwrap::indent(&run.stderr, "  ")
+    )),
+    (CodeBlockMode::ShouldPanic, Some(run)) if run.status != Status::Failure => cx.error(miette!(
+      code = "markdown::code_block_does_not_panic",
+      labels = label,
+      "code block is marked should_panic but does not panic"
+    )),
+    _ => {}
+  }
+}
+
+/// Reports every misspelled word in the text of `nodes`, where `root` starts at `base` in the quiz.
+fn spellcheck(
+  cx: &mut ValidationContext,
+  root: &Node,
+  nodes: Vec<&Node>,
+  base: usize,
+  dict: &Dictionaries,
+  ignored_words: &[String],
+) {
+  let ignored_ranges = spellcheck_ignored_ranges(root);
+  for node in nodes {
+    if let (Node::Text(text), Some(pos)) = (node, node.position()) {
+      let errors = crate::spellcheck::words(&text.value)
+        .filter(|(idx, _)| {
+          let offset = pos.start.offset + idx;
+          !ignored_ranges.iter().any(|range| range.contains(&offset))
+        })
+        .filter(|(_, word)| {
+          !ignored_words
+            .iter()
+            .any(|ignored| ignored.to_lowercase() == word.to_lowercase())
+        })
+        .filter(|(_, word)| !dict.check_word(word));
+      for (idx, substr) in errors {
+        // base: location of string literal in TOML file
+        // pos.start.offset: location of markdown text node in the string
+        // idx: location of error in text node
+        let span = (base + pos.start.offset + idx, substr.len());
+        let error = SpellingError {
+          word: substr.to_string(),
+          span: span.into(),
+        };
+        cx.warning(error);
+      }
+    }
+  }
+}
+
 impl Validate for Markdown {
   fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
     let root = markdown::to_mdast(&self.0, &ParseOptions::default()).unwrap();
@@ -29,39 +285,55 @@ impl Validate for Markdown {
       nodes
     }
 
-    if cx.spellcheck {
-      let nodes = collect_nodes(&root);
-      let dict = crate::spellcheck::dictionary();
-      let open_quote = &cx.contents()[value.start()..];
-      let quote_size = if let Some(next) = open_quote.strip_prefix(r#"""""#) {
-        if next.starts_with('\n') {
-          4
-        } else {
-          3
-        }
+    let open_quote = &cx.contents()[value.start()..];
+    let quote_size = if let Some(next) = open_quote.strip_prefix(r#"""""#) {
+      if next.starts_with('\n') {
+        4
       } else {
-        1
-      };
-      let base = value.start() + quote_size;
-      for node in nodes {
-        if let (Node::Text(text), Some(pos)) = (node, node.position()) {
-          let errors = dict
-            .check_indices(&text.value)
-            .filter(|(_, s)| *s != "-")
-            .filter(|(_, s)| s.parse::<isize>().is_err())
-            .filter(|(_, s)| s.parse::<f32>().is_err());
-          for (idx, substr) in errors {
-            // base: location of string literal in TOML file
-            // pos.start.offset: location of markdown text node in the string
-            // idx: location of error in text node
-            let span = (base + pos.start.offset + idx, substr.len());
-            let error = SpellingError {
-              word: substr.to_string(),
-              span: span.into(),
-            };
-            cx.warning(error);
-          }
-        }
+        3
+      }
+    } else {
+      1
+    };
+    // base: location of string literal in TOML file
+    let base = value.start() + quote_size;
+
+    // A quiz or question can opt out of a book-wide spellcheck.
+    let (quiz_enabled, ignored_words) = super::QUIZ.get(|quiz| {
+      let config = quiz.and_then(|quiz| quiz.spellcheck.as_ref());
+      (
+        config.and_then(|config| config.enabled).unwrap_or(true),
+        config
+          .and_then(|config| config.ignore.clone())
+          .unwrap_or_default(),
+      )
+    });
+    let question_enabled = super::SKIP_SPELLCHECK.get(|skip| !skip.copied().unwrap_or(false));
+    if cx.config.spellcheck && quiz_enabled && question_enabled {
+      match cx.config.dictionary.load() {
+        Ok(dict) => spellcheck(cx, &root, collect_nodes(&root), base, &dict, &ignored_words),
+        Err(e) => cx.error(miette!(
+          code = "markdown::dictionary",
+          labels = vec![value.labeled_span()],
+          "failed to load spellcheck dictionary: {e:#}"
+        )),
+      }
+    }
+
+    if cx.config.compile_code_blocks {
+      for node in collect_nodes(&root) {
+        let (Node::Code(code), Some(pos)) = (node, node.position()) else {
+          continue;
+        };
+        let Some(attributes) = CodeBlockAttributes::parse(code) else {
+          continue;
+        };
+        let span = base + pos.start.offset..base + pos.end.offset;
+        let line_span = |line| {
+          let range = code_line_range(&self.0, pos.start.offset, line)?;
+          Some(base + range.start..base + range.end)
+        };
+        check_code_block(cx, code, &attributes, span, line_span);
       }
     }
   }
@@ -91,3 +363,179 @@ prompt.distractors = [""]
   // TODO: right now this test is just verified looking at stderr
   assert!(crate::harness(contents).is_ok());
 }
+
+#[test]
+fn validate_markdown_spellcheck_ignores() {
+  let quiz = |ignore: &str| {
+    format!(
+      r#"
+[spellcheck]
+ignore = {ignore}
+
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = """
+Does mdBook call `wrold` or my_wrold or WroldBuilder at https://wrold.example?
+
+Maybe wrold <!-- spellcheck-ignore --> wrold.
+
+<!-- spellcheck-ignore -->
+Or wrold.
+
+Or wrold.
+
+Or flurbo.
+"""
+answer.answer = "Yes"
+"#
+    )
+  };
+  let config = crate::ValidationConfig {
+    spellcheck: true,
+    ..Default::default()
+  };
+  let misspelled = |contents: &str| {
+    let report = crate::diagnose(
+      Path::new("dummy.toml"),
+      contents,
+      &crate::IdSet::default(),
+      &config,
+    );
+    let mut lines = report
+      .diagnostics()
+      .iter()
+      .filter(|d| d.rule.as_deref() == Some("markdown::spelling"))
+      .map(|d| d.span.as_ref().unwrap().start.line)
+      .collect::<Vec<_>>();
+    lines.sort();
+    lines
+  };
+  assert_eq!(misspelled(&quiz("[]")), [10, 15, 17]);
+  // Ignored words are matched regardless of case.
+  assert_eq!(misspelled(&quiz("[\"Flurbo\"]")), [10, 15]);
+}
+
+#[test]
+fn validate_markdown_spellcheck_disabled() {
+  let contents = r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "Hello wrold"
+answer.answer = "Yes"
+
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "Hallo Welt"
+answer.answer = "Ja"
+spellcheck = false
+"#;
+  let config = crate::ValidationConfig {
+    spellcheck: true,
+    ..Default::default()
+  };
+  let count = |contents: &str| {
+    crate::diagnose(
+      Path::new("dummy.toml"),
+      contents,
+      &crate::IdSet::default(),
+      &config,
+    )
+    .diagnostics()
+    .iter()
+    .filter(|d| d.rule.as_deref() == Some("markdown::spelling"))
+    .count()
+  };
+  assert_eq!(count(contents), 1);
+  assert_eq!(count(&format!("spellcheck.enabled = false\n{contents}")), 0);
+}
+
+#[test]
+fn validate_markdown_code_blocks() {
+  let contents = r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = """
+What does this print?
+
+```rust
+# let greeting = "Hello";
+println!("{greeting} world");
+```
+"""
+answer.answer = "Hello world"
+context = """
+```rust,compile_fail
+let x: String = 1;
+```
+
+```rust,should_panic
+let v: Vec<i32> = Vec::new();
+v[0];
+```
+
+```rust,ignore
+this is not Rust
+```
+
+```rust,no_run
+fn main() {
+  loop {}
+}
+```
+"""
+"#;
+  let config = crate::ValidationConfig {
+    compile_code_blocks: true,
+    ..Default::default()
+  };
+  let diagnose = |contents: &str| {
+    crate::diagnose(
+      Path::new("dummy.toml"),
+      contents,
+      &crate::IdSet::default(),
+      &config,
+    )
+  };
+  let rules = |contents: &str| {
+    diagnose(contents)
+      .diagnostics()
+      .iter()
+      .filter_map(|d| d.rule.clone())
+      .collect::<Vec<_>>()
+  };
+
+  assert!(rules(contents).is_empty(), "{:?}", rules(contents));
+  assert_eq!(
+    rules(&contents.replace("rust,compile_fail", "rust")),
+    ["markdown::code_block_does_not_compile"]
+  );
+  assert_eq!(
+    rules(&contents.replace(
+      "rust,compile_fail",
+      "rust,compile_fail\nlet x = 1;\n```\n\n```text"
+    )),
+    ["markdown::code_block_compiles"]
+  );
+  assert_eq!(
+    rules(&contents.replace("rust,should_panic", "rust")),
+    ["markdown::code_block_fails"]
+  );
+  assert_eq!(
+    rules(&contents.replace("v[0];", "v.len();")),
+    ["markdown::code_block_does_not_panic"]
+  );
+
+  // Compiler errors are reported at their line in the code block.
+  let broken = contents.replace(
+    "println!(\"{greeting} world\");",
+    "println!(\"{greting} world\");",
+  );
+  let report = diagnose(&broken);
+  let span = report.diagnostics()[0].span.as_ref().unwrap();
+  assert_eq!(
+    &broken[span.offset..span.offset + span.length],
+    "println!(\"{greting} world\");"
+  );
+
+  assert!(crate::harness(&contents.replace("rust,compile_fail", "rust")).is_ok());
+}
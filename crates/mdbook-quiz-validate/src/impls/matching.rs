@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use crate::{cxensure, tomlcast, SpannedValue, SpannedValueExt, Validate, ValidationContext};
+use mdbook_quiz_schema::*;
+
+/// Checks that no two items in a column are the same, since answers refer to items by their text.
+fn check_column(cx: &mut ValidationContext, items: &[Markdown], value: &SpannedValue) {
+  let mut seen = HashSet::new();
+  for (item, item_val) in items.iter().zip(tomlcast!(value.array)) {
+    item.validate(cx, item_val);
+    cxensure!(
+      cx,
+      seen.insert(item.0.as_str()),
+      code = "matching::duplicate_item",
+      labels = vec![item_val.labeled_span()],
+      "Item appears more than once in its column"
+    );
+  }
+}
+
+impl Validate for Matching {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.0.validate(cx, value);
+
+    let QuestionFields { prompt, answer, .. } = &self.0;
+    let pairs_val = tomlcast!(value.table["answer"].table["pairs"]);
+    let contains = |items: &[Markdown], item: &Markdown| items.iter().any(|i| i.0 == item.0);
+
+    let mut matched = HashSet::new();
+    for ((left, right), pair_val) in answer.pairs.iter().zip(tomlcast!(pairs_val.array)) {
+      let pair_val = tomlcast!(pair_val.array);
+      let (left_val, right_val) = (&pair_val[0], &pair_val[1]);
+      cxensure!(
+        cx,
+        contains(&prompt.left, left),
+        code = "matching::unknown_item",
+        labels = vec![left_val.labeled_span()],
+        "Item is not in the left column"
+      );
+      cxensure!(
+        cx,
+        contains(&prompt.right, right),
+        code = "matching::unknown_item",
+        labels = vec![right_val.labeled_span()],
+        "Item is not in the right column"
+      );
+      cxensure!(
+        cx,
+        matched.insert(left.0.as_str()),
+        code = "matching::not_a_function",
+        labels = vec![left_val.labeled_span()],
+        "Item is matched more than once"
+      );
+    }
+
+    let left_val = tomlcast!(value.table["prompt"].table["left"].array);
+    for (item, item_val) in prompt.left.iter().zip(left_val) {
+      cxensure!(
+        cx,
+        matched.contains(item.0.as_str()),
+        code = "matching::unmatched_item",
+        labels = vec![item_val.labeled_span()],
+        "Item is not matched with any item in the right column"
+      );
+    }
+  }
+}
+
+impl Validate for MatchingPrompt {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.prompt.validate(cx, tomlcast!(value.table["prompt"]));
+    check_column(cx, &self.left, tomlcast!(value.table["left"]));
+    check_column(cx, &self.right, tomlcast!(value.table["right"]));
+  }
+}
+
+impl Validate for MatchingAnswer {
+  fn validate(&self, _cx: &mut ValidationContext, _value: &SpannedValue) {}
+}
+
+#[test]
+fn validate_matching_passes() {
+  let contents = r#"
+[[questions]]
+type = "Matching"
+prompt.prompt = "Match each type with its description."
+prompt.left = ["`Vec<T>`", "`String`"]
+prompt.right = ["An owned list of values", "An owned string", "A borrowed string"]
+answer.pairs = [
+  ["`Vec<T>`", "An owned list of values"],
+  ["`String`", "An owned string"],
+]
+"#;
+  assert!(crate::harness(contents).is_ok());
+}
+
+#[test]
+fn validate_matching_unknown_item() {
+  let contents = r#"
+[[questions]]
+type = "Matching"
+prompt.prompt = ""
+prompt.left = ["a", "b"]
+prompt.right = ["c", "d"]
+answer.pairs = [["a", "c"], ["b", "e"]]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_matching_not_a_function() {
+  let contents = r#"
+[[questions]]
+type = "Matching"
+prompt.prompt = ""
+prompt.left = ["a", "b"]
+prompt.right = ["c", "d"]
+answer.pairs = [["a", "c"], ["a", "d"], ["b", "d"]]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_matching_unmatched_item() {
+  let contents = r#"
+[[questions]]
+type = "Matching"
+prompt.prompt = ""
+prompt.left = ["a", "b"]
+prompt.right = ["c", "d"]
+answer.pairs = [["a", "c"]]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
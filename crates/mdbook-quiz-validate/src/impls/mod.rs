@@ -1,14 +1,28 @@
-use crate::{cxensure, tomlcast, SpannedValue, SpannedValueExt, Validate, ValidationContext};
+use crate::{
+  cxensure, ids, pools, tomlcast, IdStrategy, SpannedValue, SpannedValueExt, Validate,
+  ValidationContext,
+};
 use fluid_let::{fluid_let, fluid_set};
 use mdbook_quiz_schema::{Question, QuestionFields, Quiz};
+use uuid::Uuid;
 
+mod code_exercise;
+mod fill_in_the_blank;
 mod markdown;
+mod matching;
 mod multiple_choice;
+mod numeric;
+mod ordering;
 mod short_answer;
 mod tracing;
 
+pub(crate) use tracing::prefetch;
+
 fluid_let!(static QUIZ: Quiz);
 
+// True while validating a question that has disabled the spellchecker.
+fluid_let!(static SKIP_SPELLCHECK: bool);
+
 impl Validate for Quiz {
   fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
     fluid_set!(QUIZ, self);
@@ -16,14 +30,57 @@ impl Validate for Quiz {
     cxensure!(
       cx,
       !self.questions.is_empty(),
+      code = "quiz::empty",
       labels = vec![value.labeled_span()],
       "Quiz must have at least one question"
     );
 
+    let content_ids = match (&cx.config.book_root, cx.config.id_strategy) {
+      (Some(book_root), IdStrategy::UuidV5Content) => {
+        let quiz_key = cx.quiz_key(book_root);
+        Some(ids::content_ids(&quiz_key, self))
+      }
+      _ => None,
+    };
+
+    prefetch(self, &cx.config);
+
     let table = tomlcast!(value.table["questions"].array);
-    for (q, qvalue) in self.questions.iter().zip(table.iter()) {
+    for (i, (q, qvalue)) in self.questions.iter().zip(table.iter()).enumerate() {
+      cx.set_question(i, q.id());
       q.validate(cx, qvalue);
+
+      // Content-derived IDs let us notice when a question was rewritten without getting a new ID,
+      // which would silently merge telemetry for two different questions.
+      // Small edits like typo fixes are tolerated when the question records a fingerprint of
+      // its original prompt.
+      if let (Some(content_ids), Some(id)) = (&content_ids, q.id()) {
+        let is_changed = Uuid::parse_str(id)
+          .is_ok_and(|uuid| uuid.get_version_num() == 5 && uuid != content_ids[i]);
+        let is_rewritten = q
+          .fingerprint()
+          .and_then(|fingerprint| ids::is_rewritten(q, fingerprint));
+        if is_changed && is_rewritten != Some(false) {
+          let help = match is_rewritten {
+            Some(_) => "If it now asks something different, remove the ID and run \
+                        `mdbook-quiz add-ids`."
+              .to_string(),
+            None => format!(
+              "If it now asks something different, remove the ID and run `mdbook-quiz add-ids`. \
+               Otherwise, set its fingerprint to `{}`.",
+              ids::content_fingerprint(q)
+            ),
+          };
+          cx.warning(miette::miette!(
+            code = "quiz::stale_id",
+            labels = vec![tomlcast!(qvalue.table["id"]).labeled_span()],
+            help = help,
+            "The content of this question has changed since its ID was generated."
+          ));
+        }
+      }
     }
+    cx.clear_question();
 
     if let Some(multipart) = &self.multipart {
       let multipart_val = tomlcast!(value.table["multipart"]);
@@ -31,6 +88,8 @@ impl Validate for Quiz {
         v.validate(cx, multipart_val.get_ref().get(k).unwrap());
       }
     }
+
+    pools::validate_pools(self, cx, value);
   }
 }
 
@@ -40,14 +99,32 @@ impl Validate for Question {
       Question::MultipleChoice(q) => q.validate(cx, value),
       Question::ShortAnswer(q) => q.validate(cx, value),
       Question::Tracing(q) => q.validate(cx, value),
+      Question::FillInTheBlank(q) => q.validate(cx, value),
+      Question::Ordering(q) => q.validate(cx, value),
+      Question::Matching(q) => q.validate(cx, value),
+      Question::CodeExercise(q) => q.validate(cx, value),
+      Question::Numeric(q) => q.validate(cx, value),
     }
   }
 }
 
 impl<Prompt: Validate, Answer: Validate> Validate for QuestionFields<Prompt, Answer> {
   fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
-    if let Some(id) = &self.id {
-      cx.check_id(id, tomlcast!(value.table["id"]));
+    let skip_spellcheck = self.spellcheck == Some(false);
+    fluid_set!(SKIP_SPELLCHECK, &skip_spellcheck);
+
+    match &self.id {
+      Some(id) => cx.check_id(id, tomlcast!(value.table["id"])),
+      None => {
+        let require_ids = cx.config.require_ids;
+        cxensure!(
+          cx,
+          !require_ids,
+          code = "quiz::missing_id",
+          labels = vec![value.labeled_span()],
+          "Question is missing an id. Run `mdbook-quiz add-ids` to generate one."
+        );
+      }
     }
 
     if let Some(multipart) = &self.multipart {
@@ -62,6 +139,7 @@ impl<Prompt: Validate, Answer: Validate> Validate for QuestionFields<Prompt, Ans
         cxensure!(
           cx,
           contains_key,
+          code = "quiz::unknown_multipart",
           labels = vec![multipart_val.labeled_span()],
           "Quiz does not have multipart: {multipart}"
         );
@@ -98,6 +176,83 @@ prompt.distractors = [""]
   assert!(crate::harness(contents).is_err());
 }
 
+#[test]
+fn validate_missing_id() {
+  let contents = r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = ""
+answer.answer = ""
+"#;
+  let config = crate::ValidationConfig {
+    require_ids: true,
+    ..Default::default()
+  };
+  let report = crate::diagnose(
+    std::path::Path::new("dummy.toml"),
+    contents,
+    &crate::IdSet::default(),
+    &config,
+  );
+  assert!(report.is_fatal());
+  assert!(crate::harness(contents).is_ok());
+}
+
+#[test]
+fn validate_stale_content_id() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("quiz.toml");
+  let config = crate::ValidationConfig {
+    id_strategy: IdStrategy::UuidV5Content,
+    book_root: Some(dir.path().to_owned()),
+    ..Default::default()
+  };
+  let quiz = |prompt: &str, id: &str| {
+    format!(
+      r#"
+[[questions]]
+id = "{id}"
+type = "ShortAnswer"
+prompt.prompt = "{prompt}"
+answer.answer = ""
+"#
+    )
+  };
+
+  let original = quiz("What is 1 + 1?", "");
+  std::fs::write(&path, &original).unwrap();
+  let id = IdStrategy::UuidV5Content.generate("quiz.toml", &toml::from_str(&original).unwrap());
+  let diagnose = |contents: &str| {
+    crate::diagnose(&path, contents, &crate::IdSet::default(), &config).into_diagnostics()
+  };
+
+  assert!(diagnose(&quiz("What is *1 + 1*", &id[0])).is_empty());
+
+  let diagnostics = diagnose(&quiz("What is 2 + 2?", &id[0]));
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(diagnostics[0].rule.as_deref(), Some("quiz::stale_id"));
+
+  // With a fingerprint, a small edit keeps the ID but a rewrite does not.
+  let prompt = "Which keyword declares a variable that can be reassigned later?";
+  let original = quiz(prompt, "");
+  let (parsed, _) = crate::parse_quiz(&original).unwrap();
+  let id = IdStrategy::UuidV5Content.generate("quiz.toml", &parsed);
+  let fingerprint = ids::content_fingerprint(&parsed.questions[0]);
+  let with_fingerprint = |prompt: &str| {
+    quiz(prompt, &id[0]).replace(
+      "type = ",
+      &format!("fingerprint = \"{fingerprint}\"\ntype = "),
+    )
+  };
+
+  let edited = prompt.replace("reassigned", "re-assigned");
+  assert!(diagnose(&with_fingerprint(&edited)).is_empty());
+
+  let diagnostics = diagnose(&with_fingerprint("What does the borrow checker prevent?"));
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(diagnostics[0].rule.as_deref(), Some("quiz::stale_id"));
+}
+
 #[test]
 fn validate_multipart_spelling() {
   let contents = r#"
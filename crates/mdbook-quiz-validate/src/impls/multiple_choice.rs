@@ -20,6 +20,7 @@ impl Validate for MultipleChoicePrompt {
       cxensure!(
         cx,
         idx <= self.distractors.len(),
+        code = "multiple_choice::answer_index",
         labels = vec![tomlcast!(value.table["answerIndex"]).labeled_span()],
         "Answer index is too large"
       );
@@ -28,6 +29,7 @@ impl Validate for MultipleChoicePrompt {
     cxensure!(
       cx,
       !(self.answer_index.is_some() && self.sort_answers.is_some()),
+      code = "multiple_choice::index_and_sort",
       labels = vec![
         tomlcast!(value.table["sortAnswers"]).labeled_span(),
         tomlcast!(value.table["answerIndex"]).labeled_span()
@@ -47,6 +49,7 @@ impl Validate for MultipleChoiceAnswerFormat {
         cxensure!(
           cx,
           !v_ans.is_empty(),
+          code = "multiple_choice::no_answer",
           labels = vec![value.labeled_span()],
           "Must be at least one correct answer"
         );
@@ -0,0 +1,168 @@
+use crate::{cxensure, tomlcast, SpannedValue, SpannedValueExt, Validate, ValidationContext};
+use mdbook_quiz_schema::*;
+
+impl Validate for Numeric {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.0.validate(cx, value)
+  }
+}
+
+impl Validate for NumericPrompt {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.prompt.validate(cx, tomlcast!(value.table["prompt"]));
+  }
+}
+
+impl Validate for NumericAnswer {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    cxensure!(
+      cx,
+      self.value.is_some() != self.range.is_some(),
+      code = "numeric::value_and_range",
+      labels = vec![value.labeled_span()],
+      "Answer must have exactly one of `value` and `range`"
+    );
+
+    if let Some(v) = self.value {
+      cxensure!(
+        cx,
+        v.is_finite(),
+        code = "numeric::not_finite",
+        labels = vec![tomlcast!(value.table["value"]).labeled_span()],
+        "Value must be a finite number"
+      );
+    }
+
+    if let Some((min, max)) = self.range {
+      let range_val = tomlcast!(value.table["range"]);
+      cxensure!(
+        cx,
+        min.is_finite() && max.is_finite(),
+        code = "numeric::not_finite",
+        labels = vec![range_val.labeled_span()],
+        "Range must contain finite numbers"
+      );
+      cxensure!(
+        cx,
+        min <= max,
+        code = "numeric::range",
+        labels = vec![range_val.labeled_span()],
+        "Range minimum {min} is greater than its maximum {max}"
+      );
+    }
+
+    for (key, tolerance) in [
+      ("tolerance", self.tolerance),
+      ("relativeTolerance", self.relative_tolerance),
+    ] {
+      if let Some(tolerance) = tolerance {
+        cxensure!(
+          cx,
+          tolerance >= 0. && tolerance.is_finite(),
+          code = "numeric::tolerance",
+          labels = vec![value.get_ref().as_table().unwrap()[key].labeled_span()],
+          "Tolerance must be a non-negative number"
+        );
+      }
+    }
+
+    let integer = self.integer.unwrap_or(false);
+    if integer {
+      if let Some(v) = self.value {
+        cxensure!(
+          cx,
+          v.fract() == 0.,
+          code = "numeric::not_integer",
+          labels = vec![tomlcast!(value.table["value"]).labeled_span()],
+          "Value must be an integer when `integer = true`"
+        );
+      }
+      if let Some((min, max)) = self.range {
+        cxensure!(
+          cx,
+          min.ceil() <= max.floor(),
+          code = "numeric::not_integer",
+          labels = vec![tomlcast!(value.table["range"]).labeled_span()],
+          "Range must contain an integer when `integer = true`"
+        );
+      }
+    }
+
+    if self.representations.is_some() {
+      cxensure!(
+        cx,
+        integer,
+        code = "numeric::representation",
+        labels = vec![tomlcast!(value.table["representations"]).labeled_span()],
+        "Alternate representations require `integer = true`"
+      );
+    }
+  }
+}
+
+#[test]
+fn validate_numeric_passes() {
+  let contents = r#"
+[[questions]]
+type = "Numeric"
+prompt.prompt = "How many bytes does a `u64` occupy?"
+prompt.units = "bytes"
+answer.value = 8
+answer.integer = true
+answer.representations = ["hex", "binary"]
+
+[[questions]]
+type = "Numeric"
+prompt.prompt = "What is the value of pi?"
+answer.range = [3.14, 3.15]
+answer.relativeTolerance = 0.01
+"#;
+  assert!(crate::harness(contents).is_ok());
+}
+
+#[test]
+fn validate_numeric_value_and_range() {
+  let contents = r#"
+[[questions]]
+type = "Numeric"
+prompt.prompt = ""
+answer.value = 1
+answer.range = [0, 2]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_numeric_bad_range() {
+  let contents = r#"
+[[questions]]
+type = "Numeric"
+prompt.prompt = ""
+answer.range = [2, 1]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_numeric_negative_tolerance() {
+  let contents = r#"
+[[questions]]
+type = "Numeric"
+prompt.prompt = ""
+answer.value = 1.5
+answer.tolerance = -0.1
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_numeric_not_integer() {
+  let contents = r#"
+[[questions]]
+type = "Numeric"
+prompt.prompt = ""
+answer.value = 1.5
+answer.integer = true
+"#;
+  assert!(crate::harness(contents).is_err());
+}
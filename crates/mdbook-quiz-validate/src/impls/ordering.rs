@@ -0,0 +1,152 @@
+use miette::miette;
+use tempfile::TempDir;
+
+use super::tracing::compile;
+use crate::{cxensure, tomlcast, SpannedValue, SpannedValueExt, Validate, ValidationContext};
+use mdbook_quiz_schema::*;
+
+/// Assembles the fragments into a program, wrapping them in a `main` function
+/// if they don't define one.
+fn assemble(fragments: &[String]) -> String {
+  let program = fragments.join("\n");
+  if program.contains("fn main") {
+    program
+  } else {
+    format!("fn main() {{\n{program}\n}}")
+  }
+}
+
+impl Validate for Ordering {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.0.validate(cx, value);
+
+    let QuestionFields { prompt, answer, .. } = &self.0;
+    let fragments_val = tomlcast!(value.table["answer"].table["fragments"]);
+
+    if let Some(distractors) = &prompt.distractors {
+      let distractors_val = tomlcast!(value.table["prompt"].table["distractors"].array);
+      for (d, dv) in distractors.iter().zip(distractors_val) {
+        cxensure!(
+          cx,
+          !answer.fragments.iter().any(|f| f.trim() == d.trim()),
+          code = "ordering::ambiguous_distractor",
+          labels = vec![dv.labeled_span()],
+          "Distractor is identical to a fragment of the answer"
+        );
+      }
+    }
+
+    if !matches!(prompt.format, Some(OrderingFormat::Code)) {
+      for (f, fv) in answer.fragments.iter().zip(tomlcast!(fragments_val.array)) {
+        Markdown(f.clone()).validate(cx, fv);
+      }
+    } else {
+      let program = assemble(&answer.fragments);
+      let rustc_output = TempDir::new()
+        .map_err(anyhow::Error::from)
+        .and_then(|dir| compile(dir.path(), &program, &[], &cx.config.rust));
+      match rustc_output {
+        Ok(output) => {
+          let rustc_stderr = String::from_utf8_lossy(&output.stderr);
+          cxensure!(
+            cx,
+            output.status.success(),
+            code = "ordering::does_compile",
+            labels = vec![fragments_val.labeled_span()],
+            "fragments in the correct order do not compile. rustc stderr:\n{}",
+            textwrap::indent(&rustc_stderr, "  ")
+          );
+        }
+        Err(e) => cx.error(miette!(
+          code = "ordering::does_compile",
+          labels = vec![fragments_val.labeled_span()],
+          "failed to compile fragments: {e}"
+        )),
+      }
+    }
+  }
+}
+
+impl Validate for OrderingPrompt {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    self.prompt.validate(cx, tomlcast!(value.table["prompt"]));
+
+    if !matches!(self.format, Some(OrderingFormat::Code)) {
+      if let Some(distractors) = &self.distractors {
+        let distractors_val = tomlcast!(value.table["distractors"].array);
+        for (d, dv) in distractors.iter().zip(distractors_val) {
+          Markdown(d.clone()).validate(cx, dv);
+        }
+      }
+    }
+  }
+}
+
+impl Validate for OrderingAnswer {
+  fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
+    let fragments_val = tomlcast!(value.table["fragments"]);
+    cxensure!(
+      cx,
+      self.fragments.len() >= 2,
+      code = "ordering::too_few_fragments",
+      labels = vec![fragments_val.labeled_span()],
+      "Must have at least two fragments to order"
+    );
+  }
+}
+
+#[test]
+fn validate_ordering_passes() {
+  let contents = r#"
+[[questions]]
+type = "Ordering"
+prompt.prompt = "Put the lines in order so the program prints `2`."
+prompt.format = "code"
+prompt.distractors = ["let x = 1;"]
+prompt.gradeIndentation = true
+answer.fragments = [
+  "let mut x = 1;",
+  "x += 1;",
+  'println!("{x}");'
+]
+"#;
+  assert!(crate::harness(contents).is_ok());
+}
+
+#[test]
+fn validate_ordering_does_not_compile() {
+  let contents = r#"
+[[questions]]
+type = "Ordering"
+prompt.prompt = ""
+prompt.format = "code"
+answer.fragments = [
+  "x += 1;",
+  "let mut x = 1;",
+]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_ordering_ambiguous_distractor() {
+  let contents = r#"
+[[questions]]
+type = "Ordering"
+prompt.prompt = ""
+prompt.distractors = ["Second"]
+answer.fragments = ["First", "Second"]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
+
+#[test]
+fn validate_ordering_too_few_fragments() {
+  let contents = r#"
+[[questions]]
+type = "Ordering"
+prompt.prompt = ""
+answer.fragments = ["First"]
+"#;
+  assert!(crate::harness(contents).is_err());
+}
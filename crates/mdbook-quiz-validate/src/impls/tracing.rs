@@ -1,96 +1,490 @@
+use miette::miette;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::{
+  collections::BTreeSet,
   fs,
-  process::{Command, Stdio},
+  path::{Component, Path},
+  process::{Command, Output, Stdio},
 };
 use tempfile::TempDir;
 
-use crate::{cxensure, tomlcast, SpannedValue, SpannedValueExt, Validate, ValidationContext};
+use crate::{
+  cache, cxensure, hidden,
+  limits::{self, Status},
+  rust::EDITIONS,
+  tomlcast, RustSettings, SpannedValue, SpannedValueExt, Validate, ValidationConfig,
+  ValidationContext,
+};
 use mdbook_quiz_schema::*;
 
+/// Compiles `program` with rustc into an executable `main` in `dir`, passing `args` to rustc.
+pub(super) fn compile(
+  dir: &Path,
+  program: &str,
+  args: &[&str],
+  settings: &RustSettings,
+) -> anyhow::Result<Output> {
+  fs::write(dir.join("main.rs"), program)?;
+  compile_file(dir, Path::new("main.rs"), args, settings)
+}
+
+/// Compiles the file at `path`, relative to `dir`, with rustc, passing `args` to rustc.
+///
+/// rustc runs in `dir`, so the paths in its diagnostics are relative to `dir`.
+fn compile_file(
+  dir: &Path,
+  path: &Path,
+  args: &[&str],
+  settings: &RustSettings,
+) -> anyhow::Result<Output> {
+  let output = settings
+    .rustc()?
+    .arg(path)
+    .args(["-A", "warnings"])
+    .args(args)
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .current_dir(dir)
+    .output()?;
+  Ok(output)
+}
+
+/// The path of the entrypoint of a program if the question does not specify one.
+const DEFAULT_ENTRYPOINT: &str = "main.rs";
+
+/// The name of the library crate of a program if the question does not specify one.
+const DEFAULT_CRATE_NAME: &str = "quiz";
+
+/// Checks that `path` is a relative path that stays inside the directory of the program.
+fn check_path(path: &str) -> anyhow::Result<()> {
+  anyhow::ensure!(
+    !path.is_empty()
+      && Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_))),
+    "`{path}` must be a relative path without `..`"
+  );
+  Ok(())
+}
+
+/// Writes every file of the program of `prompt` into `dir`, and compiles it into an
+/// executable `main`, returning rustc's JSON-formatted stderr and whether compilation succeeded.
+fn build(
+  dir: &Path,
+  prompt: &TracingPrompt,
+  settings: &RustSettings,
+) -> anyhow::Result<(String, bool)> {
+  let entrypoint = prompt.entrypoint.as_deref().unwrap_or(DEFAULT_ENTRYPOINT);
+  let files = prompt
+    .files
+    .iter()
+    .flatten()
+    .map(|(path, contents)| (path.as_str(), contents.as_str()));
+  for (path, contents) in files.clone().chain([(entrypoint, prompt.program.as_str())]) {
+    check_path(path)?;
+    let path = dir.join(path);
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, hidden::full_program(contents))?;
+  }
+
+  let mut args = vec!["--error-format=json", "-o", "main"];
+  let mut rustc_stderr = String::new();
+
+  // Like Cargo, a lib.rs beside the entrypoint is a library crate that the entrypoint can use.
+  let entrypoint = Path::new(entrypoint);
+  let lib = entrypoint.with_file_name("lib.rs");
+  let has_lib = files.clone().any(|(path, _)| Path::new(path) == lib);
+  let crate_name = prompt.crate_name.as_deref().unwrap_or(DEFAULT_CRATE_NAME);
+  let extern_arg = format!("{crate_name}=lib{crate_name}.rlib");
+  if has_lib {
+    let output = compile_file(
+      dir,
+      &lib,
+      &[
+        "--error-format=json",
+        "--crate-type=lib",
+        "--crate-name",
+        crate_name,
+      ],
+      settings,
+    )?;
+    rustc_stderr.push_str(&String::from_utf8(output.stderr)?);
+    if !output.status.success() {
+      return Ok((rustc_stderr, false));
+    }
+    args.extend(["--extern", &extern_arg]);
+  }
+
+  let output = compile_file(dir, entrypoint, &args, settings)?;
+  rustc_stderr.push_str(&String::from_utf8(output.stderr)?);
+  Ok((rustc_stderr, output.status.success()))
+}
+
+/// A diagnostic emitted by rustc with `--error-format=json`.
+#[derive(Deserialize)]
+pub(super) struct RustcDiagnostic {
+  level: String,
+  pub(super) spans: Vec<RustcSpan>,
+  pub(super) rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct RustcSpan {
+  pub(super) file_name: String,
+  pub(super) line_start: usize,
+  pub(super) is_primary: bool,
+}
+
+/// Parses the errors out of rustc's JSON-formatted stderr.
+pub(super) fn rustc_errors(stderr: &str) -> Vec<RustcDiagnostic> {
+  stderr
+    .lines()
+    .filter_map(|line| serde_json::from_str::<RustcDiagnostic>(line).ok())
+    .filter(|diagnostic| diagnostic.level == "error")
+    .collect()
+}
+
+/// Parses the message of a panic out of a program's stderr, if the program panicked.
+fn panic_message(stderr: &str) -> Option<String> {
+  let mut lines = stderr.lines();
+  let header = lines.find(|line| line.starts_with("thread '") && line.contains(" panicked at "))?;
+  let location = &header[header.find(" panicked at ")? + " panicked at ".len()..];
+
+  // Before Rust 1.73, the message was quoted on the same line, e.g.
+  // `thread 'main' panicked at 'explicit panic', src/main.rs:2:3`.
+  if let Some(quoted) = location.strip_prefix('\'') {
+    let end = quoted.rfind("', ")?;
+    return Some(quoted[..end].to_string());
+  }
+
+  // Since then, the message follows the location on its own lines.
+  let message = lines
+    .take_while(|line| !line.starts_with("note: ") && *line != "stack backtrace:")
+    .collect::<Vec<_>>()
+    .join("\n");
+  Some(message)
+}
+
+/// The result of compiling and running the program of a [`Tracing`] question.
+#[derive(Serialize, Deserialize)]
+pub(super) struct Execution {
+  /// rustc's JSON-formatted stderr.
+  pub(super) rustc_stderr: String,
+
+  /// True if the program compiled.
+  pub(super) compiled: bool,
+
+  /// The result of running the program, if it compiled and was run.
+  pub(super) run: Option<Run>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct Run {
+  pub(super) status: Status,
+  pub(super) code: Option<i32>,
+  pub(super) stdout: String,
+  pub(super) stderr: String,
+}
+
+/// Compiles the program of `prompt` and, if `run_program` is true, runs it, reusing the result
+/// of a previous execution under the same settings if one is cached.
+pub(super) fn execute(
+  prompt: &TracingPrompt,
+  settings: &RustSettings,
+  config: &ValidationConfig,
+  run_program: bool,
+) -> anyhow::Result<Execution> {
+  let limits = format!("{:?}", config.limits);
+  let layout = format!(
+    "{:?}\n{:?}\n{:?}",
+    prompt.entrypoint, prompt.crate_name, prompt.files
+  );
+  let key = cache::key(&[
+    if run_program {
+      "tracing"
+    } else {
+      "tracing-no-run"
+    },
+    &settings.fingerprint()?,
+    &limits,
+    &layout,
+    &prompt.program,
+  ]);
+  let run = || {
+    let dir = TempDir::new()?;
+    let (rustc_stderr, success) = build(dir.path(), prompt, settings)?;
+    if !success || !run_program {
+      return Ok(Execution {
+        rustc_stderr,
+        compiled: success,
+        run: None,
+      });
+    }
+
+    let mut cmd = Command::new(dir.path().join("main"));
+    cmd.current_dir(dir.path());
+    let output = limits::run(cmd, &config.limits)?;
+    Ok(Execution {
+      rustc_stderr,
+      compiled: true,
+      run: Some(Run {
+        status: output.status,
+        code: output.code,
+        stdout: output.stdout,
+        stderr: output.stderr,
+      }),
+    })
+  };
+
+  // A timeout can be caused by a busy machine rather than the program, so it is retried next time.
+  let should_cache =
+    |execution: &Execution| !matches!(&execution.run, Some(run) if run.status == Status::TimedOut);
+  cache::cached(config.cache_dir.as_deref(), key, run, should_cache)
+}
+
+/// The settings for compiling the program of `question`.
+fn settings(question: &Tracing, config: &ValidationConfig) -> RustSettings {
+  match &question.0.prompt.rust {
+    Some(rust) => config.rust.with_overrides(rust),
+    None => config.rust.clone(),
+  }
+}
+
+/// Executes the programs of every [`Tracing`] question in `quiz` in parallel,
+/// so that validating each question only has to look up its cached result.
+pub(crate) fn prefetch(quiz: &Quiz, config: &ValidationConfig) {
+  quiz
+    .questions
+    .par_iter()
+    .filter_map(|q| match q {
+      Question::Tracing(q) => Some(q),
+      _ => None,
+    })
+    .for_each(|q| {
+      // Any error is reported when the question itself is validated.
+      let _ = execute(&q.0.prompt, &settings(q, config), config, true);
+    });
+}
+
 impl Validate for Tracing {
   fn validate(&self, cx: &mut ValidationContext, value: &SpannedValue) {
-    let QuestionFields {
-      prompt: TracingPrompt { program },
-      answer,
-      ..
-    } = &self.0;
+    let QuestionFields { prompt, answer, .. } = &self.0;
+    let rust = &prompt.rust;
+    let entrypoint = prompt.entrypoint.as_deref().unwrap_or(DEFAULT_ENTRYPOINT);
+
+    if let Some(file) = &answer.file {
+      let files = prompt.files.iter().flatten().map(|(path, _)| path.as_str());
+      cxensure!(
+        cx,
+        files.chain([entrypoint]).any(|path| path == file),
+        code = "tracing::unknown_file",
+        labels = vec![tomlcast!(value.table["answer"].table["file"]).labeled_span()],
+        "answer.file `{file}` is not a file of the program"
+      );
+    }
+
+    if let Some(edition) = rust.as_ref().and_then(|rust| rust.edition.as_ref()) {
+      cxensure!(
+        cx,
+        EDITIONS.contains(&edition.as_str()),
+        code = "tracing::edition",
+        labels =
+          vec![tomlcast!(value.table["prompt"].table["rust"].table["edition"]).labeled_span()],
+        "unknown edition `{edition}`, expected one of: {}",
+        EDITIONS.join(", ")
+      );
+    }
+
+    let config = cx.config.clone();
+    let settings = settings(self, &config);
     let mut inner = || -> anyhow::Result<()> {
-      let dir = TempDir::new()?;
-      let src_path = dir.path().join("main.rs");
-      fs::write(&src_path, program)?;
-
-      let rustc_output = Command::new("rustc")
-        .arg(src_path)
-        .args(["-A", "warnings"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .current_dir(dir.path())
-        .output()?;
-
-      let rustc_stderr = String::from_utf8(rustc_output.stderr)?;
+      let execution = execute(prompt, &settings, &config, true)?;
       let answer_val = tomlcast!(value.table["answer"]);
 
-      if rustc_output.status.success() {
+      if let Some(run) = &execution.run {
         cxensure!(
           cx,
           answer.does_compile,
+          code = "tracing::does_compile",
           labels = vec![tomlcast!(answer_val.table["doesCompile"]).labeled_span()],
           "program compiles but doesCompile = false",
         );
 
         cxensure!(
           cx,
-          answer.stdout.is_some(),
+          answer.stdout.is_some() || answer.panic.is_some(),
+          code = "tracing::missing_stdout",
           labels = vec![answer_val.labeled_span()],
           "program compiles but stdout is missing"
         );
 
-        let exe_path = dir.path().join("main");
-        let cmd_output = Command::new(exe_path)
-          .stdout(Stdio::piped())
-          .stderr(Stdio::piped())
-          .current_dir(dir.path())
-          .output()?;
-        let cmd_stdout = String::from_utf8(cmd_output.stdout)?;
-        let cmd_stderr = String::from_utf8(cmd_output.stderr)?;
-
         cxensure!(
           cx,
-          cmd_output.status.success(),
+          run.status != Status::TimedOut,
+          code = "tracing::timeout",
           labels = vec![answer_val.labeled_span()],
-          "program fails when executed. stderr:\n{}",
-          textwrap::indent(&cmd_stderr, "  ")
+          "program did not finish within {:?}. Does it contain an infinite loop?",
+          config.limits.timeout
         );
 
-        let expected_stdout = answer.stdout.as_ref().unwrap();
         cxensure!(
           cx,
-          cmd_stdout.trim() == expected_stdout.trim(),
-          labels = vec![tomlcast!(answer_val.table["stdout"]).labeled_span()],
-          "expected stdout:\n{}\ndid not match actual stdout:\n{}",
-          textwrap::indent(expected_stdout, "  "),
-          textwrap::indent(&cmd_stdout, "  ")
+          run.status != Status::OutputLimit,
+          code = "tracing::output_limit",
+          labels = vec![answer_val.labeled_span()],
+          "program printed more than the limit of {} bytes",
+          config.limits.max_output
         );
+
+        // A program that was killed didn't finish, so its output can't be compared.
+        if matches!(run.status, Status::TimedOut | Status::OutputLimit) {
+          return Ok(());
+        }
+
+        match &answer.panic {
+          None => cxensure!(
+            cx,
+            run.status != Status::Failure,
+            code = "tracing::runtime_failure",
+            labels = vec![answer_val.labeled_span()],
+            "program fails when executed. If it should panic, add an answer.panic table. stderr:\n{}",
+            textwrap::indent(&run.stderr, "  ")
+          ),
+          Some(panic) => {
+            let panic_val = tomlcast!(answer_val.table["panic"]);
+            cxensure!(
+              cx,
+              run.status != Status::Success,
+              code = "tracing::missing_panic",
+              labels = vec![panic_val.labeled_span()],
+              "program does not fail when executed but answer.panic is set"
+            );
+
+            if run.status == Status::Failure {
+              let expected_code = panic.exit_code.unwrap_or(101);
+              cxensure!(
+                cx,
+                run.code == Some(expected_code),
+                code = "tracing::exit_code_mismatch",
+                labels = vec![panic_val.labeled_span()],
+                "expected exit code {expected_code} but the program exited with {}. stderr:\n{}",
+                run
+                  .code
+                  .map_or_else(|| "a signal".to_string(), |code| code.to_string()),
+                textwrap::indent(&run.stderr, "  ")
+              );
+
+              if let Some(expected_message) = &panic.message {
+                let message = panic_message(&run.stderr);
+                cxensure!(
+                  cx,
+                  message.as_deref().map(str::trim) == Some(expected_message.trim()),
+                  code = "tracing::panic_message_mismatch",
+                  labels = vec![tomlcast!(panic_val.table["message"]).labeled_span()],
+                  "expected panic message:\n{}\ndid not match actual panic message:\n{}",
+                  textwrap::indent(expected_message, "  "),
+                  textwrap::indent(message.as_deref().unwrap_or("(the program did not panic)"), "  ")
+                );
+              }
+            }
+          }
+        }
+
+        // A panicking program may print nothing before it panics, so its stdout can be omitted.
+        if answer.stdout.is_some() || answer.panic.is_some() {
+          let expected_stdout = answer.stdout.as_deref().unwrap_or_default();
+          let stdout_val = match answer.stdout {
+            Some(_) => tomlcast!(answer_val.table["stdout"]),
+            None => answer_val,
+          };
+          cxensure!(
+            cx,
+            run.stdout.trim() == expected_stdout.trim(),
+            code = "tracing::stdout_mismatch",
+            labels = vec![stdout_val.labeled_span()],
+            "expected stdout:\n{}\ndid not match actual stdout:\n{}",
+            textwrap::indent(expected_stdout, "  "),
+            textwrap::indent(&run.stdout, "  ")
+          );
+        }
       } else {
+        let errors = rustc_errors(&execution.rustc_stderr);
+        let rendered = errors
+          .iter()
+          .filter_map(|error| error.rendered.as_deref())
+          .collect::<String>();
         cxensure!(
           cx,
           !answer.does_compile,
+          code = "tracing::does_compile",
           labels = vec![tomlcast!(answer_val.table["doesCompile"]).labeled_span()],
           "program does not compile but doesCompile = true. rustc stderr:\n{}",
-          textwrap::indent(&rustc_stderr, "  ")
+          textwrap::indent(&rendered, "  ")
         );
 
         cxensure!(
           cx,
           answer.stdout.is_none(),
+          code = "tracing::unexpected_stdout",
           labels = vec![answer_val.labeled_span()],
           "program does not compile but contains a stdout key"
         );
+
+        cxensure!(
+          cx,
+          answer.panic.is_none(),
+          code = "tracing::unexpected_panic",
+          labels = vec![answer_val.labeled_span()],
+          "program does not compile but contains a panic key"
+        );
+
+        if !answer.does_compile {
+          cxensure!(
+            cx,
+            answer.line_number.is_some(),
+            code = "tracing::missing_line_number",
+            labels = vec![answer_val.labeled_span()],
+            "program does not compile but lineNumber is missing"
+          );
+
+          if let Some(line_number) = answer.line_number {
+            let file = answer.file.as_deref().unwrap_or(entrypoint);
+            let source = match prompt.files.as_ref().and_then(|files| files.get(file)) {
+              Some(contents) if file != entrypoint => contents,
+              _ => &prompt.program,
+            };
+            // Hidden lines are not shown to readers, so lineNumber counts only visible lines.
+            let file = Path::new(file);
+            let error_lines = errors
+              .iter()
+              .flat_map(|error| &error.spans)
+              .filter(|span| span.is_primary && Path::new(&span.file_name) == file)
+              .filter_map(|span| hidden::visible_line(source, span.line_start))
+              .collect::<BTreeSet<_>>();
+            cxensure!(
+              cx,
+              error_lines.contains(&line_number),
+              code = "tracing::line_number_mismatch",
+              labels = vec![tomlcast!(answer_val.table["lineNumber"]).labeled_span()],
+              "lineNumber = {line_number} does not match the line of any compiler error in {}, expected one of: {}. rustc stderr:\n{}",
+              file.display(),
+              error_lines.iter().map(|line| line.to_string()).collect::<Vec<_>>().join(", "),
+              textwrap::indent(&rendered, "  ")
+            );
+          }
+        }
       }
 
       Ok(())
     };
-    inner().unwrap();
+    if let Err(e) = inner() {
+      cx.error(miette!(
+        code = "tracing::failed_to_run",
+        labels = vec![tomlcast!(value.table["prompt"].table["program"]).labeled_span()],
+        "failed to compile or run program: {e:#}"
+      ));
+    }
   }
 }
 
@@ -141,3 +535,183 @@ answer.stdout = "meep meep"
 "#;
   assert!(crate::harness(contents).is_err());
 }
+
+#[test]
+fn validate_tracing_line_number() {
+  let contents = r#"
+[[questions]]
+type = "Tracing"
+prompt.program = """
+fn main() {
+  let x = 1;
+  println!("{x}");
+  x += 1;
+  println!("{x}");
+}
+"""
+answer.doesCompile = false
+answer.lineNumber = 4
+"#;
+  assert!(crate::harness(contents).is_ok());
+  assert!(crate::harness(&contents.replace("lineNumber = 4", "lineNumber = 2")).is_err());
+  assert!(crate::harness(&contents.replace("answer.lineNumber = 4", "")).is_err());
+}
+
+#[test]
+fn validate_tracing_edition() {
+  let contents = r#"
+[[questions]]
+type = "Tracing"
+prompt.program = """
+async fn hello() {}
+fn main() {
+  let _future = hello();
+  println!("Hello world");
+}
+"""
+prompt.rust.edition = "2021"
+answer.doesCompile = true
+answer.stdout = "Hello world"
+"#;
+  assert!(crate::harness(contents).is_ok());
+  assert!(crate::harness(&contents.replace("\"2021\"", "\"2015\"")).is_err());
+  assert!(crate::harness(&contents.replace("\"2021\"", "\"2077\"")).is_err());
+}
+
+#[test]
+fn validate_tracing_timeout() {
+  let contents = r#"
+[[questions]]
+type = "Tracing"
+prompt.program = """
+fn main() {
+  println!("Hello");
+  loop {}
+  println!("world");
+}
+"""
+answer.doesCompile = true
+answer.stdout = "Hello\nworld"
+"#;
+  let config = crate::ValidationConfig {
+    limits: crate::ResourceLimits {
+      timeout: std::time::Duration::from_millis(500),
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+  let report = crate::diagnose(
+    Path::new("dummy.toml"),
+    contents,
+    &crate::IdSet::default(),
+    &config,
+  );
+  // Output that was cut short is not compared.
+  let rules = report
+    .diagnostics()
+    .iter()
+    .filter_map(|d| d.rule.as_deref())
+    .collect::<Vec<_>>();
+  assert_eq!(rules, ["tracing::timeout"]);
+}
+
+#[test]
+fn validate_tracing_panic() {
+  let contents = r#"
+[[questions]]
+type = "Tracing"
+prompt.program = """
+fn main() {
+  let v = vec![1, 2, 3];
+  println!("{}", v[0]);
+  println!("{}", v[5]);
+}
+"""
+answer.doesCompile = true
+answer.stdout = "1"
+answer.panic.message = "index out of bounds: the len is 3 but the index is 5"
+"#;
+  assert!(crate::harness(contents).is_ok());
+  assert!(crate::harness(&contents.replace("the index is 5", "the index is 4")).is_err());
+  assert!(crate::harness(
+    &contents.replace("answer.panic.message", "answer.panic.exitCode = 1\n#")
+  )
+  .is_err());
+  assert!(crate::harness(&contents.replace("answer.panic.message", "#")).is_err());
+  assert!(crate::harness(&contents.replace("v[5]", "v[2]")).is_err());
+}
+
+#[test]
+fn test_panic_message() {
+  let stderr = "\nthread 'main' (1234) panicked at src/main.rs:3:5:\nexplicit panic\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n";
+  assert_eq!(panic_message(stderr).as_deref(), Some("explicit panic"));
+
+  let stderr = "thread 'main' panicked at 'explicit panic', src/main.rs:3:5\n";
+  assert_eq!(panic_message(stderr).as_deref(), Some("explicit panic"));
+
+  assert_eq!(panic_message("error: something else"), None);
+}
+
+#[test]
+fn validate_tracing_files() {
+  let contents = r#"
+[[questions]]
+type = "Tracing"
+prompt.program = """
+mod garden;
+
+fn main() {
+  garden::plant();
+  quiz::harvest();
+}
+"""
+prompt.files."garden.rs" = """
+pub fn plant() {
+  println!("planted");
+}
+"""
+prompt.files."lib.rs" = """
+pub fn harvest() {
+  println!("harvested");
+}
+"""
+answer.doesCompile = true
+answer.stdout = "planted\nharvested"
+"#;
+  assert!(crate::harness(contents).is_ok());
+  assert!(
+    crate::harness(&contents.replace("prompt.files.\"lib.rs\"", "prompt.files.\"util.rs\""))
+      .is_err()
+  );
+  assert!(crate::harness(&contents.replace("\"garden.rs\"", "\"../garden.rs\"")).is_err());
+
+  let private = contents.replace("pub fn plant", "fn plant").replace(
+    "answer.stdout = \"planted\\nharvested\"",
+    "answer.lineNumber = 4",
+  );
+  let private = private.replace("answer.doesCompile = true", "answer.doesCompile = false");
+  assert!(crate::harness(&private).is_ok());
+  assert!(crate::harness(&format!("{private}answer.file = \"garden.rs\"\n")).is_err());
+  assert!(crate::harness(&format!("{private}answer.file = \"other.rs\"\n")).is_err());
+}
+
+#[test]
+fn validate_tracing_hidden_lines() {
+  let contents = r#"
+[[questions]]
+type = "Tracing"
+prompt.program = """
+# use std::collections::HashMap;
+fn main() {
+  # let mut map = HashMap::new();
+  # map.insert("a", 1);
+  let x = map["a"];
+  x += 1;
+}
+"""
+answer.doesCompile = false
+answer.lineNumber = 3
+"#;
+  assert!(crate::harness(contents).is_ok());
+  assert!(crate::harness(&contents.replace("lineNumber = 3", "lineNumber = 6")).is_err());
+}
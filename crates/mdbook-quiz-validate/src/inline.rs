@@ -0,0 +1,189 @@
+//! Quizzes written in `quiz` code blocks inside a chapter, rather than in a separate file.
+
+use std::{ops::Range, path::Path};
+
+use markdown::{mdast::Node, ParseOptions};
+
+use crate::{ids, source_map::SourceMap};
+
+/// A quiz in the TOML format written in a ```` ```quiz ```` code block of a chapter.
+pub struct InlineQuiz<'a> {
+  /// The path to the chapter.
+  pub chapter_path: &'a Path,
+
+  /// The Markdown of the chapter.
+  pub chapter: &'a str,
+
+  /// The position of the quiz among the inline quizzes of the chapter.
+  pub index: usize,
+
+  /// The range of the code block in the chapter, including its fences.
+  pub range: Range<usize>,
+
+  /// The TOML inside the code block, without the block's indentation.
+  pub contents: String,
+}
+
+impl<'a> InlineQuiz<'a> {
+  /// Finds every inline quiz in `chapter`, the Markdown of the chapter at `chapter_path`.
+  pub fn find_all(chapter_path: &'a Path, chapter: &'a str) -> Vec<InlineQuiz<'a>> {
+    fn collect<'n>(node: &'n Node, blocks: &mut Vec<&'n Node>) {
+      match node {
+        Node::Code(code) if code.lang.as_deref() == Some("quiz") => blocks.push(node),
+        _ => node
+          .children()
+          .into_iter()
+          .flatten()
+          .for_each(|child| collect(child, blocks)),
+      }
+    }
+
+    let Ok(root) = markdown::to_mdast(chapter, &ParseOptions::gfm()) else {
+      return Vec::new();
+    };
+    let mut blocks = Vec::new();
+    collect(&root, &mut blocks);
+    blocks
+      .into_iter()
+      .enumerate()
+      .filter_map(|(index, node)| {
+        let (Node::Code(code), Some(pos)) = (node, node.position()) else {
+          return None;
+        };
+        Some(InlineQuiz {
+          chapter_path,
+          chapter,
+          index,
+          range: pos.start.offset..pos.end.offset,
+          contents: code.value.clone(),
+        })
+      })
+      .collect()
+  }
+
+  /// Identifies the quiz within the book at `book_root` when generating IDs, like
+  /// [`quiz_key`](crate::quiz_key) does for quiz files.
+  pub fn quiz_key(&self, book_root: &Path) -> String {
+    let chapter_key = ids::quiz_key(book_root, self.chapter_path);
+    format!("{chapter_key}#quiz-{}", self.index)
+  }
+
+  /// The offset in the chapter of the first line inside the code block.
+  fn lines_start(&self) -> usize {
+    match self.chapter[self.range.start..].find('\n') {
+      Some(i) => self.range.start + i + 1,
+      None => self.range.end,
+    }
+  }
+
+  /// Returns the range of the chapter between the code block's fences, and `contents` with
+  /// the block's indentation to replace it, e.g. to write IDs into the quiz.
+  pub fn replace_contents(&self, contents: &str) -> (Range<usize>, String) {
+    let lines_start = self.lines_start();
+    let block = &self.chapter[lines_start..self.range.end];
+    let is_fence = |line: &str| {
+      let line = line.trim_start_matches(|c: char| c.is_whitespace() || c == '>');
+      line.starts_with("```") || line.starts_with("~~~")
+    };
+    let lines_end = match block.rfind('\n') {
+      Some(i) if is_fence(&block[i + 1..]) => lines_start + i + 1,
+      _ => self.range.end,
+    };
+
+    // Lines inside a list item or block quote start with the same prefix as the first line.
+    let first_line = self.chapter[lines_start..lines_end].lines().next();
+    let prefix = first_line
+      .zip(self.contents.lines().next())
+      .and_then(|(line, dedented)| line.strip_suffix(dedented))
+      .unwrap_or_default();
+    let mut replacement = String::new();
+    for line in contents.lines() {
+      // Blank lines keep a block quote's `>` but not trailing whitespace.
+      match line {
+        "" => replacement.push_str(prefix.trim_end()),
+        _ => replacement.push_str(prefix),
+      }
+      replacement.push_str(line);
+      replacement.push('\n');
+    }
+    (lines_start..lines_end, replacement)
+  }
+
+  /// Moves ranges of the quiz's TOML to the chapter.
+  pub(crate) fn source_map(&self) -> SourceMap {
+    let lines_start = self.lines_start();
+    SourceMap::embedded(
+      self.chapter,
+      lines_start,
+      self.range.clone(),
+      &self.contents,
+    )
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{diagnose_inline, IdSet, ValidationConfig};
+
+  #[test]
+  fn validate_inline_quiz() {
+    let chapter = r#"# Variables
+
+Some prose.
+
+- A list item with a quiz:
+
+  ```quiz
+  [[questions]]
+  type = "ShortAnswer"
+  prompt.prompt = "What is the keyword for declaring a variable?"
+  answer.answer = "let"
+  ```
+
+```quiz
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "What is the keyword for declaring a constant?"
+answer.answer = 1
+```
+"#;
+    let path = Path::new("chapter.md");
+    let quizzes = InlineQuiz::find_all(path, chapter);
+    assert_eq!(quizzes.len(), 2);
+    assert!(quizzes[0].contents.starts_with("[[questions]]\ntype"));
+    assert_eq!(quizzes[1].index, 1);
+
+    let ids = IdSet::default();
+    let config = ValidationConfig::default();
+    assert!(!diagnose_inline(&quizzes[0], &ids, &config).is_fatal());
+
+    let report = diagnose_inline(&quizzes[1], &ids, &config);
+    let [diagnostic] = report.diagnostics() else {
+      panic!("expected one diagnostic, got {report:?}")
+    };
+    assert_eq!(diagnostic.rule.as_deref(), Some("quiz::parse"));
+    assert_eq!(diagnostic.file, path);
+    // toml reports type errors at the start of the table.
+    assert_eq!(diagnostic.span.as_ref().unwrap().start.line, 15);
+  }
+
+  #[test]
+  fn replace_inline_quiz_contents() {
+    let chapter = "- Item:\n\n  ```quiz\n  a = 1\n\n  b = 2\n  ```\n";
+    let quizzes = InlineQuiz::find_all(Path::new("chapter.md"), chapter);
+    let (range, replacement) = quizzes[0].replace_contents("id = 0\na = 1\n\nb = 2\n");
+    let mut new_chapter = chapter.to_string();
+    new_chapter.replace_range(range, &replacement);
+    assert_eq!(
+      new_chapter,
+      "- Item:\n\n  ```quiz\n  id = 0\n  a = 1\n\n  b = 2\n  ```\n"
+    );
+
+    let chapter = "> ```quiz\n> a = 1\n> ```\n";
+    let quizzes = InlineQuiz::find_all(Path::new("chapter.md"), chapter);
+    let (range, replacement) = quizzes[0].replace_contents("a = 1\n\nb = 2\n");
+    assert_eq!(&chapter[range], "> a = 1\n");
+    assert_eq!(replacement, "> a = 1\n>\n> b = 2\n");
+  }
+}
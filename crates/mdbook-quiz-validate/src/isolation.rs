@@ -0,0 +1,221 @@
+//! Isolating quiz programs from the network and from files outside their working directory.
+//!
+//! On Linux, a program is started in a new network namespace, which has no interfaces besides
+//! an unconnected loopback, and is restricted with [Landlock](https://docs.kernel.org/userspace-api/landlock.html)
+//! to reading system libraries and devices and to reading and writing its working directory.
+//! Isolation is not available on other platforms.
+
+use anyhow::Result;
+use std::process::Command;
+
+/// Keeps the resources needed to isolate a command alive until it has been spawned.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) struct Isolation {
+  #[cfg(target_os = "linux")]
+  _ruleset: std::os::fd::OwnedFd,
+}
+
+#[cfg(target_os = "linux")]
+mod landlock {
+  pub const CREATE_RULESET_VERSION: u32 = 1 << 0;
+  pub const RULE_PATH_BENEATH: libc::c_int = 1;
+
+  pub const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+  pub const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+  pub const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+  pub const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+  pub const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+
+  pub const ACCESS_NET_BIND_TCP: u64 = 1 << 0;
+  pub const ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+
+  pub const SCOPE_ABSTRACT_UNIX_SOCKET: u64 = 1 << 0;
+  pub const SCOPE_SIGNAL: u64 = 1 << 1;
+
+  /// `struct landlock_ruleset_attr`, whose size depends on the ABI version.
+  #[repr(C)]
+  pub struct RulesetAttr {
+    pub handled_access_fs: u64,
+    pub handled_access_net: u64,
+    pub scoped: u64,
+  }
+
+  /// `struct landlock_path_beneath_attr`.
+  #[repr(C, packed)]
+  pub struct PathBeneathAttr {
+    pub allowed_access: u64,
+    pub parent_fd: i32,
+  }
+
+  /// Returns every filesystem access right known to the Landlock ABI `abi`.
+  pub fn all_access_fs(abi: i64) -> u64 {
+    let rights = match abi {
+      1 => 13,
+      2 => 14,
+      3 | 4 => 15,
+      _ => 16,
+    };
+    (1 << rights) - 1
+  }
+}
+
+/// Directories whose files programs may read and execute, such as the dynamic loader and libc.
+#[cfg(target_os = "linux")]
+const READABLE_DIRS: &[&str] = &[
+  "/bin", "/lib", "/lib32", "/lib64", "/proc", "/sbin", "/sys", "/usr",
+];
+
+/// Files that programs may read.
+#[cfg(target_os = "linux")]
+const READABLE_FILES: &[&str] = &[
+  "/dev/random",
+  "/dev/urandom",
+  "/dev/zero",
+  "/etc/ld.so.cache",
+  "/etc/localtime",
+];
+
+/// Allows the accesses in `allowed` beneath `path` in `ruleset`, if `path` exists.
+#[cfg(target_os = "linux")]
+fn add_rule(ruleset: &std::os::fd::OwnedFd, path: &std::path::Path, allowed: u64) -> Result<()> {
+  use std::os::{fd::AsRawFd, unix::fs::OpenOptionsExt};
+
+  let file = match std::fs::OpenOptions::new()
+    .read(true)
+    .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+    .open(path)
+  {
+    Ok(file) => file,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+    Err(e) => return Err(e.into()),
+  };
+  let attr = landlock::PathBeneathAttr {
+    allowed_access: allowed,
+    parent_fd: file.as_raw_fd(),
+  };
+  // SAFETY: `attr` is a valid `landlock_path_beneath_attr` that outlives the call.
+  let result = unsafe {
+    libc::syscall(
+      libc::SYS_landlock_add_rule,
+      ruleset.as_raw_fd(),
+      landlock::RULE_PATH_BENEATH,
+      &attr as *const landlock::PathBeneathAttr,
+      0,
+    )
+  };
+  anyhow::ensure!(
+    result == 0,
+    "Failed to allow access to {}: {}",
+    path.display(),
+    std::io::Error::last_os_error()
+  );
+  Ok(())
+}
+
+/// Makes `cmd` run without network access, and only able to write files in its working
+/// directory and read system files.
+///
+/// The returned [`Isolation`] must be kept until `cmd` is spawned.
+#[cfg(target_os = "linux")]
+pub(crate) fn isolate(cmd: &mut Command) -> Result<Isolation> {
+  use landlock::*;
+  use std::{
+    os::{
+      fd::{AsRawFd, FromRawFd, OwnedFd},
+      unix::process::CommandExt,
+    },
+    path::Path,
+    ptr,
+  };
+
+  // SAFETY: querying the ABI version doesn't read any memory.
+  let abi = unsafe {
+    libc::syscall(
+      libc::SYS_landlock_create_ruleset,
+      ptr::null::<RulesetAttr>(),
+      0,
+      CREATE_RULESET_VERSION,
+    )
+  };
+  anyhow::ensure!(
+    abi >= 1,
+    "Landlock is not supported by this kernel: {}",
+    std::io::Error::last_os_error()
+  );
+
+  let all_fs = all_access_fs(abi);
+  let attr = RulesetAttr {
+    handled_access_fs: all_fs,
+    handled_access_net: if abi >= 4 {
+      ACCESS_NET_BIND_TCP | ACCESS_NET_CONNECT_TCP
+    } else {
+      0
+    },
+    scoped: if abi >= 6 {
+      SCOPE_ABSTRACT_UNIX_SOCKET | SCOPE_SIGNAL
+    } else {
+      0
+    },
+  };
+  let size = match abi {
+    1..=3 => 8,
+    4 | 5 => 16,
+    _ => std::mem::size_of::<RulesetAttr>(),
+  };
+  // SAFETY: `attr` is a valid `landlock_ruleset_attr` of at least `size` bytes.
+  let fd = unsafe {
+    libc::syscall(
+      libc::SYS_landlock_create_ruleset,
+      &attr as *const RulesetAttr,
+      size,
+      0,
+    )
+  };
+  anyhow::ensure!(
+    fd >= 0,
+    "Failed to create Landlock ruleset: {}",
+    std::io::Error::last_os_error()
+  );
+  // SAFETY: `fd` is a new file descriptor that nothing else owns.
+  let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+  let read = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+  for dir in READABLE_DIRS {
+    add_rule(&ruleset, Path::new(dir), read)?;
+  }
+  for file in READABLE_FILES {
+    add_rule(&ruleset, Path::new(file), ACCESS_FS_READ_FILE)?;
+  }
+  let write_null = ACCESS_FS_READ_FILE | ACCESS_FS_WRITE_FILE | (ACCESS_FS_TRUNCATE & all_fs);
+  add_rule(&ruleset, Path::new("/dev/null"), write_null)?;
+  if let Some(dir) = cmd.get_current_dir() {
+    add_rule(&ruleset, dir, all_fs)?;
+  }
+
+  let ruleset_fd = ruleset.as_raw_fd();
+  // SAFETY: the closure only makes syscalls, which are async-signal-safe, and doesn't allocate.
+  unsafe {
+    cmd.pre_exec(move || {
+      // Creating a network namespace needs privileges, which an unprivileged user can get by
+      // also creating a user namespace.
+      if libc::unshare(libc::CLONE_NEWNET) != 0
+        && libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0
+      {
+        return Err(std::io::Error::last_os_error());
+      }
+      if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+        || libc::syscall(libc::SYS_landlock_restrict_self, ruleset_fd, 0) != 0
+      {
+        return Err(std::io::Error::last_os_error());
+      }
+      Ok(())
+    });
+  }
+
+  Ok(Isolation { _ruleset: ruleset })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn isolate(_cmd: &mut Command) -> Result<Isolation> {
+  anyhow::bail!("Quiz programs can only be isolated on Linux")
+}
@@ -6,6 +6,7 @@ use std::{
   cell::RefCell,
   collections::HashSet,
   fmt,
+  ops::Range,
   path::{Path, PathBuf},
   sync::{Arc, Mutex},
 };
@@ -16,18 +17,89 @@ use miette::{
 };
 use thiserror::Error;
 
-pub use spellcheck::register_more_words;
+pub use diagnostics::{Position, Severity, Span, ValidationDiagnostic, ValidationReport};
+pub use formats::QuizFormat;
+pub use hidden::visible_program;
+pub use ids::{content_fingerprint, quiz_key, IdStrategy};
+pub use inline::InlineQuiz;
+pub use limits::ResourceLimits;
+pub use markdown_quiz::{is_markdown_quiz, MarkdownQuiz, MarkdownQuizError};
+pub use migrate::{migrate, parse_quiz};
+pub use pools::{quiz_draws, Draw};
+pub use rust::RustSettings;
+pub use selection::{diagnose_selection, QuestionSelection, SelectionError};
+#[allow(deprecated)]
+pub use spellcheck::{register_more_words, DictionarySettings};
 pub use toml_spanned_value::SpannedValue;
 
+mod cache;
+mod diagnostics;
+mod formats;
+mod hidden;
+mod ids;
 mod impls;
+mod inline;
+mod isolation;
+mod limits;
+mod markdown_quiz;
+mod migrate;
+mod pools;
+mod rust;
+mod selection;
+mod source_map;
 mod spellcheck;
 
 /// A thread-safe mutable set of question identifiers.
 pub type IdSet = Arc<Mutex<HashSet<String>>>;
 
+/// Options that control which checks are run by [`validate_with_config`].
+#[derive(Debug, Clone, Default)]
+pub struct ValidationConfig {
+  /// If true, then run a spellchecker on all Markdown strings.
+  pub spellcheck: bool,
+
+  /// Which dictionaries the spellchecker uses.
+  pub dictionary: DictionarySettings,
+
+  /// If true, then compile and run the Rust code blocks in all Markdown strings,
+  /// honoring rustdoc's `ignore`, `no_run`, `should_panic`, and `compile_fail` attributes.
+  pub compile_code_blocks: bool,
+
+  /// If true, then every question must have an `id`.
+  pub require_ids: bool,
+
+  /// The strategy used to generate IDs in this book.
+  ///
+  /// If [`IdStrategy::UuidV5Content`], then questions whose content no longer matches
+  /// their ID are reported. Requires `book_root` to be set.
+  pub id_strategy: IdStrategy,
+
+  /// The root directory of the book containing the quiz.
+  pub book_root: Option<PathBuf>,
+
+  /// How to compile the Rust programs in quizzes.
+  pub rust: RustSettings,
+
+  /// Limits on running the Rust programs in quizzes.
+  pub limits: ResourceLimits,
+
+  /// A directory for caching the results of compiling and running programs across runs.
+  ///
+  /// Results are always cached in memory for the lifetime of the process.
+  pub cache_dir: Option<PathBuf>,
+}
+
 struct QuizDiagnostic {
   error: miette::Error,
   fatal: bool,
+  question: Option<QuestionRef>,
+}
+
+/// The question being validated when a diagnostic was emitted.
+#[derive(Clone)]
+pub(crate) struct QuestionRef {
+  index: usize,
+  id: Option<String>,
 }
 
 pub(crate) struct ValidationContext {
@@ -35,17 +107,25 @@ pub(crate) struct ValidationContext {
   path: PathBuf,
   contents: String,
   ids: IdSet,
-  spellcheck: bool,
+  config: ValidationConfig,
+  question: Option<QuestionRef>,
+  /// For a quiz not in a TOML file, how to move diagnostics about `contents` back to the file.
+  source_map: Option<source_map::SourceMap>,
+  /// Identifies the quiz when generating IDs, if it isn't the only quiz at `path`.
+  quiz_key: Option<String>,
 }
 
 impl ValidationContext {
-  pub fn new(path: &Path, contents: &str, ids: IdSet, spellcheck: bool) -> Self {
+  pub fn new(path: &Path, contents: &str, ids: IdSet, config: &ValidationConfig) -> Self {
     ValidationContext {
       diagnostics: Default::default(),
       path: path.to_owned(),
       contents: contents.to_owned(),
       ids,
-      spellcheck,
+      config: config.clone(),
+      question: None,
+      source_map: None,
+      quiz_key: None,
     }
   }
 
@@ -53,9 +133,22 @@ impl ValidationContext {
     self.diagnostics.borrow_mut().push(QuizDiagnostic {
       error: err.into(),
       fatal,
+      question: self.question.clone(),
+    });
+  }
+
+  /// Attributes all subsequent diagnostics to the question at `index`.
+  pub fn set_question(&mut self, index: usize, id: Option<&str>) {
+    self.question = Some(QuestionRef {
+      index,
+      id: id.map(String::from),
     });
   }
 
+  pub fn clear_question(&mut self) {
+    self.question = None;
+  }
+
   pub fn error(&mut self, err: impl Into<miette::Error>) {
     self.add_diagnostic(err, true);
   }
@@ -74,6 +167,7 @@ impl ValidationContext {
     let new_id = self.ids.lock().unwrap().insert(id.to_string());
     if !new_id {
       self.error(miette!(
+        code = "quiz::duplicate_id",
         labels = vec![value.labeled_span()],
         "Duplicate ID: {id}"
       ));
@@ -83,17 +177,55 @@ impl ValidationContext {
   pub fn contents(&self) -> &str {
     &self.contents
   }
+
+  /// The key used to generate IDs for the quiz in the book at `book_root`.
+  pub fn quiz_key(&self, book_root: &Path) -> String {
+    match &self.quiz_key {
+      Some(quiz_key) => quiz_key.clone(),
+      None => ids::quiz_key(book_root, &self.path),
+    }
+  }
 }
 
-impl fmt::Debug for ValidationContext {
-  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    let handler = MietteHandler::default();
-    for diagnostic in self.diagnostics.borrow_mut().drain(..) {
-      let src = NamedSource::new(self.path.to_string_lossy(), self.contents.clone());
-      let report = diagnostic.error.with_source_code(src);
-      handler.debug(report.as_ref(), f)?;
+impl ValidationContext {
+  /// Converts the collected diagnostics into a [`ValidationReport`], rendering each one
+  /// against the quiz source.
+  fn finish(self) -> ValidationReport {
+    struct Render<'a>(&'a miette::Report);
+    impl fmt::Display for Render<'_> {
+      fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        MietteHandler::default().debug(self.0.as_ref(), f)
+      }
     }
-    Ok(())
+
+    let source = match &self.source_map {
+      Some(source_map) => source_map.source(),
+      None => &self.contents,
+    };
+    let mut rendered = String::new();
+    let diagnostics = self
+      .diagnostics
+      .into_inner()
+      .into_iter()
+      .map(|diagnostic| {
+        let error = match &self.source_map {
+          Some(source_map) => miette::Report::new(source_map.remap(&*diagnostic.error)),
+          None => diagnostic.error,
+        };
+        let structured = ValidationDiagnostic::new(
+          &*error,
+          diagnostic.fatal,
+          &self.path,
+          source,
+          diagnostic.question,
+        );
+        let src = NamedSource::new(self.path.to_string_lossy(), source.to_string());
+        let report = error.with_source_code(src);
+        rendered.push_str(&Render(&report).to_string());
+        structured
+      })
+      .collect();
+    ValidationReport::new(diagnostics, rendered)
   }
 }
 
@@ -140,48 +272,221 @@ impl SpannedValueExt for SpannedValue {
 }
 
 #[derive(Error, Diagnostic, Debug)]
-#[error("TOML parse error: {cause}")]
-struct ParseError {
+#[error("{format} parse error: {cause}")]
+#[diagnostic(code(quiz::parse))]
+pub(crate) struct ParseError {
+  format: &'static str,
   cause: String,
 
   #[label]
   span: Option<SourceSpan>,
 }
 
-/// Runs validation on a quiz with TOML-format `contents` at `path` under the ID set `ids`.
-pub fn validate(path: &Path, contents: &str, ids: &IdSet, spellcheck: bool) -> anyhow::Result<()> {
-  let mut cx = ValidationContext::new(path, contents, Arc::clone(ids), spellcheck);
+#[derive(Error, Diagnostic, Debug)]
+#[error(
+  "Quiz uses version {version} of the quiz format, but the latest version is {}",
+  CURRENT_VERSION
+)]
+#[diagnostic(
+  code(quiz::outdated_version),
+  help(
+    "run `mdbook-quiz migrate` to upgrade it. The diagnostics below refer to the upgraded quiz."
+  )
+)]
+struct OutdatedVersion {
+  version: u32,
+}
 
-  let parse_result = toml::from_str::<Quiz>(contents);
+/// Runs validation on a quiz with `contents` at `path` under the ID set `ids`,
+/// returning every diagnostic rather than printing them.
+///
+/// The quiz is read in the [`QuizFormat`] of `path`.
+pub fn diagnose(
+  path: &Path,
+  contents: &str,
+  ids: &IdSet,
+  config: &ValidationConfig,
+) -> ValidationReport {
+  match QuizFormat::from_path(path).convert(contents) {
+    Ok(None) => diagnose_toml(ValidationContext::new(
+      path,
+      contents,
+      Arc::clone(ids),
+      config,
+    )),
+    Ok(Some((toml, source_map))) => {
+      let mut cx = ValidationContext::new(path, &toml, Arc::clone(ids), config);
+      cx.source_map = Some(source_map);
+      diagnose_toml(cx)
+    }
+    Err(error) => {
+      let mut cx = ValidationContext::new(path, contents, Arc::clone(ids), config);
+      cx.error(miette::Report::new_boxed(error));
+      cx.finish()
+    }
+  }
+}
+
+/// Runs validation on the TOML-format contents of `cx`.
+fn diagnose_toml(mut cx: ValidationContext) -> ValidationReport {
+  let contents = cx.contents.clone();
+  let contents = contents.as_str();
+  let parse_result =
+    parse_quiz(contents).and_then(|(quiz, version)| Ok((quiz, version, toml::from_str(contents)?)));
   match parse_result {
-    Ok(quiz) => {
-      let value: SpannedValue = toml::from_str(contents)?;
-      quiz.validate(&mut cx, &value)
+    Ok((quiz, CURRENT_VERSION, value)) => quiz.validate(&mut cx, &value),
+    Ok((_, version, _)) => {
+      // Spans in an older quiz don't line up with the current schema, so validate the
+      // migrated quiz instead.
+      cx.warning(OutdatedVersion { version });
+      match migrate(contents) {
+        Ok(migrated) => {
+          let migrated = migrated.as_deref().unwrap_or(contents);
+          let mut migrated_cx =
+            ValidationContext::new(&cx.path, migrated, Arc::clone(&cx.ids), &cx.config);
+          migrated_cx.quiz_key = cx.quiz_key.clone();
+          let mut report = cx.finish();
+          report.extend(diagnose_toml(migrated_cx));
+          return report;
+        }
+        Err(e) => cx.error(miette!("Failed to migrate quiz: {e}")),
+      }
     }
     Err(parse_err) => {
+      // toml only reports the position of an error as a (line, column) pair.
+      let span = parse_err.line_col().map(|(line, col)| {
+        let line_start: usize = contents
+          .split_inclusive('\n')
+          .take(line)
+          .map(str::len)
+          .sum();
+        SourceSpan::from((line_start + col, 0))
+      });
+      let mut cause = format!("{parse_err}");
+      if cx.source_map.is_some() {
+        // The position is relative to the TOML rather than the file, and the span says it anyway.
+        if let Some(position) = cause.rfind(" at line ") {
+          cause.truncate(position);
+        }
+      }
+      let format = cx
+        .source_map
+        .as_ref()
+        .map_or("TOML", |source_map| source_map.format());
       let error = ParseError {
-        cause: format!("{parse_err}"),
-        span: None,
+        format,
+        cause,
+        span,
       };
       cx.error(error);
     }
   }
 
-  let has_diagnostic = cx.diagnostics.borrow().len() > 0;
-  let is_fatal = cx.diagnostics.borrow().iter().any(|d| d.fatal);
+  cx.finish()
+}
 
-  if has_diagnostic {
-    eprintln!("{cx:?}");
+/// Runs validation on a quiz written in a code block of a chapter under the ID set `ids`,
+/// returning every diagnostic rather than printing them.
+pub fn diagnose_inline(
+  quiz: &InlineQuiz,
+  ids: &IdSet,
+  config: &ValidationConfig,
+) -> ValidationReport {
+  let mut cx = ValidationContext::new(quiz.chapter_path, &quiz.contents, Arc::clone(ids), config);
+  cx.source_map = Some(quiz.source_map());
+  cx.quiz_key = config
+    .book_root
+    .as_ref()
+    .map(|book_root| quiz.quiz_key(book_root));
+  diagnose_toml(cx)
+}
+
+/// Compiles and runs the programs in a quiz with `contents` at `path` ahead of validating it.
+///
+/// Validation is deterministic only when quizzes are validated in order, but preparing them
+/// can be done in parallel, after which validation reuses the cached results.
+pub fn prepare(path: &Path, contents: &str, config: &ValidationConfig) {
+  let quiz = match QuizFormat::from_path(path).convert(contents) {
+    Ok(None) => parse_quiz(contents).ok(),
+    Ok(Some((toml, _))) => parse_quiz(&toml).ok(),
+    Err(_) => None,
+  };
+  if let Some((quiz, _)) = quiz {
+    impls::prefetch(&quiz, config);
   }
+}
+
+/// Runs validation on a quiz with `contents` at `path` under the ID set `ids`, see [`diagnose`].
+///
+/// Diagnostics are printed to stderr, and an error is returned if any of them are fatal.
+pub fn validate_with_config(
+  path: &Path,
+  contents: &str,
+  ids: &IdSet,
+  config: &ValidationConfig,
+) -> anyhow::Result<()> {
+  print_report(path, diagnose(path, contents, ids, config))
+}
+
+/// Runs validation on a quiz with `contents` at `path` under the ID set `ids`, with every
+/// other option left at its default.
+#[deprecated(note = "use `validate_with_config` instead")]
+pub fn validate(path: &Path, contents: &str, ids: &IdSet, spellcheck: bool) -> anyhow::Result<()> {
+  let config = ValidationConfig {
+    spellcheck,
+    ..Default::default()
+  };
+  validate_with_config(path, contents, ids, &config)
+}
 
-  anyhow::ensure!(!is_fatal, "Quiz failed to validate: {}", path.display());
+/// Runs validation on a quiz written in a code block of a chapter under the ID set `ids`,
+/// see [`validate_with_config`].
+pub fn validate_inline(
+  quiz: &InlineQuiz,
+  ids: &IdSet,
+  config: &ValidationConfig,
+) -> anyhow::Result<()> {
+  print_report(quiz.chapter_path, diagnose_inline(quiz, ids, config))
+}
+
+/// Runs validation on the arguments in `args` of a `{{#quiz}}` directive in a chapter,
+/// see [`diagnose_selection`].
+///
+/// Diagnostics are printed to stderr, and an error is returned if any of them are fatal.
+pub fn validate_selection(
+  chapter_path: &Path,
+  chapter: &str,
+  args: Range<usize>,
+  quiz: &Quiz,
+) -> anyhow::Result<()> {
+  print_report(
+    chapter_path,
+    diagnose_selection(chapter_path, chapter, args, quiz),
+  )
+}
+
+/// Prints the diagnostics in `report` to stderr, returning an error if any of them are fatal.
+fn print_report(path: &Path, report: ValidationReport) -> anyhow::Result<()> {
+  if !report.diagnostics().is_empty() {
+    eprintln!("{report:?}");
+  }
+
+  anyhow::ensure!(
+    !report.is_fatal(),
+    "Quiz failed to validate: {}",
+    path.display()
+  );
 
   Ok(())
 }
 
 #[cfg(test)]
 pub(crate) fn harness(contents: &str) -> anyhow::Result<()> {
-  validate(Path::new("dummy.rs"), contents, &IdSet::default(), true)
+  let config = ValidationConfig {
+    spellcheck: true,
+    ..Default::default()
+  };
+  validate_with_config(Path::new("dummy.rs"), contents, &IdSet::default(), &config)
 }
 
 #[cfg(test)]
@@ -199,4 +504,45 @@ prompt.distractors = [""]
     "#;
     assert!(harness(contents).is_err());
   }
+
+  #[test]
+  fn validate_outdated_version() {
+    let contents = r#"
+[[questions]]
+type = "MultipleChoice"
+prompt.prompt = "What is 1 + 1?"
+prompt.choices = [
+  "1",
+  "2",
+  "3",
+]
+answer.answer = 1
+"#;
+    let report = diagnose(
+      Path::new("dummy.toml"),
+      contents,
+      &IdSet::default(),
+      &ValidationConfig::default(),
+    );
+    assert!(!report.is_fatal());
+    assert_eq!(report.diagnostics().len(), 1);
+    assert_eq!(
+      report.diagnostics()[0].rule.as_deref(),
+      Some("quiz::outdated_version")
+    );
+  }
+
+  #[test]
+  #[allow(deprecated)]
+  fn validate_with_spellcheck_flag() {
+    let contents = r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "Helo wrold"
+answer.answer = "a"
+"#;
+    let ids = IdSet::default();
+    assert!(validate(Path::new("dummy.toml"), contents, &ids, false).is_ok());
+    assert!(validate(Path::new("dummy.toml"), "[[questions]]", &ids, false).is_err());
+  }
 }
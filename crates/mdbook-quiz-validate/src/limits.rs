@@ -0,0 +1,358 @@
+//! Running quiz programs with limits on their time, output, and resources.
+//!
+//! These limits keep a buggy program from hanging or exhausting the machine during a build.
+//! Programs are also [isolated](crate::isolation) from the network and from the user's files,
+//! so that books with untrusted quizzes can be validated.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+  io::Read,
+  process::{Child, Command, ExitStatus, Stdio},
+  sync::mpsc,
+  thread,
+  time::{Duration, Instant},
+};
+
+use crate::isolation;
+
+/// Limits on executing quiz programs, set in `[preprocessor.quiz.limits]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ResourceLimits {
+  /// How long a program may run before it is killed, written in seconds.
+  #[serde(deserialize_with = "deserialize_secs")]
+  pub timeout: Duration,
+
+  /// The maximum number of bytes a program may write to each of stdout and stderr,
+  /// and to any file.
+  pub max_output: u64,
+
+  /// The maximum size of a program's address space in bytes. Only enforced on Unix.
+  pub max_memory: u64,
+
+  /// The maximum number of processes and threads a program may start. Only enforced on Unix,
+  /// where this counts every process of the user running the build.
+  pub max_processes: u64,
+
+  /// If true, programs can't use the network or access files outside their working directory,
+  /// and fail to run if that can't be enforced. Only supported on Linux.
+  pub isolate: bool,
+}
+
+impl Default for ResourceLimits {
+  fn default() -> Self {
+    ResourceLimits {
+      timeout: Duration::from_secs(10),
+      max_output: 1 << 20,
+      max_memory: 1 << 30,
+      max_processes: 4096,
+      isolate: true,
+    }
+  }
+}
+
+fn deserialize_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+  let secs = f64::deserialize(deserializer)?;
+  Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+}
+
+/// How a program finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Status {
+  /// The program exited successfully.
+  Success,
+
+  /// The program exited with an error, or was killed by a signal.
+  Failure,
+
+  /// The program ran for longer than the timeout and was killed.
+  TimedOut,
+
+  /// The program wrote more output than allowed and was killed.
+  OutputLimit,
+}
+
+/// The result of running a program.
+pub(crate) struct LimitedOutput {
+  pub(crate) status: Status,
+  /// The exit code, if the program exited by itself.
+  pub(crate) code: Option<i32>,
+  pub(crate) stdout: String,
+  pub(crate) stderr: String,
+}
+
+type Reader = mpsc::Receiver<std::io::Result<(Vec<u8>, bool)>>;
+
+/// Reads at most `limit` bytes from `pipe` on a new thread, sending the bytes and whether
+/// the pipe had more to read.
+///
+/// Closing the pipe early means that a program which keeps writing will fail with a broken pipe.
+fn read_limited(pipe: impl Read + Send + 'static, limit: u64) -> Reader {
+  let (tx, rx) = mpsc::channel();
+  thread::spawn(move || {
+    let mut buf = Vec::new();
+    let result = pipe.take(limit + 1).read_to_end(&mut buf).map(|_| {
+      let exceeded = buf.len() as u64 > limit;
+      buf.truncate(limit as usize);
+      (buf, exceeded)
+    });
+    let _ = tx.send(result);
+  });
+  rx
+}
+
+/// Kills every process in the process group that `child` leads, including any it started.
+///
+/// `child` must not have been reaped yet.
+#[cfg(unix)]
+fn kill_group(child: &Child) -> Result<()> {
+  // SAFETY: `killpg` has no memory-safety preconditions. The group's leader hasn't been reaped,
+  // so the group's ID can't have been reused by an unrelated group.
+  let result = unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) };
+  let error = std::io::Error::last_os_error();
+  anyhow::ensure!(
+    result == 0 || error.raw_os_error() == Some(libc::ESRCH),
+    "Failed to kill program: {error}"
+  );
+  Ok(())
+}
+
+#[cfg(not(unix))]
+fn kill_group(_child: &Child) -> Result<()> {
+  Ok(())
+}
+
+/// Returns true if `child` has exited, without reaping it.
+#[cfg(unix)]
+fn has_exited(child: &mut Child) -> Result<bool> {
+  // SAFETY: an all-zero `siginfo_t` is valid, and `waitid` only writes into `info`.
+  let mut info = unsafe { std::mem::zeroed::<libc::siginfo_t>() };
+  let options = libc::WEXITED | libc::WNOHANG | libc::WNOWAIT;
+  // SAFETY: `info` is a valid `siginfo_t` that outlives the call.
+  let result = unsafe { libc::waitid(libc::P_PID, child.id() as libc::id_t, &mut info, options) };
+  anyhow::ensure!(
+    result == 0,
+    "Failed to wait for program: {}",
+    std::io::Error::last_os_error()
+  );
+  // SAFETY: `waitid` succeeded, so `si_pid` is set, and is 0 if the child hasn't exited.
+  Ok(unsafe { info.si_pid() } != 0)
+}
+
+#[cfg(not(unix))]
+fn has_exited(child: &mut Child) -> Result<bool> {
+  Ok(child.try_wait()?.is_some())
+}
+
+/// Waits for `child` to exit, killing it once `deadline` has passed. Processes it started are
+/// killed either way, before `child` is reaped, so they can't keep running or hold its pipes open.
+///
+/// Returns `None` if the child was killed.
+fn wait_timeout(child: &mut Child, deadline: Instant) -> Result<Option<ExitStatus>> {
+  loop {
+    if has_exited(child)? {
+      kill_group(child)?;
+      return Ok(Some(child.wait()?));
+    }
+    if Instant::now() >= deadline {
+      kill_group(child)?;
+      child.kill()?;
+      child.wait()?;
+      return Ok(None);
+    }
+    thread::sleep(Duration::from_millis(10));
+  }
+}
+
+#[cfg(unix)]
+fn set_rlimits(cmd: &mut Command, settings: &ResourceLimits) {
+  use std::os::unix::process::CommandExt;
+
+  let cpu_secs = settings.timeout.as_secs() + 1;
+  let limits = [
+    (libc::RLIMIT_AS, settings.max_memory),
+    (libc::RLIMIT_FSIZE, settings.max_output),
+    (libc::RLIMIT_CPU, cpu_secs),
+    (libc::RLIMIT_CORE, 0),
+    (libc::RLIMIT_NPROC, settings.max_processes),
+  ];
+
+  // Limits can't be raised above the current hard limits without privileges.
+  let limits = limits.map(|(resource, limit)| {
+    let mut current = libc::rlimit {
+      rlim_cur: 0,
+      rlim_max: libc::RLIM_INFINITY,
+    };
+    // SAFETY: `current` is a valid `rlimit` for `getrlimit` to write into.
+    unsafe { libc::getrlimit(resource, &mut current) };
+    (resource, (limit as libc::rlim_t).min(current.rlim_max))
+  });
+
+  // SAFETY: the closure only calls `setrlimit`, which is async-signal-safe, and doesn't allocate.
+  unsafe {
+    cmd.pre_exec(move || {
+      for (resource, limit) in limits {
+        let rlimit = libc::rlimit {
+          rlim_cur: limit,
+          rlim_max: limit,
+        };
+        if libc::setrlimit(resource, &rlimit) != 0 {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
+      Ok(())
+    });
+  }
+}
+
+#[cfg(not(unix))]
+fn set_rlimits(_cmd: &mut Command, _settings: &ResourceLimits) {}
+
+/// Runs `cmd` under the limits in `settings`, with an empty environment.
+pub(crate) fn run(mut cmd: Command, settings: &ResourceLimits) -> Result<LimitedOutput> {
+  cmd
+    .env_clear()
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  set_rlimits(&mut cmd, settings);
+  #[cfg(unix)]
+  std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+
+  const ISOLATION_HELP: &str = "Failed to isolate program. Set `isolate = false` in \
+    `[preprocessor.quiz.limits]` to run programs without isolation";
+  let isolation = match settings.isolate {
+    true => Some(isolation::isolate(&mut cmd).context(ISOLATION_HELP)?),
+    false => None,
+  };
+
+  let deadline = Instant::now() + settings.timeout;
+  let spawned = cmd.spawn();
+  drop(isolation);
+  let mut child = match spawned {
+    Err(e) if settings.isolate => return Err(anyhow::Error::new(e).context(ISOLATION_HELP)),
+    spawned => spawned?,
+  };
+  let stdout = read_limited(child.stdout.take().unwrap(), settings.max_output);
+  let stderr = read_limited(child.stderr.take().unwrap(), settings.max_output);
+  let mut exit_status = wait_timeout(&mut child, deadline)?;
+
+  // A process that left the group can still hold the pipes open, so stop reading at the deadline.
+  let mut read = |reader: Reader| -> Result<(String, bool)> {
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    match reader.recv_timeout(timeout) {
+      Ok(result) => {
+        let (bytes, exceeded) = result?;
+        Ok((String::from_utf8_lossy(&bytes).into_owned(), exceeded))
+      }
+      Err(_) => {
+        exit_status = None;
+        Ok((String::new(), false))
+      }
+    }
+  };
+  let (stdout, stdout_exceeded) = read(stdout)?;
+  let (stderr, stderr_exceeded) = read(stderr)?;
+
+  let status = match exit_status {
+    None => Status::TimedOut,
+    Some(_) if stdout_exceeded || stderr_exceeded => Status::OutputLimit,
+    Some(status) if status.success() => Status::Success,
+    Some(_) => Status::Failure,
+  };
+  Ok(LimitedOutput {
+    status,
+    code: exit_status.and_then(|status| status.code()),
+    stdout,
+    stderr,
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[cfg(unix)]
+  #[test]
+  fn test_limits() -> Result<()> {
+    let settings = ResourceLimits {
+      timeout: Duration::from_millis(200),
+      max_output: 16,
+      ..Default::default()
+    };
+
+    let mut echo = Command::new("sh");
+    echo.args(["-c", "echo hello"]);
+    let output = run(echo, &settings)?;
+    assert_eq!(output.status, Status::Success);
+    assert_eq!(output.stdout, "hello\n");
+
+    let mut exit = Command::new("sh");
+    exit.args(["-c", "exit 3"]);
+    let output = run(exit, &settings)?;
+    assert_eq!(output.status, Status::Failure);
+    assert_eq!(output.code, Some(3));
+
+    let mut sleep = Command::new("sh");
+    sleep.args(["-c", "sleep 5"]);
+    assert_eq!(run(sleep, &settings)?.status, Status::TimedOut);
+
+    // Processes started by the program are killed with it, and don't hold its output open.
+    let start = Instant::now();
+    let mut background = Command::new("sh");
+    background.args(["-c", "sleep 5 & echo started"]);
+    let output = run(background, &settings)?;
+    assert_eq!(output.status, Status::Success);
+    assert_eq!(output.stdout, "started\n");
+
+    let mut nested = Command::new("sh");
+    nested.args(["-c", "sh -c 'sleep 5'; echo done"]);
+    assert_eq!(run(nested, &settings)?.status, Status::TimedOut);
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    let mut yes = Command::new("sh");
+    yes.args(["-c", "while true; do echo y; done"]);
+    let output = run(yes, &settings)?;
+    assert_eq!(output.status, Status::OutputLimit);
+    assert_eq!(output.stdout.len(), 16);
+
+    Ok(())
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn test_isolation() -> Result<()> {
+    let settings = ResourceLimits::default();
+    let dir = tempfile::tempdir()?;
+    let outside = tempfile::tempdir()?;
+    std::fs::write(outside.path().join("secret"), "secret")?;
+    let sh = |script: String| {
+      let mut cmd = Command::new("sh");
+      cmd.args(["-c", &script]).current_dir(dir.path());
+      run(cmd, &settings)
+    };
+
+    let output = sh("echo hello > out && cat out && echo > /dev/null".into())?;
+    assert_eq!(output.status, Status::Success);
+    assert_eq!(output.stdout, "hello\n");
+
+    let outside = outside.path().display();
+    let output = sh(format!("echo hello > {outside}/out"))?;
+    assert_eq!(output.status, Status::Failure);
+    let output = sh(format!("cat {outside}/secret"))?;
+    assert_eq!(output.status, Status::Failure);
+    assert_eq!(output.stdout, "");
+
+    // The only network interface is loopback.
+    let output = sh("cat /proc/net/dev".into())?;
+    let interfaces = output
+      .stdout
+      .lines()
+      .filter_map(|line| Some(line.split_once(':')?.0.trim()))
+      .collect::<Vec<_>>();
+    assert_eq!(interfaces, ["lo"]);
+
+    Ok(())
+  }
+}
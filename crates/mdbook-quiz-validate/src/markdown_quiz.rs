@@ -0,0 +1,736 @@
+//! The Markdown quiz format, an alternative to writing quizzes in TOML.
+//!
+//! A `.quiz.md` file is converted into the TOML format before it is validated, along with a
+//! [`SourceMap`] that moves the labels of each diagnostic back to the Markdown.
+
+use std::{ops::Range, path::Path, sync::OnceLock};
+
+use markdown::{
+  mdast::{Code, Heading, ListItem, Node},
+  Constructs, ParseOptions,
+};
+use mdbook_quiz_schema::Quiz;
+use miette::{Diagnostic, SourceSpan};
+use regex::Regex;
+use thiserror::Error;
+use toml_spanned_value::{spanned_value::ValueKind, SpannedValue};
+
+use crate::source_map::{source_offset, Kind, SourceMap, Table, Value};
+
+/// The question types that can be written as a heading.
+const QUESTION_TYPES: [&str; 8] = [
+  "ShortAnswer",
+  "Tracing",
+  "MultipleChoice",
+  "FillInTheBlank",
+  "Ordering",
+  "Matching",
+  "CodeExercise",
+  "Numeric",
+];
+
+/// Returns true if `path` is a quiz in the Markdown quiz format, i.e. its name ends in `.quiz.md`.
+pub fn is_markdown_quiz(path: &Path) -> bool {
+  path
+    .file_name()
+    .and_then(|name| name.to_str())
+    .is_some_and(|name| name.ends_with(".quiz.md"))
+}
+
+/// A mistake in the structure of a Markdown quiz.
+#[derive(Error, Diagnostic, Debug)]
+#[error("Markdown quiz error: {message}")]
+#[diagnostic(code(quiz::parse))]
+pub struct MarkdownQuizError {
+  message: String,
+
+  #[label]
+  span: SourceSpan,
+}
+
+fn error(message: impl Into<String>, span: Range<usize>) -> MarkdownQuizError {
+  MarkdownQuizError {
+    message: message.into(),
+    span: span.into(),
+  }
+}
+
+type Result<T, E = MarkdownQuizError> = std::result::Result<T, E>;
+
+/// A quiz written in the Markdown quiz format, converted into the TOML format.
+///
+/// Each question starts with a `#` heading naming its type, optionally followed by an ID
+/// like `# MultipleChoice {#my-id}` and a fingerprint like `{#my-id fingerprint=0a1b...}`.
+/// The blocks under the heading are read as:
+///
+/// * a ```` ```toml ```` block right after the heading: any other fields of the question,
+///   e.g. `answer.lineNumber = 3`;
+/// * a blockquote at the end: the `context`;
+/// * a task list at the end (before the context): the answers of a MultipleChoice,
+///   ShortAnswer, or Ordering question, where checked items are correct;
+/// * ```` ```rust ```` blocks: the program of a Tracing question, or with `file=<path>`, one of
+///   its `files`. A ```` ```stdout ```` block is its expected output. The ```` ```rust starter ````,
+///   ```` ```rust solution ````, and ```` ```rust tests ```` blocks of a CodeExercise question;
+/// * everything else: the prompt.
+///
+/// Fields of the quiz itself, like `spellcheck`, go in `+++`-delimited TOML front matter.
+pub struct MarkdownQuiz {
+  toml: String,
+  source_map: SourceMap,
+  id_positions: Vec<Option<usize>>,
+}
+
+impl MarkdownQuiz {
+  /// Parses a quiz with Markdown-format `contents`.
+  pub fn parse(contents: &str) -> Result<Self> {
+    Parser { source: contents }.parse()
+  }
+
+  /// The quiz in the TOML format.
+  pub fn toml(&self) -> &str {
+    &self.toml
+  }
+
+  /// Deserializes the quiz.
+  pub fn quiz(&self) -> Result<Quiz, toml::de::Error> {
+    toml::from_str(&self.toml)
+  }
+
+  /// Adds `ids[i]` to the heading of the `i`th question if it doesn't have an ID, along with
+  /// `fingerprints[i]` if given.
+  ///
+  /// Returns the new contents of the quiz if any IDs were added.
+  pub fn insert_ids(
+    &self,
+    contents: &str,
+    ids: &[String],
+    fingerprints: Option<&[String]>,
+  ) -> Option<String> {
+    let mut new_contents = contents.to_string();
+    let mut changed = false;
+    for (i, (position, id)) in self.id_positions.iter().zip(ids).enumerate().rev() {
+      if let Some(position) = position {
+        let attribute = match fingerprints {
+          Some(fingerprints) => format!(" {{#{id} fingerprint={}}}", fingerprints[i]),
+          None => format!(" {{#{id}}}"),
+        };
+        new_contents.insert_str(*position, &attribute);
+        changed = true;
+      }
+    }
+    changed.then_some(new_contents)
+  }
+
+  pub(crate) fn into_source_map(self) -> SourceMap {
+    self.source_map
+  }
+}
+
+/// Returns the range of the Markdown covered by `node`.
+fn node_span(node: &Node) -> Range<usize> {
+  node
+    .position()
+    .map_or(0..0, |pos| pos.start.offset..pos.end.offset)
+}
+
+/// Returns the range covered by `nodes`, or an empty range at `default` if there are none.
+fn nodes_span(nodes: &[&Node], default: usize) -> Range<usize> {
+  match (nodes.first(), nodes.last()) {
+    (Some(first), Some(last)) => node_span(first).start..node_span(last).end,
+    _ => default..default,
+  }
+}
+
+/// Returns true if `node` is a list whose items are all task list items.
+fn is_task_list(node: &Node) -> bool {
+  match node {
+    Node::List(list) => list.children.iter().all(|item| {
+      matches!(
+        item,
+        Node::ListItem(ListItem {
+          checked: Some(_),
+          ..
+        })
+      )
+    }),
+    _ => false,
+  }
+}
+
+struct Parser<'a> {
+  source: &'a str,
+}
+
+/// The parts of a question's section in the quiz.
+struct Section<'a> {
+  heading: &'a Heading,
+  blocks: Vec<&'a Node>,
+}
+
+impl<'a> Parser<'a> {
+  fn parse(&self) -> Result<MarkdownQuiz> {
+    let options = ParseOptions {
+      constructs: Constructs {
+        frontmatter: true,
+        ..Constructs::gfm()
+      },
+      ..ParseOptions::gfm()
+    };
+    let root = markdown::to_mdast(self.source, &options).map_err(|e| error(e.to_string(), 0..0))?;
+
+    let mut front_matter = Table::new();
+    let mut sections: Vec<Section> = Vec::new();
+    for node in root.children().unwrap() {
+      match (node, sections.last_mut()) {
+        (Node::Heading(heading), _) if heading.depth == 1 => sections.push(Section {
+          heading,
+          blocks: Vec::new(),
+        }),
+        (_, Some(section)) => section.blocks.push(node),
+        (Node::Toml(toml), None) => {
+          let start = node_span(node).start;
+          let lines_start = self.line_end(start);
+          let block = Value::text(
+            self.source,
+            lines_start,
+            node_span(node),
+            toml.value.clone(),
+          );
+          front_matter = self.metadata(&block)?;
+          if let Some((key, value)) = front_matter
+            .iter()
+            .find(|(key, _)| key == "version" || key == "questions")
+          {
+            return Err(error(
+              format!("`{key}` cannot be set in the front matter of a Markdown quiz"),
+              value.span.clone(),
+            ));
+          }
+        }
+        (Node::Yaml(_), None) => {
+          return Err(error(
+            "Front matter must be TOML delimited by `+++`",
+            node_span(node),
+          ))
+        }
+        (Node::Html(_), None) => {}
+        (_, None) => {
+          return Err(error(
+            "Expected a question, which starts with a heading like `# MultipleChoice`",
+            node_span(node),
+          ))
+        }
+      }
+    }
+
+    let mut questions = Vec::new();
+    let mut id_positions = Vec::new();
+    for section in &sections {
+      let (question, id_position) = self.question(section)?;
+      questions.push(question);
+      id_positions.push(id_position);
+    }
+    let mut quiz = front_matter;
+    quiz.push(("questions".into(), Value::array(questions, 0..0)));
+
+    let (toml, source_map) = SourceMap::generate(self.source, "Markdown quiz", &quiz);
+    Ok(MarkdownQuiz {
+      toml,
+      source_map,
+      id_positions,
+    })
+  }
+
+  /// Returns the offset of the start of the line after `offset`.
+  fn line_end(&self, offset: usize) -> usize {
+    match self.source[offset..].find('\n') {
+      Some(i) => offset + i + 1,
+      None => self.source.len(),
+    }
+  }
+
+  /// Reads the lines in `range`, removing the prefix of every line but the first with `strip`.
+  fn dedent(&self, range: Range<usize>, strip: impl Fn(&str) -> &str) -> Value {
+    let text = self.source[range.clone()]
+      .lines()
+      .enumerate()
+      .map(|(i, line)| if i == 0 { line } else { strip(line) })
+      .collect::<Vec<_>>()
+      .join("\n");
+    Value::text(self.source, range.start, range, text)
+  }
+
+  fn code(&self, node: &Node, code: &Code) -> Value {
+    let span = node_span(node);
+    let lines_start = self.line_end(span.start);
+    Value::text(self.source, lines_start, span, code.value.clone())
+  }
+
+  /// The contents of a list item, without the indentation of its later lines.
+  fn item_text(&self, item: &Node) -> Value {
+    let children = item.children().unwrap().iter().collect::<Vec<_>>();
+    let range = nodes_span(&children, node_span(item).end);
+    let line_start = self.source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let indent = range.start - line_start;
+    self.dedent(range, |line| {
+      let spaces = line.len() - line.trim_start_matches(' ').len();
+      &line[spaces.min(indent)..]
+    })
+  }
+
+  /// Parses a metadata block into a table.
+  fn metadata(&self, block: &Value) -> Result<Table> {
+    let Kind::String(text, lines) = &block.kind else {
+      unreachable!()
+    };
+    let value = toml::from_str::<SpannedValue>(text).map_err(|e| {
+      let offset = e.line_col().map_or(0, |(line, col)| {
+        text
+          .split_inclusive('\n')
+          .take(line)
+          .map(str::len)
+          .sum::<usize>()
+          + col
+      });
+      let offset = source_offset(lines, offset);
+      error(format!("Invalid TOML: {e}"), offset..offset)
+    })?;
+
+    fn convert(value: &SpannedValue, lines: &[(usize, usize)]) -> Value {
+      let span = source_offset(lines, value.start())..source_offset(lines, value.end());
+      match value.get_ref() {
+        ValueKind::String(s) => Value {
+          kind: Kind::String(s.clone(), vec![(0, span.start + 1)]),
+          span,
+        },
+        ValueKind::Integer(n) => Value::scalar(*n, span),
+        ValueKind::Float(f) => Value::scalar(*f, span),
+        ValueKind::Boolean(b) => Value::scalar(*b, span),
+        ValueKind::Datetime(d) => Value::scalar(toml::Value::Datetime(d.clone()), span),
+        ValueKind::Array(items) => Value::array(
+          items.iter().map(|item| convert(item, lines)).collect(),
+          span,
+        ),
+        ValueKind::Table(entries) => Value::table(
+          entries
+            .iter()
+            .map(|(key, value)| (key.get_ref().clone(), convert(value, lines)))
+            .collect(),
+          span,
+        ),
+      }
+    }
+
+    match convert(&value, lines).kind {
+      Kind::Table(entries) => Ok(entries),
+      _ => unreachable!("TOML documents are tables"),
+    }
+  }
+
+  /// Converts a question's section into a table, also returning where an ID could be added
+  /// to the heading if it doesn't have one.
+  fn question(&self, section: &Section) -> Result<(Value, Option<usize>)> {
+    static HEADING: OnceLock<Regex> = OnceLock::new();
+    let heading_regex = HEADING.get_or_init(|| {
+      Regex::new(r"^(\w+)(?:\s+\{#([^}\s]+)(?:\s+fingerprint=([^}\s]+))?\})?\s*$").unwrap()
+    });
+
+    let heading_span = section
+      .heading
+      .position
+      .as_ref()
+      .map_or(0..0, |pos| pos.start.offset..pos.end.offset);
+    let children = section.heading.children.iter().collect::<Vec<_>>();
+    let text_span = nodes_span(&children, heading_span.end);
+    let heading_text = &self.source[text_span.clone()];
+    let captures = heading_regex
+      .captures(heading_text)
+      .filter(|captures| QUESTION_TYPES.contains(&&captures[1]))
+      .ok_or_else(|| {
+        error(
+          format!(
+            "Question headings must be a question type, one of: {}",
+            QUESTION_TYPES.join(", ")
+          ),
+          text_span.clone(),
+        )
+      })?;
+
+    let ty = captures.get(1).unwrap();
+    let mut question = vec![(
+      "type".to_string(),
+      Value::text(
+        self.source,
+        text_span.start + ty.start(),
+        heading_span.clone(),
+        ty.as_str().to_string(),
+      ),
+    )];
+    if let Some(id) = captures.get(2) {
+      let span = text_span.start + id.start()..text_span.start + id.end();
+      question.push((
+        "id".to_string(),
+        Value::text(self.source, span.start, span, id.as_str().to_string()),
+      ));
+    }
+    if let Some(fingerprint) = captures.get(3) {
+      let span = text_span.start + fingerprint.start()..text_span.start + fingerprint.end();
+      question.push((
+        "fingerprint".to_string(),
+        Value::text(
+          self.source,
+          span.start,
+          span,
+          fingerprint.as_str().to_string(),
+        ),
+      ));
+    }
+
+    let mut blocks = section.blocks.as_slice();
+    let end = blocks
+      .last()
+      .map_or(heading_span.end, |node| node_span(node).end);
+    let span = heading_span.start..end;
+
+    let mut metadata = None;
+    if let [node @ Node::Code(code), rest @ ..] = blocks {
+      if code.lang.as_deref() == Some("toml") {
+        metadata = Some(self.metadata(&self.code(node, code))?);
+        blocks = rest;
+      }
+    }
+
+    let mut context = None;
+    if let [rest @ .., node @ Node::BlockQuote(_)] = blocks {
+      let children = node.children().unwrap().iter().collect::<Vec<_>>();
+      let range = nodes_span(&children, node_span(node).end);
+      context = Some(self.dedent(range, |line| {
+        let line = line.trim_start();
+        let line = line.strip_prefix('>').unwrap_or(line);
+        line.strip_prefix(' ').unwrap_or(line)
+      }));
+      blocks = rest;
+    }
+
+    let mut prompt = Table::new();
+    let mut answer = Table::new();
+    match ty.as_str() {
+      "Tracing" => {
+        let mut files = Table::new();
+        for node in blocks {
+          let Node::Code(code) = node else {
+            return Err(error(
+              "Tracing questions can only contain code blocks, metadata, and context",
+              node_span(node),
+            ));
+          };
+          let value = self.code(node, code);
+          let file = code
+            .meta
+            .iter()
+            .flat_map(|meta| meta.split_whitespace())
+            .find_map(|word| word.strip_prefix("file="));
+          match (code.lang.as_deref(), file) {
+            (Some("stdout"), _) => {
+              push(
+                &mut answer,
+                "doesCompile",
+                Value::scalar(true, value.span.clone()),
+              )?;
+              push(&mut answer, "stdout", value)?;
+            }
+            (_, Some(file)) => push(&mut files, file, value)?,
+            (_, None) => push(&mut prompt, "program", value)?,
+          }
+        }
+        if !files.is_empty() {
+          prompt.push(("files".into(), Value::table(files, span.clone())));
+        }
+      }
+      "CodeExercise" => {
+        let mut rest = Vec::new();
+        for node in blocks {
+          let field = match node {
+            Node::Code(code) => code
+              .meta
+              .iter()
+              .flat_map(|meta| meta.split_whitespace())
+              .find(|word| ["starter", "solution", "tests"].contains(word))
+              .map(|field| (field, code)),
+            _ => None,
+          };
+          match field {
+            Some(("starter", code)) => push(&mut prompt, "starter", self.code(node, code))?,
+            Some((field, code)) => push(&mut answer, field, self.code(node, code))?,
+            None => rest.push(*node),
+          }
+        }
+        prompt.insert(0, ("prompt".into(), self.prompt(&rest, heading_span.end)));
+      }
+      _ => {
+        let answers = matches!(ty.as_str(), "MultipleChoice" | "ShortAnswer" | "Ordering");
+        if let [rest @ .., list] = blocks {
+          if answers && is_task_list(list) {
+            self.answers(ty.as_str(), list, &mut prompt, &mut answer)?;
+            blocks = rest;
+          }
+        }
+        prompt.insert(0, ("prompt".into(), self.prompt(blocks, heading_span.end)));
+      }
+    }
+
+    if !prompt.is_empty() {
+      question.push(("prompt".into(), Value::table(prompt, span.clone())));
+    }
+    if !answer.is_empty() {
+      question.push(("answer".into(), Value::table(answer, span.clone())));
+    }
+    if let Some(context) = context {
+      question.push(("context".into(), context));
+    }
+    if let Some(metadata) = metadata {
+      merge(&mut question, metadata)?;
+    }
+
+    let has_id = question.iter().any(|(key, _)| key == "id");
+    let id_position = (!has_id).then_some(text_span.end);
+    Ok((Value::table(question, span), id_position))
+  }
+
+  /// The prompt made of `blocks`, which are copied verbatim.
+  fn prompt(&self, blocks: &[&Node], default: usize) -> Value {
+    let range = nodes_span(blocks, default);
+    let text = self.source[range.clone()].to_string();
+    Value::text(self.source, range.start, range, text)
+  }
+
+  /// Reads the answers of a question from a task list.
+  fn answers(&self, ty: &str, list: &Node, prompt: &mut Table, answer: &mut Table) -> Result<()> {
+    let span = node_span(list);
+    let (mut checked, mut unchecked) = (Vec::new(), Vec::new());
+    for item in list.children().unwrap() {
+      let Node::ListItem(ListItem {
+        checked: Some(is_checked),
+        ..
+      }) = item
+      else {
+        unreachable!()
+      };
+      let text = self.item_text(item);
+      if *is_checked {
+        checked.push(text);
+      } else {
+        unchecked.push((text, node_span(item)));
+      }
+    }
+
+    match ty {
+      "MultipleChoice" => {
+        let answers = match checked.len() {
+          1 => checked.remove(0),
+          _ => Value::array(checked, span.clone()),
+        };
+        answer.push(("answer".into(), answers));
+        let distractors = unchecked.into_iter().map(|(text, _)| text).collect();
+        prompt.push(("distractors".into(), Value::array(distractors, span)));
+      }
+      "ShortAnswer" => {
+        if let Some((_, item_span)) = unchecked.first() {
+          return Err(error(
+            "Every answer to a ShortAnswer question must be checked",
+            item_span.clone(),
+          ));
+        }
+        let mut answers = checked.into_iter();
+        if let Some(first) = answers.next() {
+          answer.push(("answer".into(), first));
+        }
+        let alternatives = answers.collect::<Vec<_>>();
+        if !alternatives.is_empty() {
+          answer.push(("alternatives".into(), Value::array(alternatives, span)));
+        }
+      }
+      "Ordering" => {
+        answer.push(("fragments".into(), Value::array(checked, span.clone())));
+        if !unchecked.is_empty() {
+          let distractors = unchecked.into_iter().map(|(text, _)| text).collect();
+          prompt.push(("distractors".into(), Value::array(distractors, span)));
+        }
+      }
+      _ => unreachable!(),
+    }
+    Ok(())
+  }
+}
+
+/// Adds `key` to `table`, which must not already contain it.
+fn push(table: &mut Table, key: &str, value: Value) -> Result<()> {
+  if table.iter().any(|(k, _)| k == key) {
+    return Err(error(
+      format!("`{key}` is given more than once"),
+      value.span,
+    ));
+  }
+  table.push((key.to_string(), value));
+  Ok(())
+}
+
+/// Adds the entries of `other` to `table`, merging nested tables.
+fn merge(table: &mut Table, other: Table) -> Result<()> {
+  for (key, value) in other {
+    match table.iter_mut().find(|(k, _)| *k == key) {
+      None => table.push((key, value)),
+      Some((_, existing)) => match (&mut existing.kind, value.kind) {
+        (Kind::Table(entries), Kind::Table(other)) => merge(entries, other)?,
+        _ => {
+          return Err(error(
+            format!("`{key}` is already given by the Markdown of the question"),
+            value.span,
+          ))
+        }
+      },
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{diagnose, IdSet, ValidationConfig};
+  use mdbook_quiz_schema::{MultipleChoiceAnswerFormat, Question};
+
+  const QUIZ: &str = r#"+++
+spellcheck.ignore = ["rustc"]
++++
+
+# MultipleChoice {#first}
+
+Which of these is a *keyword*?
+
+- [x] `let`
+- [ ] `var`
+- [ ] `const
+  fn`
+
+> Rust declares variables
+> with `let`.
+
+# ShortAnswer
+
+What is the name of the Rust compiler?
+
+- [x] rustc
+- [x] `rustc`
+
+# Tracing
+
+```toml
+answer.lineNumber = 2
+answer.doesCompile = false
+```
+
+```rust
+fn main() {
+  let x: i32 = "a";
+}
+```
+"#;
+
+  #[test]
+  fn test_parse_markdown_quiz() {
+    let quiz = MarkdownQuiz::parse(QUIZ).unwrap();
+    let parsed = quiz
+      .quiz()
+      .unwrap_or_else(|e| panic!("{e}\n{}", quiz.toml()));
+    assert_eq!(parsed.spellcheck.unwrap().ignore.unwrap(), ["rustc"]);
+
+    let [Question::MultipleChoice(mc), Question::ShortAnswer(sa), Question::Tracing(tr)] =
+      &parsed.questions[..]
+    else {
+      panic!("wrong questions: {:?}", parsed.questions)
+    };
+    assert_eq!(mc.0.id.as_deref(), Some("first"));
+    assert_eq!(mc.0.prompt.prompt.0, "Which of these is a *keyword*?");
+    let MultipleChoiceAnswerFormat::Single(answer) = &mc.0.answer.answer else {
+      panic!()
+    };
+    assert_eq!(answer.0, "`let`");
+    assert_eq!(mc.0.prompt.distractors[1].0, "`const\nfn`");
+    assert_eq!(
+      mc.0.context.as_ref().unwrap().0,
+      "Rust declares variables\nwith `let`."
+    );
+
+    assert_eq!(sa.0.answer.answer, "rustc");
+    assert_eq!(sa.0.answer.alternatives.as_ref().unwrap(), &["`rustc`"]);
+
+    assert_eq!(tr.0.prompt.program, "fn main() {\n  let x: i32 = \"a\";\n}");
+    assert!(!tr.0.answer.does_compile);
+    assert_eq!(tr.0.answer.line_number, Some(2));
+
+    let ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let with_ids = quiz.insert_ids(QUIZ, &ids, None).unwrap();
+    assert!(with_ids.contains("# MultipleChoice {#first}\n"));
+    assert!(with_ids.contains("# ShortAnswer {#b}\n"));
+    assert!(with_ids.contains("# Tracing {#c}\n"));
+
+    let fingerprints = vec!["0".repeat(32), "1".repeat(32), "2".repeat(32)];
+    let with_ids = quiz.insert_ids(QUIZ, &ids, Some(&fingerprints)).unwrap();
+    assert!(with_ids.contains("# MultipleChoice {#first}\n"));
+    assert!(with_ids.contains(&format!(
+      "# ShortAnswer {{#b fingerprint={}}}\n",
+      "1".repeat(32)
+    )));
+    let parsed = MarkdownQuiz::parse(&with_ids).unwrap().quiz().unwrap();
+    assert_eq!(
+      parsed.questions[2].fingerprint(),
+      Some("2".repeat(32).as_str())
+    );
+  }
+
+  #[test]
+  fn test_markdown_quiz_errors() {
+    let err = |contents: &str| MarkdownQuiz::parse(contents).err().unwrap().message;
+    assert!(err("Intro\n\n# ShortAnswer\n").contains("Expected a question"));
+    assert!(err("# Essay\n").contains("question type"));
+    assert!(err("# Tracing\n\nWhat?\n").contains("Tracing questions"));
+    assert!(err("# ShortAnswer\n\n- [x] a\n- [ ] b\n").contains("must be checked"));
+    assert!(err("# ShortAnswer\n\n```toml\ncontext = \"a\"\n```\n\n> b\n").contains("already"));
+  }
+
+  #[test]
+  fn validate_markdown_quiz_spans() {
+    let contents = QUIZ.replace("Rust declares", "Rust declarse");
+    let config = ValidationConfig {
+      spellcheck: true,
+      ..Default::default()
+    };
+    let report = diagnose(
+      Path::new("test.quiz.md"),
+      &contents,
+      &IdSet::default(),
+      &config,
+    );
+    let [diagnostic] = report.diagnostics() else {
+      panic!("expected one diagnostic, got {report:?}")
+    };
+    assert_eq!(diagnostic.rule.as_deref(), Some("markdown::spelling"));
+    let span = diagnostic.span.as_ref().unwrap();
+    assert_eq!(
+      &contents[span.offset..span.offset + span.length],
+      "declarse"
+    );
+    assert_eq!(span.start.line, 14);
+
+    let contents = "# ShortAnswer\n\nWhat?\n\n```toml\nanswer.answer = 1\n```\n";
+    let report = diagnose(
+      Path::new("test.quiz.md"),
+      contents,
+      &IdSet::default(),
+      &ValidationConfig::default(),
+    );
+    assert!(report.is_fatal());
+    assert_eq!(report.diagnostics()[0].rule.as_deref(), Some("quiz::parse"));
+  }
+}
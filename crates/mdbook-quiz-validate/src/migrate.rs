@@ -0,0 +1,170 @@
+//! Reading and upgrading quizzes written in older versions of the quiz format.
+
+use anyhow::{Context, Result};
+use mdbook_quiz_schema::{v1, Quiz, CURRENT_VERSION};
+use serde::{de::Error as _, Deserialize};
+use toml_edit::{Array, Document, Item, Value};
+
+#[derive(Deserialize)]
+struct Version {
+  version: Option<u32>,
+}
+
+fn upgrade(quiz: v1::Quiz) -> Result<(Quiz, u32), toml::de::Error> {
+  let quiz = Quiz::try_from(quiz).map_err(toml::de::Error::custom)?;
+  Ok((quiz, 1))
+}
+
+/// Parses a quiz with TOML-format `contents` written in any version of the quiz format,
+/// returning the quiz upgraded to the current version along with the version it was written in.
+///
+/// Quizzes without a `version` key are read as the current version if possible,
+/// and as version 1 otherwise.
+pub fn parse_quiz(contents: &str) -> Result<(Quiz, u32), toml::de::Error> {
+  match toml::from_str::<Version>(contents)?.version {
+    Some(CURRENT_VERSION) => Ok((toml::from_str(contents)?, CURRENT_VERSION)),
+    Some(1) => upgrade(toml::from_str(contents)?),
+    Some(version) => Err(toml::de::Error::custom(format!(
+      "unknown quiz format version {version}, the latest version is {CURRENT_VERSION}"
+    ))),
+    None => match toml::from_str::<Quiz>(contents) {
+      Ok(quiz) => Ok((quiz, CURRENT_VERSION)),
+      // If the quiz isn't valid in either version, then report the error for the current one.
+      Err(err) => toml::from_str::<v1::Quiz>(contents)
+        .map_err(|_| err)
+        .and_then(upgrade),
+    },
+  }
+}
+
+/// Moves the value at `index` out of `array`, keeping the whitespace before it in place.
+fn remove_element(array: &mut Array, index: usize) -> Value {
+  let mut removed = array.remove(index);
+  if let Some(prefix) = removed.decor().prefix().cloned() {
+    if let Some(next) = array.get_mut(index) {
+      next.decor_mut().set_prefix(prefix);
+    }
+  }
+  removed.decor_mut().clear();
+  removed
+}
+
+/// Rewrites version 1 multiple choice questions, which list `prompt.choices` and the index of
+/// the correct choice in `answer.answer`, to use `prompt.distractors` and the text of the answer.
+fn upgrade_v1(doc: &mut Document) -> Result<()> {
+  let qs = doc
+    .get_mut("questions")
+    .and_then(|questions| questions.as_array_of_tables_mut())
+    .context("Must contain questions")?;
+  for q in qs.iter_mut() {
+    if q.get("type").and_then(Item::as_str) != Some("MultipleChoice") {
+      continue;
+    }
+
+    let answer = q
+      .get_mut("answer")
+      .and_then(Item::as_table_like_mut)
+      .context("Question must have an answer")?;
+    let Some(index) = answer.get("answer").and_then(Item::as_integer) else {
+      continue;
+    };
+    let index = index as usize;
+
+    let prompt = q
+      .get_mut("prompt")
+      .and_then(Item::as_table_like_mut)
+      .context("Question must have a prompt")?;
+    let mut choices = prompt
+      .remove("choices")
+      .and_then(|choices| match choices.into_value() {
+        Ok(Value::Array(choices)) => Some(choices),
+        _ => None,
+      })
+      .context("Question must have an array of choices")?;
+    let correct = remove_element(&mut choices, index);
+    prompt.insert("distractors", Item::Value(Value::Array(choices)));
+    prompt.insert("answerIndex", toml_edit::value(index as i64));
+
+    let answer = q
+      .get_mut("answer")
+      .and_then(Item::as_table_like_mut)
+      .unwrap();
+    answer.insert("answer", Item::Value(correct));
+  }
+  Ok(())
+}
+
+/// Upgrades a quiz with TOML-format `contents` to the current version of the quiz format,
+/// preserving its comments and formatting.
+///
+/// Returns the new contents of the file if anything changed, including setting the `version` key.
+pub fn migrate(contents: &str) -> Result<Option<String>> {
+  let (_, version) = parse_quiz(contents)?;
+  let mut doc = contents.parse::<Document>()?;
+  if version == 1 {
+    upgrade_v1(&mut doc)?;
+  }
+
+  if doc.get("version").and_then(Item::as_integer) != Some(CURRENT_VERSION as i64) {
+    doc["version"] = toml_edit::value(CURRENT_VERSION as i64);
+  }
+
+  let new_contents = doc.to_string();
+  Ok((new_contents != contents).then_some(new_contents))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  const V1_QUIZ: &str = r#"
+# A comment that should be preserved
+[[questions]]
+type = "MultipleChoice"
+prompt.prompt = "What is 1 + 1?"
+prompt.choices = ["1", "2", "3"]
+answer.answer = 1
+
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "What is 2 + 2?"
+answer.answer = "4"
+"#;
+
+  #[test]
+  fn test_parse_versions() {
+    let (_, version) = parse_quiz(V1_QUIZ).unwrap();
+    assert_eq!(version, 1);
+
+    let versioned = format!("version = 1\n{V1_QUIZ}");
+    assert_eq!(parse_quiz(&versioned).unwrap().1, 1);
+
+    // A quiz that declares the current version is never read as version 1.
+    let current = V1_QUIZ.replace("prompt.choices", "prompt.distractors");
+    let current = format!("version = {CURRENT_VERSION}\n{current}");
+    assert!(parse_quiz(&current).is_err());
+
+    assert!(parse_quiz(&format!("version = 100\n{V1_QUIZ}")).is_err());
+  }
+
+  #[test]
+  fn test_migrate_v1() {
+    let migrated = migrate(V1_QUIZ).unwrap().unwrap();
+    assert!(migrated.contains("# A comment that should be preserved"));
+    assert!(migrated.contains(r#"prompt.distractors = ["1", "3"]"#));
+    assert!(migrated.contains(r#"answer.answer = "2""#));
+    assert!(!migrated.contains("choices"));
+
+    // The rewritten file should mean the same thing as the upgraded quiz.
+    let (quiz, version) = parse_quiz(&migrated).unwrap();
+    assert_eq!(version, CURRENT_VERSION);
+    assert_eq!(quiz.version, Some(CURRENT_VERSION));
+    let (upgraded, _) = parse_quiz(V1_QUIZ).unwrap();
+    assert_eq!(
+      serde_json::to_value(&quiz.questions).unwrap(),
+      serde_json::to_value(&upgraded.questions).unwrap()
+    );
+
+    assert!(migrate(&migrated).unwrap().is_none());
+  }
+}
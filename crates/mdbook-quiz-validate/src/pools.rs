@@ -0,0 +1,300 @@
+//! Pools of interchangeable questions, of which only some are asked in each attempt at a quiz.
+
+use mdbook_quiz_schema::{PoolDraw, QuestionPool, Quiz};
+use serde::Serialize;
+
+use crate::{cxensure, tomlcast, SpannedValue, SpannedValueExt, ValidationContext};
+
+/// Questions of a quiz of which `draw` are asked in each attempt, as sent to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Draw {
+  /// The indices of the questions, in order.
+  pub questions: Vec<usize>,
+
+  /// How many of the questions are asked.
+  pub draw: usize,
+}
+
+/// Returns the indices of the questions in `pool`.
+fn pool_questions(quiz: &Quiz, pool: &QuestionPool) -> Vec<usize> {
+  let contains = |list: &Option<Vec<String>>, value: &str| {
+    list
+      .as_ref()
+      .is_some_and(|list| list.iter().any(|s| s == value))
+  };
+  quiz
+    .questions
+    .iter()
+    .enumerate()
+    .filter(|(_, question)| {
+      (pool.ids.is_none() && pool.tags.is_none())
+        || question.id().is_some_and(|id| contains(&pool.ids, id))
+        || question.tags().iter().any(|tag| contains(&pool.tags, tag))
+    })
+    .map(|(i, _)| i)
+    .collect()
+}
+
+/// Splits `pool` into draws, one for each tag if it is drawn per tag.
+fn pool_draws<'a>(quiz: &Quiz, pool: &'a QuestionPool) -> Vec<(Option<&'a str>, Draw)> {
+  let questions = pool_questions(quiz, pool);
+  match &pool.draw {
+    PoolDraw::Count(draw) => vec![(
+      None,
+      Draw {
+        questions,
+        draw: *draw,
+      },
+    )],
+    PoolDraw::PerTag(draws) => draws
+      .iter()
+      .map(|(tag, draw)| {
+        let questions = questions
+          .iter()
+          .copied()
+          .filter(|i| quiz.questions[*i].tags().contains(tag))
+          .collect();
+        (
+          Some(tag.as_str()),
+          Draw {
+            questions,
+            draw: *draw,
+          },
+        )
+      })
+      .collect(),
+  }
+}
+
+/// Resolves the pools of `quiz` into draws among the questions at the indices in `selected`,
+/// where each draw's questions are positions in `selected`.
+///
+/// Draws that would ask every one of their questions are omitted.
+pub fn quiz_draws(quiz: &Quiz, selected: &[usize]) -> Vec<Draw> {
+  let pools = quiz.pools.iter().flatten();
+  pools
+    .flat_map(|pool| pool_draws(quiz, pool))
+    .filter_map(|(_, draw)| {
+      let questions = (draw.questions.iter())
+        .filter_map(|i| selected.iter().position(|j| j == i))
+        .collect::<Vec<_>>();
+      (draw.draw < questions.len()).then_some(Draw {
+        questions,
+        draw: draw.draw,
+      })
+    })
+    .collect()
+}
+
+/// Checks that every pool of `quiz`, whose TOML is `value`, refers to questions in the quiz and
+/// has enough questions to draw from.
+pub(crate) fn validate_pools(quiz: &Quiz, cx: &mut ValidationContext, value: &SpannedValue) {
+  let Some(pools) = &quiz.pools else {
+    return;
+  };
+
+  let mut owners = vec![None; quiz.questions.len()];
+  for (i, (pool, pool_val)) in pools
+    .iter()
+    .zip(tomlcast!(value.table["pools"].array))
+    .enumerate()
+  {
+    let lists = [("ids", "ID", &pool.ids), ("tags", "tag", &pool.tags)];
+    for (key, kind, list) in lists {
+      let Some(list) = list else { continue };
+      let list_val = pool_val.get_ref().as_table().unwrap().get(key).unwrap();
+      for (item, item_val) in list.iter().zip(tomlcast!(list_val.array)) {
+        let is_known = quiz.questions.iter().any(|question| match key {
+          "ids" => question.id() == Some(item),
+          _ => question.tags().contains(item),
+        });
+        cxensure!(
+          cx,
+          is_known,
+          code = "quiz::pool",
+          labels = vec![item_val.labeled_span()],
+          "No question in the quiz has the {kind} `{item}`"
+        );
+      }
+    }
+
+    let questions = pool_questions(quiz, pool);
+    for question in &questions {
+      let owner = owners[*question].replace(i);
+      cxensure!(
+        cx,
+        owner.is_none(),
+        code = "quiz::pool",
+        labels = vec![pool_val.labeled_span()],
+        "Question {} is in more than one pool",
+        question + 1
+      );
+    }
+
+    let draw_val = tomlcast!(pool_val.table["draw"]);
+    let draws = pool_draws(quiz, pool);
+    for (tag, draw) in &draws {
+      let count_val = match tag {
+        Some(tag) => draw_val.get_ref().as_table().unwrap().get(*tag).unwrap(),
+        None => draw_val,
+      };
+      let tagged = tag
+        .map(|tag| format!(" with the tag `{tag}`"))
+        .unwrap_or_default();
+      cxensure!(
+        cx,
+        draw.draw > 0,
+        code = "quiz::pool",
+        labels = vec![count_val.labeled_span()],
+        "A pool must draw at least one question"
+      );
+      cxensure!(
+        cx,
+        draw.draw <= draw.questions.len(),
+        code = "quiz::pool",
+        labels = vec![count_val.labeled_span()],
+        "Pool draws {} questions{tagged}, but only has {}",
+        draw.draw,
+        draw.questions.len()
+      );
+    }
+
+    // A question with two of the tags a pool is drawn by could be asked twice, and a question
+    // with none of them is in no draw, so it would be asked in every attempt.
+    for question in &questions {
+      let strata = draws
+        .iter()
+        .filter(|(_, draw)| draw.questions.contains(question))
+        .count();
+      cxensure!(
+        cx,
+        strata > 0,
+        code = "quiz::pool",
+        labels = vec![draw_val.labeled_span()],
+        "Question {} has none of the tags that its pool draws by, so it would always be asked",
+        question + 1
+      );
+      cxensure!(
+        cx,
+        strata <= 1,
+        code = "quiz::pool",
+        labels = vec![draw_val.labeled_span()],
+        "Question {} has more than one of the tags that its pool draws by",
+        question + 1
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::harness;
+
+  const QUESTIONS: &str = r#"
+[[questions]]
+id = "a"
+tags = ["easy"]
+type = "ShortAnswer"
+prompt.prompt = "A"
+answer.answer = "a"
+
+[[questions]]
+id = "b"
+tags = ["easy"]
+type = "ShortAnswer"
+prompt.prompt = "B"
+answer.answer = "b"
+
+[[questions]]
+id = "c"
+tags = ["hard"]
+type = "ShortAnswer"
+prompt.prompt = "C"
+answer.answer = "c"
+
+[[questions]]
+id = "d"
+type = "ShortAnswer"
+prompt.prompt = "D"
+answer.answer = "d"
+"#;
+
+  fn quiz(pools: &str) -> String {
+    format!("{pools}\n{QUESTIONS}")
+  }
+
+  #[test]
+  fn test_quiz_draws() {
+    let (q, _) = crate::parse_quiz(&quiz("pools = [{ ids = [\"a\", \"c\"], draw = 1 }]")).unwrap();
+    let expected = Draw {
+      questions: vec![0, 2],
+      draw: 1,
+    };
+    assert_eq!(quiz_draws(&q, &[0, 1, 2, 3]), [expected]);
+    assert_eq!(
+      quiz_draws(&q, &[0, 2, 3]),
+      [Draw {
+        questions: vec![0, 1],
+        draw: 1
+      }]
+    );
+    assert!(quiz_draws(&q, &[0, 1]).is_empty());
+
+    let (q, _) = crate::parse_quiz(&quiz("pools = [{ draw = { easy = 1, hard = 1 } }]")).unwrap();
+    assert_eq!(
+      quiz_draws(&q, &[0, 1, 2, 3]),
+      [Draw {
+        questions: vec![0, 1],
+        draw: 1
+      }]
+    );
+  }
+
+  #[test]
+  fn validate_pools() {
+    assert!(harness(&quiz("pools = [{ tags = [\"easy\"], draw = 1 }]")).is_ok());
+    assert!(harness(&quiz(
+      "pools = [{ tags = [\"easy\", \"hard\"], draw = { easy = 2, hard = 1 } }]"
+    ))
+    .is_ok());
+    assert!(harness(&quiz("pools = [{ ids = [\"a\", \"z\"], draw = 1 }]")).is_err());
+    assert!(harness(&quiz("pools = [{ tags = [\"medium\"], draw = 1 }]")).is_err());
+    assert!(harness(&quiz("pools = [{ tags = [\"easy\"], draw = 3 }]")).is_err());
+    assert!(harness(&quiz("pools = [{ draw = 0 }]")).is_err());
+    assert!(harness(&quiz("pools = [{ draw = { easy = 1, hard = 2 } }]")).is_err());
+    assert!(harness(&quiz(
+      "pools = [{ ids = [\"a\", \"b\"], draw = 1 }, { tags = [\"easy\"], draw = 1 }]"
+    ))
+    .is_err());
+  }
+
+  #[test]
+  fn validate_overlapping_strata() {
+    let contents = quiz("pools = [{ draw = { easy = 1, hard = 1 } }]")
+      .replace("tags = [\"hard\"]", "tags = [\"easy\", \"hard\"]");
+    assert!(harness(&contents).is_err());
+  }
+
+  #[test]
+  fn validate_unstratified_questions() {
+    // Question D is in the pool, but has neither tag.
+    let contents = quiz("pools = [{ draw = { easy = 1, hard = 1 } }]");
+    let report = crate::diagnose(
+      std::path::Path::new("dummy.toml"),
+      &contents,
+      &crate::IdSet::default(),
+      &crate::ValidationConfig::default(),
+    );
+    let messages = report
+      .diagnostics()
+      .iter()
+      .map(|d| d.message.as_str())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      messages,
+      ["Question 4 has none of the tags that its pool draws by, so it would always be asked"]
+    );
+    assert!(report.is_fatal());
+  }
+}
@@ -0,0 +1,394 @@
+//! Settings for compiling the Rust programs in quizzes.
+
+use anyhow::{Context, Result};
+use mdbook_quiz_schema::RustConfig;
+use serde::Deserialize;
+use std::{
+  collections::{BTreeSet, HashMap},
+  fs,
+  path::{Path, PathBuf},
+  process::{Command, Stdio},
+  sync::{Arc, Mutex, OnceLock},
+};
+use uuid::Uuid;
+
+/// Book-wide settings for compiling Rust programs, set in `[preprocessor.quiz.rust]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RustSettings {
+  /// The Rust edition, e.g. `"2021"`. Defaults to rustc's default edition.
+  pub edition: Option<String>,
+
+  /// The rustup toolchain, e.g. `"nightly"`. Takes precedence over `rustc`.
+  pub toolchain: Option<String>,
+
+  /// The path to rustc. Defaults to the `rustc` on the `PATH`.
+  pub rustc: Option<PathBuf>,
+
+  /// Configuration options passed to rustc as `--cfg`.
+  pub cfg: Vec<String>,
+
+  /// Other flags passed to rustc.
+  pub flags: Vec<String>,
+
+  /// The directory of a Cargo project whose dependencies can be used by every program.
+  ///
+  /// The project is built with `cargo build --offline`, so its dependencies should be vendored.
+  pub crates: Option<PathBuf>,
+}
+
+/// The editions accepted by rustc's `--edition` flag.
+pub(crate) const EDITIONS: [&str; 4] = ["2015", "2018", "2021", "2024"];
+
+/// A compiled library that programs can link against.
+#[derive(Debug)]
+struct Extern {
+  name: String,
+  path: PathBuf,
+  /// A hash of the library's contents, which changes whenever its source does.
+  hash: String,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadata {
+  resolve: CargoResolve,
+}
+
+#[derive(Deserialize)]
+struct CargoResolve {
+  root: Option<String>,
+  nodes: Vec<CargoNode>,
+}
+
+#[derive(Deserialize)]
+struct CargoNode {
+  id: String,
+  deps: Vec<CargoDep>,
+}
+
+#[derive(Deserialize)]
+struct CargoDep {
+  /// The name of the dependency in code: its rename, or else the name of its library target.
+  name: String,
+  pkg: String,
+  dep_kinds: Vec<CargoDepKind>,
+}
+
+#[derive(Deserialize)]
+struct CargoDepKind {
+  /// `None` for a normal dependency, or `"dev"` or `"build"`.
+  kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+  reason: String,
+  package_id: Option<String>,
+  target: Option<CargoTarget>,
+  #[serde(default)]
+  filenames: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct CargoTarget {
+  kind: Vec<String>,
+}
+
+/// The kinds of Cargo targets that programs can link against.
+const LIBRARY_KINDS: [&str; 4] = ["lib", "rlib", "dylib", "proc-macro"];
+
+impl RustSettings {
+  /// Returns these settings overridden by the settings of an individual question.
+  pub(crate) fn with_overrides(&self, config: &RustConfig) -> RustSettings {
+    let mut settings = self.clone();
+    if let Some(edition) = &config.edition {
+      settings.edition = Some(edition.clone());
+    }
+    if let Some(toolchain) = &config.toolchain {
+      settings.toolchain = Some(toolchain.clone());
+    }
+    settings.cfg.extend(config.cfg.iter().flatten().cloned());
+    settings
+      .flags
+      .extend(config.flags.iter().flatten().cloned());
+    settings
+  }
+
+  /// Returns a command that invokes `program` from the configured toolchain,
+  /// e.g. `rustc +nightly` or `cargo +nightly`.
+  fn toolchain_command(&self, program: &str) -> Command {
+    match (&self.toolchain, &self.rustc) {
+      (Some(toolchain), _) => {
+        let mut cmd = Command::new(program);
+        cmd.arg(format!("+{toolchain}"));
+        cmd
+      }
+      (None, Some(rustc)) if program == "rustc" => Command::new(rustc),
+      (None, Some(rustc)) => {
+        let mut cmd = Command::new(program);
+        cmd.env("RUSTC", rustc);
+        cmd
+      }
+      (None, None) => Command::new(program),
+    }
+  }
+
+  /// Describes everything about these settings that can affect the result of compiling a
+  /// program, including the exact version of rustc and the contents of the built crates, for use
+  /// in cache keys.
+  pub(crate) fn fingerprint(&self) -> Result<String> {
+    type Versions = Mutex<HashMap<(Option<String>, Option<PathBuf>), String>>;
+    static VERSIONS: OnceLock<Versions> = OnceLock::new();
+
+    let version_key = (self.toolchain.clone(), self.rustc.clone());
+    let mut versions = VERSIONS.get_or_init(Versions::default).lock().unwrap();
+    let version = match versions.get(&version_key) {
+      Some(version) => version.clone(),
+      None => {
+        let output = self.toolchain_command("rustc").arg("-vV").output()?;
+        anyhow::ensure!(
+          output.status.success(),
+          "Failed to get rustc version:\n{}",
+          String::from_utf8_lossy(&output.stderr)
+        );
+        let version = String::from_utf8(output.stdout)?;
+        versions.insert(version_key, version.clone());
+        version
+      }
+    };
+    drop(versions);
+
+    // The crates' libraries change whenever their versions or sources do, including the
+    // sources of path dependencies.
+    let crates = match &self.crates {
+      Some(crates) => self
+        .build_crates(crates)?
+        .iter()
+        .map(|ext| format!("{}={}", ext.name, ext.hash))
+        .collect::<Vec<_>>(),
+      None => Vec::new(),
+    };
+
+    Ok(format!(
+      "{version}\n{:?}\n{:?}\n{:?}\n{:?}\n{crates:?}",
+      self.edition, self.cfg, self.flags, self.crates
+    ))
+  }
+
+  /// Returns a rustc command with every setting applied, to which a source file can be added.
+  pub(crate) fn rustc(&self) -> Result<Command> {
+    let mut cmd = self.toolchain_command("rustc");
+    if let Some(edition) = &self.edition {
+      cmd.args(["--edition", edition]);
+    }
+    for cfg in &self.cfg {
+      cmd.args(["--cfg", cfg]);
+    }
+    cmd.args(&self.flags);
+
+    if let Some(crates) = &self.crates {
+      let externs = self.build_crates(crates)?;
+      for Extern { name, path, .. } in externs.iter() {
+        cmd
+          .arg("--extern")
+          .arg(format!("{name}={}", path.display()));
+      }
+      let dirs = externs
+        .iter()
+        .filter_map(|ext| ext.path.parent())
+        .collect::<BTreeSet<_>>();
+      for dir in dirs {
+        cmd.arg("-L").arg(format!("dependency={}", dir.display()));
+      }
+    }
+
+    Ok(cmd)
+  }
+
+  /// Builds the dependencies of the Cargo project in `dir`, returning the library of each one.
+  ///
+  /// Each project is only built once per toolchain.
+  fn build_crates(&self, dir: &Path) -> Result<Arc<Vec<Extern>>> {
+    type Cache = Mutex<HashMap<RustSettings, Arc<Vec<Extern>>>>;
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+
+    // Only the settings that affect the build of the dependencies are part of the key.
+    let key = RustSettings {
+      toolchain: self.toolchain.clone(),
+      rustc: self.rustc.clone(),
+      crates: Some(dir.to_owned()),
+      ..Default::default()
+    };
+    let mut cache = CACHE.get_or_init(Cache::default).lock().unwrap();
+    if let Some(externs) = cache.get(&key) {
+      return Ok(Arc::clone(externs));
+    }
+
+    let manifest_path = dir.join("Cargo.toml");
+    let cargo = |args: &[&str]| -> Result<String> {
+      let output = self
+        .toolchain_command("cargo")
+        .args(args)
+        .args(["--offline", "--manifest-path"])
+        .arg(&manifest_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()?;
+      anyhow::ensure!(
+        output.status.success(),
+        "Failed to build crates in {}:\n{}",
+        dir.display(),
+        String::from_utf8_lossy(&output.stderr)
+      );
+      Ok(String::from_utf8(output.stdout)?)
+    };
+
+    // The names that programs use for the project's dependencies, by package ID.
+    let metadata = cargo(&["metadata", "--format-version=1"])?;
+    let resolve = serde_json::from_str::<CargoMetadata>(&metadata)?.resolve;
+    let root = resolve
+      .root
+      .context("Crates manifest must have a [package] section")?;
+    let names = resolve
+      .nodes
+      .into_iter()
+      .find(|node| node.id == root)
+      .map(|node| node.deps)
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|dep| dep.dep_kinds.iter().any(|kind| kind.kind.is_none()))
+      .map(|dep| (dep.pkg, dep.name))
+      .collect::<HashMap<_, _>>();
+
+    let mut externs = cargo(&["build", "--message-format=json"])?
+      .lines()
+      .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+      .filter(|message| message.reason == "compiler-artifact")
+      .filter(|message| {
+        (message.target.iter())
+          .flat_map(|target| &target.kind)
+          .any(|kind| LIBRARY_KINDS.contains(&kind.as_str()))
+      })
+      .filter_map(|message| {
+        let name = names.get(message.package_id.as_ref()?)?.clone();
+        let path = message
+          .filenames
+          .into_iter()
+          .find(|path| path.extension().is_some_and(|ext| ext != "rmeta"))?;
+        Some((name, path))
+      })
+      .map(|(name, path)| {
+        let contents = fs::read(&path)?;
+        let hash = Uuid::new_v5(&Uuid::NAMESPACE_OID, &contents).to_string();
+        Ok(Extern { name, path, hash })
+      })
+      .collect::<Result<Vec<_>>>()?;
+    // Cargo builds crates in parallel, so sort them for a stable fingerprint.
+    externs.sort_by(|a, b| a.name.cmp(&b.name));
+    let externs = Arc::new(externs);
+    cache.insert(key, Arc::clone(&externs));
+    Ok(externs)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_question_overrides() {
+    let book = RustSettings {
+      edition: Some("2018".into()),
+      cfg: vec!["book".into()],
+      ..Default::default()
+    };
+    let question = RustConfig {
+      edition: Some("2021".into()),
+      toolchain: None,
+      cfg: Some(vec!["question".into()]),
+      flags: None,
+    };
+    let settings = book.with_overrides(&question);
+    assert_eq!(settings.edition.as_deref(), Some("2021"));
+    assert_eq!(settings.cfg, ["book", "question"]);
+  }
+
+  #[test]
+  fn test_crates() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let crates = dir.path().join("crates");
+    let write = |path: &str, contents: &str| -> Result<()> {
+      let path = crates.join(path);
+      fs::create_dir_all(path.parent().unwrap())?;
+      fs::write(path, contents)?;
+      Ok(())
+    };
+    write(
+      "Cargo.toml",
+      r#"
+[package]
+name = "quiz-crates"
+version = "0.0.0"
+edition = "2021"
+
+[dependencies]
+greeting = { path = "greeting" }
+farewell = { path = "goodbye", package = "goodbye" }
+named = { path = "named" }
+"#,
+    )?;
+    write("src/lib.rs", "")?;
+    write(
+      "greeting/Cargo.toml",
+      "[package]\nname = \"greeting\"\nversion = \"0.0.0\"\n",
+    )?;
+    write(
+      "greeting/src/lib.rs",
+      "pub fn hello() -> &'static str { \"hello\" }",
+    )?;
+    write(
+      "goodbye/Cargo.toml",
+      "[package]\nname = \"goodbye\"\nversion = \"0.0.0\"\n",
+    )?;
+    write("goodbye/src/lib.rs", "pub fn bye() {}")?;
+    write(
+      "named/Cargo.toml",
+      "[package]\nname = \"named\"\nversion = \"0.0.0\"\n\n[lib]\nname = \"named_lib\"\n",
+    )?;
+    write("named/src/lib.rs", "pub fn name() {}")?;
+    fs::write(
+      dir.path().join("main.rs"),
+      "fn main() { println!(\"{}\", greeting::hello()); farewell::bye(); named_lib::name(); }",
+    )?;
+
+    let settings = RustSettings {
+      edition: Some("2021".into()),
+      crates: Some(crates.clone()),
+      ..Default::default()
+    };
+    let output = settings
+      .rustc()?
+      .arg("main.rs")
+      .current_dir(dir.path())
+      .output()?;
+    assert!(
+      output.status.success(),
+      "{}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Editing a path dependency changes the fingerprint. Each build is only run once per process,
+    // so the edited project is built under another toolchain path.
+    let fingerprint = settings.fingerprint()?;
+    write(
+      "greeting/src/lib.rs",
+      "pub fn hello() -> &'static str { \"hi\" }",
+    )?;
+    let edited = RustSettings {
+      rustc: Some("rustc".into()),
+      ..settings
+    };
+    assert_ne!(edited.fingerprint()?, fingerprint);
+    Ok(())
+  }
+}
@@ -0,0 +1,443 @@
+//! Selecting some of the questions of a quiz with the arguments of a `{{#quiz}}` directive.
+
+use std::{ops::Range, path::Path, str::FromStr};
+
+use mdbook_quiz_schema::Quiz;
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::{IdSet, ValidationConfig, ValidationContext, ValidationReport};
+
+/// The questions of a quiz chosen by the arguments of a `{{#quiz}}` directive, like
+/// `{{#quiz bank.toml ids=a,b tags=ownership range=3..7}}`.
+///
+/// A question is selected if it matches every argument that is given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuestionSelection {
+  /// `ids=a,b`: only the questions with one of these IDs.
+  pub ids: Option<Vec<String>>,
+
+  /// `tags=a,b`: only the questions with at least one of these tags.
+  pub tags: Option<Vec<String>>,
+
+  /// `range=3..7`: only the questions whose 0-based index is in this range.
+  /// Can also be written like `3..=6`, `3..`, or `..7`.
+  pub range: Option<Range<usize>>,
+
+  /// `draw=5`: ask only this many of the selected questions in each attempt, chosen at random.
+  /// Replaces the quiz's own pools, and asks every selected question if it is at least their number.
+  pub draw: Option<usize>,
+}
+
+/// A mistake in the arguments of a `{{#quiz}}` directive.
+#[derive(Error, Diagnostic, Debug)]
+#[error("Invalid quiz directive: {message}")]
+#[diagnostic(code(quiz::directive))]
+pub struct SelectionError {
+  message: String,
+
+  #[label]
+  span: SourceSpan,
+}
+
+fn error(message: impl Into<String>, span: Range<usize>) -> SelectionError {
+  SelectionError {
+    message: message.into(),
+    span: span.into(),
+  }
+}
+
+/// A `key=value` argument of a `{{#quiz}}` directive.
+struct Argument<'a> {
+  key: &'a str,
+  value: &'a str,
+  /// The range of the whole argument in the directive's arguments.
+  span: Range<usize>,
+}
+
+/// Splits `args` into whitespace-separated `key=value` arguments.
+fn arguments(args: &str) -> impl Iterator<Item = Argument<'_>> {
+  args.split_whitespace().map(move |arg| {
+    let start = arg.as_ptr() as usize - args.as_ptr() as usize;
+    let (key, value) = arg.split_once('=').unwrap_or((arg, ""));
+    Argument {
+      key,
+      value,
+      span: start..start + arg.len(),
+    }
+  })
+}
+
+/// Splits a comma-separated `value` starting at `start`, returning each item with its range.
+fn items(value: &str, start: usize) -> impl Iterator<Item = (&str, Range<usize>)> {
+  value.split(',').scan(start, |offset, item| {
+    let range = *offset..*offset + item.len();
+    *offset = range.end + 1;
+    Some((item, range))
+  })
+}
+
+fn parse_range(value: &str, span: Range<usize>) -> Result<Range<usize>, SelectionError> {
+  let invalid = || {
+    error(
+      format!("`{value}` is not a range like `3..7`, `3..=6`, `3..`, or `..7`"),
+      span.clone(),
+    )
+  };
+  let (start, end, inclusive) = match value.split_once("..=") {
+    Some((start, end)) => (start, end, true),
+    None => {
+      let (start, end) = value.split_once("..").ok_or_else(invalid)?;
+      (start, end, false)
+    }
+  };
+  let bound = |s: &str| s.parse::<usize>().map_err(|_| invalid());
+  let start = if start.is_empty() { 0 } else { bound(start)? };
+  let end = match (end.is_empty(), inclusive) {
+    (true, false) => usize::MAX,
+    (true, true) => return Err(invalid()),
+    (false, false) => bound(end)?,
+    (false, true) => bound(end)? + 1,
+  };
+  if start > end {
+    return Err(error(format!("Range `{value}` starts after it ends"), span));
+  }
+  Ok(start..end)
+}
+
+impl FromStr for QuestionSelection {
+  type Err = SelectionError;
+
+  fn from_str(args: &str) -> Result<Self, Self::Err> {
+    let mut selection = QuestionSelection::default();
+    for Argument { key, value, span } in arguments(args) {
+      let value_span = span.end - value.len()..span.end;
+      if !["ids", "tags", "range", "draw"].contains(&key) {
+        return Err(error(
+          format!("Unknown argument `{key}`, expected one of `ids`, `tags`, `range`, or `draw`"),
+          span,
+        ));
+      }
+      if value.is_empty() || value.split(',').any(str::is_empty) {
+        return Err(error(format!("`{key}` is missing a value"), value_span));
+      }
+      let list = || items(value, 0).map(|(item, _)| item.to_string()).collect();
+      let is_duplicate = match key {
+        "ids" => selection.ids.replace(list()).is_some(),
+        "tags" => selection.tags.replace(list()).is_some(),
+        "range" => (selection.range)
+          .replace(parse_range(value, value_span.clone())?)
+          .is_some(),
+        _ => {
+          let draw = value.parse::<usize>().ok().filter(|draw| *draw > 0);
+          let draw = draw.ok_or_else(|| {
+            error(
+              format!("`{value}` is not a positive number of questions to draw"),
+              value_span.clone(),
+            )
+          })?;
+          selection.draw.replace(draw).is_some()
+        }
+      };
+      if is_duplicate {
+        return Err(error(format!("`{key}` is given more than once"), span));
+      }
+    }
+    Ok(selection)
+  }
+}
+
+impl QuestionSelection {
+  /// Returns the indices of the questions of `quiz` that are selected.
+  pub fn select(&self, quiz: &Quiz) -> Vec<usize> {
+    let matches = |list: &Option<Vec<String>>, values: &[&str]| {
+      list
+        .as_ref()
+        .is_none_or(|list| list.iter().any(|s| values.contains(&s.as_str())))
+    };
+    quiz
+      .questions
+      .iter()
+      .enumerate()
+      .filter(|(i, question)| {
+        let tags = question
+          .tags()
+          .iter()
+          .map(String::as_str)
+          .collect::<Vec<_>>();
+        matches(&self.ids, &question.id().into_iter().collect::<Vec<_>>())
+          && matches(&self.tags, &tags)
+          && self.range.as_ref().is_none_or(|range| range.contains(i))
+      })
+      .map(|(i, _)| i)
+      .collect()
+  }
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("No question in the quiz has the {kind} `{value}`")]
+#[diagnostic(code(quiz::unknown_selection))]
+struct UnknownSelection {
+  kind: &'static str,
+  value: String,
+
+  #[label]
+  span: SourceSpan,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("{message}")]
+#[diagnostic(code(quiz::empty_selection))]
+struct EmptySelection {
+  message: String,
+
+  #[label]
+  span: SourceSpan,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error(
+  "The directive draws {draw} questions, but only selects {selected}, so every attempt asks all of them"
+)]
+#[diagnostic(code(quiz::pool))]
+struct UnsatisfiableDraw {
+  draw: usize,
+  selected: usize,
+
+  #[label]
+  span: SourceSpan,
+}
+
+#[derive(Error, Diagnostic, Debug)]
+#[error("The directive's `draw` replaces the quiz's own pools, which are ignored")]
+#[diagnostic(
+  code(quiz::pool),
+  help("Remove `draw` from the directive to use the quiz's pools")
+)]
+struct IgnoredPools {
+  #[label]
+  span: SourceSpan,
+}
+
+/// Runs validation on the arguments in `args` of a `{{#quiz}}` directive in the Markdown
+/// `chapter` at `chapter_path`, which select questions of `quiz`, returning every diagnostic
+/// rather than printing them.
+///
+/// Unknown IDs and tags, selections with no questions, drawing at least as many questions as
+/// are selected, and drawing from a quiz that defines its own pools are warnings.
+pub fn diagnose_selection(
+  chapter_path: &Path,
+  chapter: &str,
+  args: Range<usize>,
+  quiz: &Quiz,
+) -> ValidationReport {
+  let config = ValidationConfig::default();
+  let mut cx = ValidationContext::new(chapter_path, chapter, IdSet::default(), &config);
+  let offset = args.start;
+  let args = &chapter[args];
+  let selection = match args.parse::<QuestionSelection>() {
+    Ok(selection) => selection,
+    Err(e) => {
+      let span = e.span.offset() + offset..e.span.offset() + e.span.len() + offset;
+      cx.error(error(e.message, span));
+      return cx.finish();
+    }
+  };
+
+  let mut unknown = false;
+  for Argument { key, value, span } in arguments(args) {
+    for (item, range) in items(value, offset + span.end - value.len()) {
+      let (kind, is_known) = match key {
+        "ids" => ("ID", quiz.questions.iter().any(|q| q.id() == Some(item))),
+        "tags" => (
+          "tag",
+          quiz
+            .questions
+            .iter()
+            .any(|q| q.tags().iter().any(|t| t == item)),
+        ),
+        _ => continue,
+      };
+      if !is_known {
+        unknown = true;
+        cx.warning(UnknownSelection {
+          kind,
+          value: item.to_string(),
+          span: range.into(),
+        });
+      }
+    }
+  }
+
+  let count = quiz.questions.len();
+  let message = match &selection.range {
+    Some(range) if range.start >= count || (range.end > count && range.end != usize::MAX) => Some(
+      format!("Range is out of bounds for a quiz with {count} questions"),
+    ),
+    _ if !unknown && selection.select(quiz).is_empty() => {
+      Some("The directive selects none of the quiz's questions".to_string())
+    }
+    _ => None,
+  };
+  if let Some(message) = message {
+    cx.warning(EmptySelection {
+      message,
+      span: (offset..offset + args.len()).into(),
+    });
+  }
+
+  let selected = selection.select(quiz).len();
+  if let Some(draw) = selection
+    .draw
+    .filter(|draw| *draw >= selected && selected > 0)
+  {
+    cx.warning(UnsatisfiableDraw {
+      draw,
+      selected,
+      span: (offset..offset + args.len()).into(),
+    });
+  }
+
+  let has_pools = quiz.pools.as_ref().is_some_and(|pools| !pools.is_empty());
+  if selection.draw.is_some() && has_pools {
+    let draw = arguments(args).find(|arg| arg.key == "draw").unwrap();
+    cx.warning(IgnoredPools {
+      span: (offset + draw.span.start..offset + draw.span.end).into(),
+    });
+  }
+
+  cx.finish()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  const QUIZ: &str = r#"
+[[questions]]
+id = "a"
+tags = ["ownership"]
+type = "ShortAnswer"
+prompt.prompt = "A"
+answer.answer = "a"
+
+[[questions]]
+id = "b"
+tags = ["ownership", "borrowing"]
+type = "ShortAnswer"
+prompt.prompt = "B"
+answer.answer = "b"
+
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "C"
+answer.answer = "c"
+"#;
+
+  #[test]
+  fn test_parse_selection() {
+    let selection = "ids=a,b tags=x range=1..=2"
+      .parse::<QuestionSelection>()
+      .unwrap();
+    assert_eq!(selection.ids, Some(vec!["a".into(), "b".into()]));
+    assert_eq!(selection.tags, Some(vec!["x".into()]));
+    assert_eq!(selection.range, Some(1..3));
+    assert_eq!(
+      "range=..2".parse::<QuestionSelection>().unwrap().range,
+      Some(0..2)
+    );
+    assert_eq!(
+      "range=1..".parse::<QuestionSelection>().unwrap().range,
+      Some(1..usize::MAX)
+    );
+    for args in [
+      "foo=1",
+      "ids=",
+      "ids=a,,b",
+      "range=3",
+      "range=5..2",
+      "tags=a tags=b",
+    ] {
+      assert!(args.parse::<QuestionSelection>().is_err(), "{args}");
+    }
+  }
+
+  #[test]
+  fn test_select_questions() {
+    let (quiz, _) = crate::parse_quiz(QUIZ).unwrap();
+    let select = |args: &str| args.parse::<QuestionSelection>().unwrap().select(&quiz);
+    assert_eq!(select(""), [0, 1, 2]);
+    assert_eq!(select("ids=b,a"), [0, 1]);
+    assert_eq!(select("tags=borrowing"), [1]);
+    assert_eq!(select("tags=ownership range=1.."), [1]);
+    assert_eq!(select("range=..1"), [0]);
+  }
+
+  #[test]
+  fn validate_selection() {
+    let (quiz, _) = crate::parse_quiz(QUIZ).unwrap();
+    let diagnose = |directive: &str| {
+      let chapter = format!("# Chapter\n\n{directive}\n");
+      let args = chapter.find("bank.toml").unwrap() + "bank.toml".len();
+      let end = chapter.find("}}").unwrap();
+      diagnose_selection(Path::new("chapter.md"), &chapter, args..end, &quiz)
+    };
+
+    assert!(diagnose("{{#quiz bank.toml ids=a tags=ownership}}")
+      .diagnostics()
+      .is_empty());
+
+    let report = diagnose("{{#quiz bank.toml ids=a,z tags=nope}}");
+    let messages = report
+      .diagnostics()
+      .iter()
+      .map(|d| {
+        (
+          d.rule.as_deref().unwrap(),
+          d.span.as_ref().unwrap().start.column,
+        )
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      messages,
+      [
+        ("quiz::unknown_selection", 25),
+        ("quiz::unknown_selection", 32)
+      ]
+    );
+    assert!(!report.is_fatal());
+
+    let report = diagnose("{{#quiz bank.toml ids=b range=2..}}");
+    assert_eq!(
+      report.diagnostics()[0].rule.as_deref(),
+      Some("quiz::empty_selection")
+    );
+    let report = diagnose("{{#quiz bank.toml tags=ownership draw=3}}");
+    assert_eq!(report.diagnostics()[0].rule.as_deref(), Some("quiz::pool"));
+    let report = diagnose("{{#quiz bank.toml tags=ownership draw=2}}");
+    assert!(report.diagnostics()[0].message.contains("asks all of them"));
+    assert!(diagnose("{{#quiz bank.toml tags=ownership draw=1}}")
+      .diagnostics()
+      .is_empty());
+    let report = diagnose("{{#quiz bank.toml range=1..9}}");
+    assert!(report.diagnostics()[0].message.contains("out of bounds"));
+
+    let (pooled, _) = crate::parse_quiz(&format!("pools = [{{ draw = 1 }}]\n{QUIZ}")).unwrap();
+    let chapter = "{{#quiz bank.toml range=1.. draw=1}}";
+    let args = "{{#quiz bank.toml".len()..chapter.len() - 2;
+    let report = diagnose_selection(Path::new("chapter.md"), chapter, args, &pooled);
+    let diagnostics = report.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0]
+      .message
+      .contains("replaces the quiz's own pools"));
+    assert_eq!(diagnostics[0].span.as_ref().unwrap().start.column, 29);
+
+    let report = diagnose("{{#quiz bank.toml range=x}}");
+    assert!(report.is_fatal());
+    assert_eq!(
+      report.diagnostics()[0].span.as_ref().unwrap().start.column,
+      25
+    );
+  }
+}
@@ -0,0 +1,375 @@
+//! Converting quizzes written in other formats into the TOML format.
+//!
+//! A quiz is first read into a tree of [`Value`]s that remember where they came from, and then
+//! written as TOML along with a [`SourceMap`] that moves the labels of each diagnostic back to
+//! the original file.
+
+use std::{
+  fmt::{self, Write},
+  ops::Range,
+};
+
+use miette::{Diagnostic, LabeledSpan};
+use thiserror::Error;
+
+/// A value in the generated TOML, along with the part of the source it came from.
+#[derive(Debug, Clone)]
+pub(crate) struct Value {
+  pub(crate) kind: Kind,
+  pub(crate) span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Kind {
+  /// A string, with the offset in the string and in the source at which each of its lines start.
+  String(String, Vec<(usize, usize)>),
+  Scalar(toml::Value),
+  Array(Vec<Value>),
+  Table(Table),
+}
+
+pub(crate) type Table = Vec<(String, Value)>;
+
+impl Value {
+  /// Makes a string whose `i`th line is the end of the `i`th line of the source
+  /// starting at `lines_start`.
+  pub(crate) fn text(source: &str, lines_start: usize, span: Range<usize>, text: String) -> Value {
+    let mut lines = Vec::new();
+    let mut source_lines = source[lines_start.min(source.len())..].split_inclusive('\n');
+    let (mut text_offset, mut source_offset) = (0, lines_start);
+    for line in text.split('\n') {
+      let source_line = source_lines.next().unwrap_or_default();
+      let trimmed = source_line.trim_end_matches(['\n', '\r']);
+      let start = match trimmed.strip_suffix(line) {
+        Some(prefix) => source_offset + prefix.len(),
+        None => source_offset,
+      };
+      lines.push((text_offset, start));
+      text_offset += line.len() + 1;
+      source_offset += source_line.len();
+    }
+    Value {
+      kind: Kind::String(text, lines),
+      span,
+    }
+  }
+
+  pub(crate) fn scalar(value: impl Into<toml::Value>, span: Range<usize>) -> Value {
+    Value {
+      kind: Kind::Scalar(value.into()),
+      span,
+    }
+  }
+
+  pub(crate) fn array(items: Vec<Value>, span: Range<usize>) -> Value {
+    Value {
+      kind: Kind::Array(items),
+      span,
+    }
+  }
+
+  pub(crate) fn table(entries: Table, span: Range<usize>) -> Value {
+    Value {
+      kind: Kind::Table(entries),
+      span,
+    }
+  }
+}
+
+/// Converts an offset into a string [`Value`] into an offset into the source.
+pub(crate) fn source_offset(lines: &[(usize, usize)], offset: usize) -> usize {
+  let index = lines.partition_point(|(start, _)| *start <= offset);
+  match index.checked_sub(1).map(|i| lines[i]) {
+    Some((text_start, source_start)) => source_start + (offset - text_start),
+    None => offset,
+  }
+}
+
+/// Where a range of the generated TOML came from in the source.
+struct Mapping {
+  toml: Range<usize>,
+  span: Range<usize>,
+  /// For strings, where the contents start in the TOML and the lines of the string.
+  lines: Option<(usize, Vec<(usize, usize)>)>,
+}
+
+/// Moves ranges of the TOML generated from a quiz back to the file it was written in.
+pub(crate) struct SourceMap {
+  source: String,
+  mappings: Vec<Mapping>,
+  /// The name of the format of the source, e.g. "JSON".
+  format: &'static str,
+}
+
+impl SourceMap {
+  /// Writes `quiz` in the TOML format, returning the TOML and a map back to the `format`-format
+  /// `source` that `quiz` was read from.
+  ///
+  /// If `questions` is an array of tables, then each question is written as its own
+  /// `[[questions]]` table.
+  pub(crate) fn generate(source: &str, format: &'static str, quiz: &Table) -> (String, SourceMap) {
+    let is_question_list = |(key, value): &&(String, Value)| {
+      key == "questions"
+        && matches!(&value.kind, Kind::Array(items)
+          if !items.is_empty() && items.iter().all(|item| matches!(item.kind, Kind::Table(_))))
+    };
+    let questions = quiz.iter().find(is_question_list);
+    let fields = quiz
+      .iter()
+      .filter(|entry| !is_question_list(entry))
+      .cloned()
+      .collect::<Table>();
+
+    let mut emitter = Emitter::default();
+    emitter.entries(&mut Vec::new(), &fields);
+    if let Some((
+      _,
+      Value {
+        kind: Kind::Array(questions),
+        ..
+      },
+    )) = questions
+    {
+      for question in questions {
+        let start = emitter.toml.len();
+        emitter.toml.push_str("\n[[questions]]\n");
+        let Kind::Table(entries) = &question.kind else {
+          unreachable!()
+        };
+        emitter.entries(&mut Vec::new(), entries);
+        emitter.mappings.push(Mapping {
+          toml: start..emitter.toml.len(),
+          span: question.span.clone(),
+          lines: None,
+        });
+      }
+    }
+
+    let source_map = SourceMap {
+      source: source.to_string(),
+      mappings: emitter.mappings,
+      format,
+    };
+    (emitter.toml, source_map)
+  }
+
+  /// Maps TOML `text` that was written in `span` of the Markdown `source`, where the `i`th line
+  /// of `text` is the end of the `i`th line of `source` starting at `lines_start`.
+  pub(crate) fn embedded(
+    source: &str,
+    lines_start: usize,
+    span: Range<usize>,
+    text: &str,
+  ) -> SourceMap {
+    let Kind::String(_, lines) = Value::text(source, lines_start, span.clone(), text.into()).kind
+    else {
+      unreachable!()
+    };
+    SourceMap {
+      source: source.to_string(),
+      mappings: vec![Mapping {
+        toml: 0..text.len(),
+        span,
+        lines: Some((0, lines)),
+      }],
+      format: "TOML",
+    }
+  }
+
+  /// The name of the format the quiz was written in.
+  pub(crate) fn format(&self) -> &'static str {
+    self.format
+  }
+
+  /// The file the quiz was written in.
+  pub(crate) fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Returns the range of the source that produced `range` of the TOML, if any.
+  fn span(&self, range: Range<usize>) -> Option<Range<usize>> {
+    let mapping = self
+      .mappings
+      .iter()
+      .filter(|m| m.toml.start <= range.start && range.end <= m.toml.end)
+      .min_by_key(|m| m.toml.len())?;
+    match &mapping.lines {
+      Some((content_start, lines)) if mapping.toml != range && range.start >= *content_start => {
+        let start = source_offset(lines, range.start - content_start);
+        let end = source_offset(lines, range.end - content_start).max(start);
+        Some(start.min(self.source.len())..end.min(self.source.len()))
+      }
+      _ => Some(mapping.span.clone()),
+    }
+  }
+
+  /// Copies `diagnostic` with its labels moved to the source.
+  pub(crate) fn remap(&self, diagnostic: &dyn Diagnostic) -> Remapped {
+    let labels = diagnostic
+      .labels()
+      .into_iter()
+      .flatten()
+      .filter_map(|label| {
+        let span = self.span(label.offset()..label.offset() + label.len())?;
+        Some(LabeledSpan::new_with_span(
+          label.label().map(String::from),
+          span,
+        ))
+      })
+      .collect();
+    Remapped {
+      message: diagnostic.to_string(),
+      code: diagnostic.code().map(|code| code.to_string()),
+      help: diagnostic.help().map(|help| help.to_string()),
+      severity: diagnostic.severity(),
+      labels,
+    }
+  }
+}
+
+/// A diagnostic about a quiz that was converted into TOML, see [`SourceMap::remap`].
+#[derive(Debug, Error)]
+#[error("{message}")]
+pub(crate) struct Remapped {
+  message: String,
+  code: Option<String>,
+  help: Option<String>,
+  severity: Option<miette::Severity>,
+  labels: Vec<LabeledSpan>,
+}
+
+impl Diagnostic for Remapped {
+  fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    self
+      .code
+      .as_ref()
+      .map(|code| Box::new(code) as Box<dyn fmt::Display>)
+  }
+
+  fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+    self
+      .help
+      .as_ref()
+      .map(|help| Box::new(help) as Box<dyn fmt::Display>)
+  }
+
+  fn severity(&self) -> Option<miette::Severity> {
+    self.severity
+  }
+
+  fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+    Some(Box::new(self.labels.iter().cloned()))
+  }
+}
+
+/// Writes the TOML for a quiz, recording where each value came from.
+#[derive(Default)]
+struct Emitter {
+  toml: String,
+  mappings: Vec<Mapping>,
+}
+
+impl Emitter {
+  fn key(&mut self, key: &str) {
+    let is_bare = !key.is_empty()
+      && key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_bare {
+      self.toml.push_str(key);
+    } else {
+      self.string(key);
+    }
+  }
+
+  /// Writes `s` as a basic string, returning the offset of its contents.
+  fn string(&mut self, s: &str) -> usize {
+    let multiline = s.contains('\n');
+    self
+      .toml
+      .push_str(if multiline { "\"\"\"\n" } else { "\"" });
+    let start = self.toml.len();
+    for c in s.chars() {
+      match c {
+        '"' => self.toml.push_str("\\\""),
+        '\\' => self.toml.push_str("\\\\"),
+        '\n' | '\t' => self.toml.push(c),
+        c if c.is_control() => write!(self.toml, "\\u{:04X}", c as u32).unwrap(),
+        c => self.toml.push(c),
+      }
+    }
+    self.toml.push_str(if multiline { "\"\"\"" } else { "\"" });
+    start
+  }
+
+  fn value(&mut self, value: &Value) {
+    let start = self.toml.len();
+    let mut lines = None;
+    match &value.kind {
+      Kind::String(s, string_lines) => {
+        let content_start = self.string(s);
+        lines = Some((content_start, string_lines.clone()));
+      }
+      Kind::Scalar(scalar) => match scalar {
+        toml::Value::Float(f) if f.is_nan() => self.toml.push_str("nan"),
+        toml::Value::Float(f) if f.is_infinite() => {
+          self.toml.push_str(if *f > 0. { "inf" } else { "-inf" })
+        }
+        toml::Value::Float(f) => write!(self.toml, "{f:?}").unwrap(),
+        toml::Value::Integer(n) => write!(self.toml, "{n}").unwrap(),
+        toml::Value::Boolean(b) => write!(self.toml, "{b}").unwrap(),
+        toml::Value::Datetime(d) => write!(self.toml, "{d}").unwrap(),
+        _ => unreachable!("not a scalar"),
+      },
+      Kind::Array(items) => {
+        self.toml.push('[');
+        for (i, item) in items.iter().enumerate() {
+          if i > 0 {
+            self.toml.push_str(", ");
+          }
+          self.value(item);
+        }
+        self.toml.push(']');
+      }
+      Kind::Table(entries) => {
+        self.toml.push('{');
+        for (i, (key, item)) in entries.iter().enumerate() {
+          self.toml.push_str(if i > 0 { ", " } else { " " });
+          self.key(key);
+          self.toml.push_str(" = ");
+          self.value(item);
+        }
+        self
+          .toml
+          .push_str(if entries.is_empty() { "}" } else { " }" });
+      }
+    }
+    self.mappings.push(Mapping {
+      toml: start..self.toml.len(),
+      span: value.span.clone(),
+      lines,
+    });
+  }
+
+  /// Writes each entry of `table` on its own line, using dotted keys for nested tables.
+  fn entries(&mut self, prefix: &mut Vec<String>, table: &Table) {
+    for (key, value) in table {
+      prefix.push(key.clone());
+      match &value.kind {
+        Kind::Table(entries) if !entries.is_empty() => self.entries(prefix, entries),
+        _ => {
+          for (i, key) in prefix.iter().enumerate() {
+            if i > 0 {
+              self.toml.push('.');
+            }
+            self.key(key);
+          }
+          self.toml.push_str(" = ");
+          self.value(value);
+          self.toml.push('\n');
+        }
+      }
+      prefix.pop();
+    }
+  }
+}
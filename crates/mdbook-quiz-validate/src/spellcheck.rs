@@ -1,33 +1,273 @@
-use anyhow::{anyhow, Context};
-use std::{borrow::Cow, fs, path::Path, sync::OnceLock};
+//! Spellchecking the prose in Markdown strings.
+
+use anyhow::{Context, Result};
+use std::{
+  collections::HashMap,
+  fs,
+  path::{Path, PathBuf},
+  sync::{Arc, Mutex, OnceLock},
+};
 use zspell::{DictBuilder, Dictionary};
 
-static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
-static MORE_WORDS: OnceLock<String> = OnceLock::new();
+/// The language of the dictionary bundled with mdbook-quiz.
+const BUNDLED_LANGUAGE: &str = "en";
 
-/// Register a `.dic` file into the dictionary for spellchecking.
-///
-/// Will return an error if called more than once.
-pub fn register_more_words(path: &Path) -> anyhow::Result<()> {
-  let contents =
-    fs::read_to_string(path).with_context(|| format!("Failed to read path: {}", path.display()))?;
-  MORE_WORDS
-    .set(contents)
-    .map_err(|_| anyhow!("Attempted to register words twice"))
+/// Which dictionaries the spellchecker uses, set in `[preprocessor.quiz]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DictionarySettings {
+  /// The languages of the prose, e.g. `["de"]`. A word is spelled correctly if it is in the
+  /// dictionary of any language.
+  pub languages: Vec<String>,
+
+  /// A directory containing a dictionary for each language, laid out like
+  /// [wooorm/dictionaries](https://github.com/wooorm/dictionaries): `<lang>/index.aff` and
+  /// `<lang>/index.dic`. Takes precedence over the bundled English dictionary.
+  pub dictionary_dir: Option<PathBuf>,
+
+  /// `.dic` files containing more words to add to every dictionary.
+  pub more_words: Vec<PathBuf>,
 }
 
-pub(crate) fn dictionary() -> &'static Dictionary {
-  DICTIONARY.get_or_init(|| {
-    let mut base_dict = Cow::Borrowed(include_str!("../dictionaries/en/index.dic"));
+impl Default for DictionarySettings {
+  fn default() -> Self {
+    DictionarySettings {
+      languages: vec![BUNDLED_LANGUAGE.to_string()],
+      dictionary_dir: None,
+      more_words: Vec::new(),
+    }
+  }
+}
 
-    if let Some(more_words) = MORE_WORDS.get() {
-      base_dict.to_mut().push_str(more_words);
+/// Words registered with [`register_more_words`], in the format of a `.dic` file.
+fn registered_words() -> &'static Mutex<String> {
+  static REGISTERED: OnceLock<Mutex<String>> = OnceLock::new();
+  REGISTERED.get_or_init(Default::default)
+}
+
+/// Returns the words in the contents of a `.dic` file.
+fn dic_words(contents: &str) -> &str {
+  // The first line of a .dic file may be an approximate word count, which is not a word.
+  match contents.split_once('\n') {
+    Some((count, rest)) if count.trim().parse::<usize>().is_ok() => rest,
+    _ => contents,
+  }
+}
+
+/// Registers a `.dic` file of more words for every dictionary, in addition to
+/// [`DictionarySettings::more_words`].
+#[deprecated(note = "use `DictionarySettings::more_words` instead")]
+pub fn register_more_words(path: &Path) -> Result<()> {
+  let contents = read(path)?;
+  let mut registered = registered_words().lock().unwrap();
+  registered.push('\n');
+  registered.push_str(dic_words(&contents));
+  Ok(())
+}
+
+/// A set of dictionaries, one per language.
+pub(crate) struct Dictionaries(Vec<Dictionary>);
+
+impl Dictionaries {
+  /// Returns true if `word` is spelled correctly in any language.
+  pub(crate) fn check_word(&self, word: &str) -> bool {
+    self.0.iter().any(|dict| dict.check_word(word))
+  }
+}
+
+fn read(path: &Path) -> Result<String> {
+  fs::read_to_string(path).with_context(|| format!("Failed to read path: {}", path.display()))
+}
+
+impl DictionarySettings {
+  /// Returns the directory of the dictionary for `language` in `dictionary_dir`, if it should
+  /// be used instead of the bundled dictionary.
+  fn external_dir(&self, language: &str) -> Option<PathBuf> {
+    self
+      .dictionary_dir
+      .as_ref()
+      .map(|dir| dir.join(language))
+      .filter(|dir| dir.exists() || language != BUNDLED_LANGUAGE)
+  }
+
+  /// Returns true if there is a dictionary for `language`, either bundled or in `dictionary_dir`.
+  pub fn has_dictionary(&self, language: &str) -> bool {
+    match self.external_dir(language) {
+      Some(dir) => dir.exists(),
+      None => language == BUNDLED_LANGUAGE,
     }
+  }
+
+  /// Builds the dictionary for `language`.
+  fn build(&self, language: &str, more_words: &str) -> Result<Dictionary> {
+    let external = self.external_dir(language);
+    let (aff, mut dic) = match external {
+      Some(dir) => (read(&dir.join("index.aff"))?, read(&dir.join("index.dic"))?),
+      None if language == BUNDLED_LANGUAGE => (
+        include_str!("../dictionaries/en/index.aff").to_string(),
+        include_str!("../dictionaries/en/index.dic").to_string(),
+      ),
+      None => anyhow::bail!(
+        "No dictionary for language `{language}`. Set `dictionary-dir` to a directory containing `{language}/index.aff` and `{language}/index.dic`."
+      ),
+    };
+    dic.push_str(more_words);
 
     DictBuilder::new()
-      .config_str(include_str!("../dictionaries/en/index.aff"))
-      .dict_str(&base_dict)
+      .config_str(&aff)
+      .dict_str(&dic)
       .build()
-      .expect("failed to build dictionary!")
-  })
+      .map_err(|e| anyhow::anyhow!("Failed to build dictionary for `{language}`: {e}"))
+  }
+
+  /// Checks that every dictionary can be loaded, so that a misconfigured dictionary is
+  /// reported once instead of for every Markdown string.
+  pub fn preload(&self) -> Result<()> {
+    self.load().map(|_| ())
+  }
+
+  /// Loads the dictionaries for these settings, which are only built once per process.
+  pub(crate) fn load(&self) -> Result<Arc<Dictionaries>> {
+    type Cache = Mutex<HashMap<(DictionarySettings, String), Arc<Dictionaries>>>;
+    static CACHE: OnceLock<Cache> = OnceLock::new();
+
+    let key = (self.clone(), registered_words().lock().unwrap().clone());
+    let mut cache = CACHE.get_or_init(Cache::default).lock().unwrap();
+    if let Some(dicts) = cache.get(&key) {
+      return Ok(Arc::clone(dicts));
+    }
+
+    let mut more_words = key.1.clone();
+    for path in &self.more_words {
+      more_words.push('\n');
+      more_words.push_str(dic_words(&read(path)?));
+    }
+
+    let dicts = self
+      .languages
+      .iter()
+      .map(|language| self.build(language, &more_words))
+      .collect::<Result<Vec<_>>>()?;
+    let dicts = Arc::new(Dictionaries(dicts));
+    cache.insert(key, Arc::clone(&dicts));
+    Ok(dicts)
+  }
+}
+
+/// Returns true if `token` looks like code, a URL, or a number rather than a word of prose,
+/// e.g. `snake_case`, `CamelCase`, `std::vec`, or `https://rust-lang.org`.
+fn is_code_like(token: &str) -> bool {
+  let camel_case = token
+    .chars()
+    .zip(token.chars().skip(1))
+    .any(|(a, b)| a.is_lowercase() && b.is_uppercase());
+  camel_case
+    || token.contains("://")
+    || token.starts_with("www.")
+    || token.contains("::")
+    || token.contains('_')
+    || token.contains('@')
+    || token.contains(|c: char| c.is_ascii_digit())
+}
+
+/// Returns true if `c` is in a script that is written without spaces between words,
+/// such as Chinese or Japanese, which can't be split into words to spellcheck.
+fn is_unspaced_script(c: char) -> bool {
+  matches!(c,
+    '\u{0E00}'..='\u{0E7F}' // Thai
+    | '\u{3040}'..='\u{30FF}' // Hiragana and Katakana
+    | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+    | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+    | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+  )
+}
+
+/// Splits prose into the words that should be spellchecked, with their byte offsets.
+///
+/// Tokens that look like code or URLs are skipped, and anything that is not a letter,
+/// such as punctuation, emoji, or Chinese characters, separates words.
+pub(crate) fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+  let is_quote = |c: char| c == '\'' || c == '\u{2019}';
+  text
+    .split_whitespace()
+    .map(move |token| (token.as_ptr() as usize - text.as_ptr() as usize, token))
+    .filter(|(_, token)| !is_code_like(token))
+    .flat_map(move |(offset, token)| {
+      token
+        .split(move |c: char| (!c.is_alphabetic() && !is_quote(c)) || is_unspaced_script(c))
+        .map(move |word| {
+          (
+            offset + (word.as_ptr() as usize - token.as_ptr() as usize),
+            word,
+          )
+        })
+    })
+    .map(move |(offset, word)| {
+      let trimmed = word.trim_start_matches(is_quote);
+      let offset = offset + (word.len() - trimmed.len());
+      (offset, trimmed.trim_end_matches(is_quote))
+    })
+    .filter(|(_, word)| !word.is_empty())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_words() {
+    let text =
+      "Rust's `Vec` (see std::vec, HashMap, my_var or https://rust-lang.org) is 'great' 🦀!";
+    let words = words(text).collect::<Vec<_>>();
+    assert_eq!(
+      words.iter().map(|(_, word)| *word).collect::<Vec<_>>(),
+      ["Rust's", "Vec", "see", "or", "is", "great"]
+    );
+    for (offset, word) in words {
+      assert_eq!(&text[offset..offset + word.len()], word);
+    }
+  }
+
+  #[test]
+  fn test_unspaced_scripts() {
+    let words = words("Rust编程语言 ist großartig").map(|(_, word)| word);
+    assert_eq!(words.collect::<Vec<_>>(), ["Rust", "ist", "großartig"]);
+  }
+
+  #[test]
+  fn test_dictionaries() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let more_words = dir.path().join("more.dic");
+    fs::write(&more_words, "2\nrustacean\nborrowck\n")?;
+    let settings = DictionarySettings {
+      more_words: vec![more_words],
+      ..Default::default()
+    };
+    let dicts = settings.load()?;
+    assert!(dicts.check_word("hello"));
+    assert!(dicts.check_word("borrowck"));
+    assert!(!dicts.check_word("wrold"));
+
+    let missing = DictionarySettings {
+      languages: vec!["de".into()],
+      ..Default::default()
+    };
+    assert!(missing.load().is_err());
+    assert!(!missing.has_dictionary("de"));
+    assert!(missing.has_dictionary("en"));
+    Ok(())
+  }
+
+  #[test]
+  #[allow(deprecated)]
+  fn test_register_more_words() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let more_words = dir.path().join("registered.dic");
+    fs::write(&more_words, "ferrisite\n")?;
+    register_more_words(&more_words)?;
+    assert!(DictionarySettings::default()
+      .load()?
+      .check_word("ferrisite"));
+    assert!(register_more_words(&dir.path().join("missing.dic")).is_err());
+    Ok(())
+  }
 }
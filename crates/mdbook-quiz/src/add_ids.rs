@@ -0,0 +1,276 @@
+//! The `mdbook-quiz add-ids` command, which writes an ID into every question that lacks one.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use mdbook_quiz_validate::{IdStrategy, InlineQuiz, MarkdownQuiz, QuizFormat};
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+};
+use toml_edit::{Document, Formatted, Item, Value};
+
+use crate::{book, QuizConfig};
+
+#[derive(Args)]
+pub struct AddIdsArgs {
+  /// Root directory of the book, i.e. the directory containing `book.toml`.
+  #[clap(default_value = ".")]
+  dir: PathBuf,
+
+  /// Don't write any files, and exit with an error if any question is missing an ID.
+  #[clap(long)]
+  check: bool,
+
+  /// How to generate IDs: `uuid-v4`, `uuid-v5-content`, or `path-index`.
+  /// Defaults to the book's `id-strategy` setting.
+  #[clap(long)]
+  id_strategy: Option<IdStrategy>,
+}
+
+/// Adds an ID to each question without one, preserving the formatting of the file.
+///
+/// Returns the new contents of the file if any IDs were added.
+fn add_ids(contents: &str, quiz_key: &str, strategy: IdStrategy) -> Result<Option<String>> {
+  let (quiz, _) = mdbook_quiz_validate::parse_quiz(contents)?;
+  let ids = strategy.generate(quiz_key, &quiz);
+  let fingerprints = strategy.fingerprints(&quiz);
+
+  let mut doc = contents.parse::<Document>()?;
+  let qs = doc
+    .get_mut("questions")
+    .and_then(|questions| questions.as_array_of_tables_mut())
+    .context("Must contain questions")?;
+  let mut changed = false;
+  for (i, (q, id)) in qs.iter_mut().zip(ids).enumerate() {
+    if !q.contains_key("id") {
+      changed = true;
+      q.insert("id", Item::Value(Value::String(Formatted::new(id))));
+      if let Some(fingerprints) = &fingerprints {
+        let fingerprint = Formatted::new(fingerprints[i].clone());
+        q.insert("fingerprint", Item::Value(Value::String(fingerprint)));
+      }
+    }
+  }
+  Ok(changed.then(|| doc.to_string()))
+}
+
+/// Adds an ID to the heading of each question without one in a Markdown quiz.
+///
+/// Returns the new contents of the file if any IDs were added.
+fn add_markdown_ids(
+  contents: &str,
+  quiz_key: &str,
+  strategy: IdStrategy,
+) -> Result<Option<String>> {
+  let quiz = MarkdownQuiz::parse(contents)?;
+  let parsed = quiz.quiz()?;
+  let ids = strategy.generate(quiz_key, &parsed);
+  let fingerprints = strategy.fingerprints(&parsed);
+  Ok(quiz.insert_ids(contents, &ids, fingerprints.as_deref()))
+}
+
+/// Adds an `id` field to each question without one in a JSON or YAML quiz.
+///
+/// Returns the new contents of the file if any IDs were added.
+fn add_data_ids(
+  format: QuizFormat,
+  contents: &str,
+  quiz_key: &str,
+  strategy: IdStrategy,
+) -> Result<Option<String>> {
+  let (quiz, _) = mdbook_quiz_validate::parse_quiz(&format.to_toml(contents)?)?;
+  let ids = strategy.generate(quiz_key, &quiz);
+  let fingerprints = strategy.fingerprints(&quiz);
+  format.insert_ids(contents, &ids, fingerprints.as_deref())
+}
+
+/// Adds IDs to every quiz in the book at `root`, including inline quizzes, returning the paths
+/// of the quizzes and chapters that were (or, if `check` is true, would have been) changed.
+fn add_ids_to_book(root: &Path, check: bool, strategy: Option<IdStrategy>) -> Result<Vec<PathBuf>> {
+  let book = book::load(root)?;
+  let strategy = match strategy {
+    Some(strategy) => strategy,
+    None => QuizConfig::new(&book.config)?.id_strategy,
+  };
+  let mut seen = HashSet::new();
+  let mut changed = Vec::new();
+  for reference in book::quiz_references(&book) {
+    let contents = fs::read_to_string(&reference.path).with_context(|| {
+      format!(
+        "Failed to read quiz file referenced by {}: {}",
+        reference.chapter.display(),
+        reference.path.display()
+      )
+    })?;
+    if !seen.insert(reference.path.canonicalize()?) {
+      continue;
+    }
+
+    let quiz_key = mdbook_quiz_validate::quiz_key(&book.root, &reference.path);
+    let new_contents = match QuizFormat::from_path(&reference.path) {
+      QuizFormat::Toml => add_ids(&contents, &quiz_key, strategy),
+      QuizFormat::Markdown => add_markdown_ids(&contents, &quiz_key, strategy),
+      format @ (QuizFormat::Json | QuizFormat::Yaml) => {
+        add_data_ids(format, &contents, &quiz_key, strategy)
+      }
+    };
+    let new_contents = new_contents
+      .with_context(|| format!("Failed to parse quiz: {}", reference.path.display()))?;
+    if let Some(new_contents) = new_contents {
+      if !check {
+        fs::write(&reference.path, new_contents)?;
+      }
+      changed.push(book::display_path(&reference.path));
+    }
+  }
+
+  // Inline quizzes are written back into the chapters that contain them.
+  for (chapter_path, _) in book::chapters(&book) {
+    let chapter = fs::read_to_string(&chapter_path)
+      .with_context(|| format!("Failed to read chapter: {}", chapter_path.display()))?;
+    let mut new_chapter = chapter.clone();
+    for quiz in InlineQuiz::find_all(&chapter_path, &chapter).iter().rev() {
+      let new_contents = add_ids(&quiz.contents, &quiz.quiz_key(&book.root), strategy)
+        .with_context(|| format!("Failed to parse quiz in: {}", chapter_path.display()))?;
+      if let Some(new_contents) = new_contents {
+        let (range, replacement) = quiz.replace_contents(&new_contents);
+        new_chapter.replace_range(range, &replacement);
+      }
+    }
+    if new_chapter != chapter {
+      if !check {
+        fs::write(&chapter_path, new_chapter)?;
+      }
+      changed.push(book::display_path(&chapter_path));
+    }
+  }
+
+  Ok(changed)
+}
+
+pub fn run(args: AddIdsArgs) -> Result<()> {
+  let changed = add_ids_to_book(&args.dir, args.check, args.id_strategy)?;
+
+  let verb = if args.check {
+    "Missing IDs in"
+  } else {
+    "Added IDs to"
+  };
+  for path in &changed {
+    eprintln!("{verb} {}", path.display());
+  }
+
+  if args.check {
+    anyhow::ensure!(
+      changed.is_empty(),
+      "{} quizzes have questions without IDs. Run `mdbook-quiz add-ids` to fix them.",
+      changed.len()
+    );
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::add_ids_to_book;
+  use anyhow::Result;
+  use mdbook_preprocessor_utils::testing::MdbookTestHarness;
+  use mdbook_quiz_schema::{Question, Quiz};
+  use mdbook_quiz_validate::IdStrategy;
+  use std::fs;
+
+  #[test]
+  fn test_add_ids() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let src_dir = harness.root().join("src");
+    let quiz_path = src_dir.join("quiz.toml");
+    fs::write(
+      &quiz_path,
+      r#"
+    # A comment that should be preserved
+    [[questions]]
+    type = "ShortAnswer"
+    prompt.prompt = "Hello world"
+    answer.answer = "No"
+    "#,
+    )?;
+    fs::write(src_dir.join("chapter_1.md"), "{{#quiz quiz.toml}}")?;
+
+    let changed = add_ids_to_book(harness.root(), true, None)?;
+    assert_eq!(changed.len(), 1);
+    let quiz: Quiz = toml::from_str(&fs::read_to_string(&quiz_path)?)?;
+    let Question::ShortAnswer(q) = &quiz.questions[0] else {
+      panic!("Invalid quiz")
+    };
+    assert!(q.0.id.is_none(), "--check should not write files");
+
+    add_ids_to_book(harness.root(), false, Some(IdStrategy::PathIndex))?;
+    let contents = fs::read_to_string(&quiz_path)?;
+    assert!(contents.contains("# A comment that should be preserved"));
+    let quiz: Quiz = toml::from_str(&contents)?;
+    let Question::ShortAnswer(q) = &quiz.questions[0] else {
+      panic!("Invalid quiz")
+    };
+    assert_eq!(q.0.id.as_deref(), Some("src/quiz.toml#0"));
+
+    assert!(add_ids_to_book(harness.root(), true, None)?.is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_add_inline_ids() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let chapter_path = harness.root().join("src").join("chapter_1.md");
+    fs::write(
+      &chapter_path,
+      r#"- A quiz in a list:
+
+  ```quiz
+  [[questions]]
+  type = "ShortAnswer"
+  prompt.prompt = "Hello world"
+  answer.answer = "No"
+  ```
+"#,
+    )?;
+
+    let changed = add_ids_to_book(harness.root(), true, Some(IdStrategy::PathIndex))?;
+    assert_eq!(changed.len(), 1);
+    assert!(!fs::read_to_string(&chapter_path)?.contains("id ="));
+
+    add_ids_to_book(harness.root(), false, Some(IdStrategy::PathIndex))?;
+    let chapter = fs::read_to_string(&chapter_path)?;
+    assert!(chapter.contains("  ```quiz\n  [[questions]]\n  type = \"ShortAnswer\"\n"));
+    assert!(chapter.contains("  id = \"src/chapter_1.md#quiz-0#0\"\n  ```\n"));
+    assert!(add_ids_to_book(harness.root(), true, None)?.is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_add_content_ids() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let src_dir = harness.root().join("src");
+    let quiz_path = src_dir.join("quiz.toml");
+    fs::write(
+      &quiz_path,
+      r#"
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "Hello world"
+answer.answer = "No"
+"#,
+    )?;
+    fs::write(src_dir.join("chapter_1.md"), "{{#quiz quiz.toml}}")?;
+
+    add_ids_to_book(harness.root(), false, Some(IdStrategy::UuidV5Content))?;
+    let quiz: Quiz = toml::from_str(&fs::read_to_string(&quiz_path)?)?;
+    let fingerprint = mdbook_quiz_validate::content_fingerprint(&quiz.questions[0]);
+    assert_eq!(quiz.questions[0].fingerprint(), Some(fingerprint.as_str()));
+
+    Ok(())
+  }
+}
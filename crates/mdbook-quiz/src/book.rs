@@ -0,0 +1,73 @@
+//! Helpers for commands that operate on every quiz in a book outside of `mdbook build`.
+
+use anyhow::{Context, Result};
+use mdbook::{BookItem, MDBook};
+use std::{
+  env,
+  path::{Path, PathBuf},
+};
+
+use crate::quiz_directives;
+
+/// A `{{#quiz ...}}` directive found in a chapter of a book.
+pub struct QuizReference {
+  /// Path to the chapter containing the directive, relative to the book's source directory.
+  pub chapter: PathBuf,
+
+  /// Path to the referenced quiz file.
+  pub path: PathBuf,
+}
+
+pub fn load(root: &Path) -> Result<MDBook> {
+  MDBook::load(root).with_context(|| format!("Failed to load book: {}", root.display()))
+}
+
+/// Returns the path and Markdown of every chapter in the book, in order.
+pub fn chapters(book: &MDBook) -> Vec<(PathBuf, &str)> {
+  let src_dir = book.source_dir();
+  book
+    .iter()
+    .filter_map(|item| match item {
+      BookItem::Chapter(chapter) => {
+        let path = src_dir.join(chapter.path.as_ref()?);
+        Some((path, chapter.content.as_str()))
+      }
+      _ => None,
+    })
+    .collect()
+}
+
+/// Returns every quiz directive in the book, in the order of the book's chapters.
+pub fn quiz_references(book: &MDBook) -> Vec<QuizReference> {
+  let src_dir = book.source_dir();
+  let mut references = Vec::new();
+  for item in book.iter() {
+    let BookItem::Chapter(chapter) = item else {
+      continue;
+    };
+    let Some(chapter_path) = &chapter.path else {
+      continue;
+    };
+    let chapter_dir = src_dir.join(chapter_path).parent().unwrap().to_path_buf();
+    for directive in quiz_directives(&chapter.content) {
+      references.push(QuizReference {
+        chapter: chapter_path.clone(),
+        path: chapter_dir.join(directive.path),
+      });
+    }
+  }
+  references
+}
+
+/// Shortens `path` to be relative to the working directory, so messages
+/// refer to files the same way regardless of how the book root was specified.
+pub fn display_path(path: &Path) -> PathBuf {
+  let Ok(path) = path.canonicalize() else {
+    return path.to_owned();
+  };
+  env::current_dir()
+    .and_then(|cwd| cwd.canonicalize())
+    .ok()
+    .and_then(|cwd| path.strip_prefix(cwd).ok().map(Path::to_owned))
+    .unwrap_or(path)
+}
@@ -0,0 +1,355 @@
+//! The `mdbook-quiz check` command, which validates every quiz in a book without building it.
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use mdbook_preprocessor_utils::rayon::prelude::*;
+use mdbook_quiz_validate::{
+  IdSet, InlineQuiz, QuizFormat, Severity, ValidationDiagnostic, ValidationReport,
+};
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::{book, quiz_directives, sarif, QuizConfig};
+
+#[derive(Args)]
+pub struct CheckArgs {
+  /// Root directory of the book, i.e. the directory containing `book.toml`.
+  #[clap(default_value = ".")]
+  dir: PathBuf,
+
+  /// How to print diagnostics. Machine-readable formats are printed to stdout.
+  #[clap(long, value_enum, default_value_t = OutputFormat::Human)]
+  format: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+  /// Human-readable reports on stderr.
+  Human,
+  /// A JSON array of diagnostics.
+  Json,
+  /// A SARIF 2.1.0 log.
+  Sarif,
+}
+
+/// The outcome of validating every quiz in a book.
+#[derive(Default)]
+struct CheckReport {
+  /// Every quiz file referenced by a chapter, in order of first reference, followed by the
+  /// chapter of every inline quiz.
+  quizzes: Vec<PathBuf>,
+
+  /// Validation reports for each quiz that could be read.
+  reports: Vec<ValidationReport>,
+
+  /// Quiz references that could not be read, and why.
+  failures: Vec<(PathBuf, anyhow::Error)>,
+
+  /// Quiz files in the book's source directory that no chapter references.
+  unreferenced: Vec<PathBuf>,
+}
+
+impl CheckReport {
+  fn has_errors(&self) -> bool {
+    !self.failures.is_empty() || self.reports.iter().any(|report| report.is_fatal())
+  }
+
+  /// Flattens the report into a list of diagnostics, including unreadable
+  /// and unreferenced quiz files.
+  fn diagnostics(&self) -> Vec<ValidationDiagnostic> {
+    let file_diagnostic =
+      |severity, rule: &str, message: String, file: &Path| ValidationDiagnostic {
+        severity,
+        message,
+        rule: Some(rule.into()),
+        file: file.to_owned(),
+        span: None,
+        question_index: None,
+        question_id: None,
+      };
+
+    let unreadable = self.failures.iter().map(|(path, failure)| {
+      file_diagnostic(
+        Severity::Error,
+        "quiz::unreadable",
+        format!("{failure:#}"),
+        path,
+      )
+    });
+    let unreferenced = self.unreferenced.iter().map(|path| {
+      file_diagnostic(
+        Severity::Warning,
+        "quiz::unreferenced",
+        "Quiz file is not referenced by any chapter".into(),
+        path,
+      )
+    });
+
+    self
+      .reports
+      .iter()
+      .flat_map(|report| report.diagnostics().iter().cloned())
+      .chain(unreadable)
+      .chain(unreferenced)
+      .collect()
+  }
+}
+
+fn check_book(root: &Path) -> Result<CheckReport> {
+  let book = book::load(root)?;
+  let mut config = QuizConfig::new(&book.config)?;
+  config.preload_dictionary(&book.root)?;
+
+  let ids = IdSet::default();
+  let validation_config = config.validation_config(&book.root);
+  let mut report = CheckReport::default();
+  let mut seen = HashSet::new();
+
+  let mut quizzes = Vec::new();
+  for reference in book::quiz_references(&book) {
+    let contents = match fs::read_to_string(&reference.path) {
+      Ok(contents) => contents,
+      Err(e) => {
+        let error = anyhow::Error::new(e).context(format!(
+          "Failed to read quiz file referenced by {}: {}",
+          reference.chapter.display(),
+          reference.path.display()
+        ));
+        report
+          .failures
+          .push((book::display_path(&reference.path), error));
+        continue;
+      }
+    };
+
+    // A quiz can be embedded in several chapters, but its IDs should only be counted once.
+    if !seen.insert(reference.path.canonicalize()?) {
+      continue;
+    }
+
+    quizzes.push((book::display_path(&reference.path), contents));
+  }
+
+  let chapters = book::chapters(&book)
+    .into_iter()
+    .map(|(path, content)| (book::display_path(&path), content))
+    .collect::<Vec<_>>();
+  let inline_quizzes = chapters
+    .iter()
+    .flat_map(|(path, content)| InlineQuiz::find_all(path, content))
+    .collect::<Vec<_>>();
+
+  // Run the quizzes' programs in parallel, then validate in order so the report is deterministic.
+  quizzes
+    .par_iter()
+    .for_each(|(path, contents)| mdbook_quiz_validate::prepare(path, contents, &validation_config));
+  inline_quizzes.par_iter().for_each(|quiz| {
+    mdbook_quiz_validate::prepare(quiz.chapter_path, &quiz.contents, &validation_config)
+  });
+  for (quiz_path, contents) in quizzes {
+    let quiz_report =
+      mdbook_quiz_validate::diagnose(&quiz_path, &contents, &ids, &validation_config);
+    report.reports.push(quiz_report);
+    report.quizzes.push(quiz_path);
+  }
+  for quiz in &inline_quizzes {
+    let quiz_report = mdbook_quiz_validate::diagnose_inline(quiz, &ids, &validation_config);
+    report.reports.push(quiz_report);
+    report.quizzes.push(quiz.chapter_path.to_owned());
+  }
+
+  // Directives that select questions are checked against their quiz, unless the quiz could not
+  // be read or parsed, which is already reported above.
+  for (chapter_path, content) in &chapters {
+    for directive in quiz_directives(content).filter(|directive| !directive.args.is_empty()) {
+      let quiz_path = chapter_path.parent().unwrap().join(directive.path);
+      let quiz = fs::read_to_string(&quiz_path).ok().and_then(|contents| {
+        let toml = QuizFormat::from_path(&quiz_path).to_toml(&contents).ok()?;
+        mdbook_quiz_validate::parse_quiz(&toml).ok()
+      });
+      if let Some((quiz, _)) = quiz {
+        let selection_report =
+          mdbook_quiz_validate::diagnose_selection(chapter_path, content, directive.args, &quiz);
+        report.reports.push(selection_report);
+      }
+    }
+  }
+
+  let mut quiz_files = Vec::new();
+  find_quiz_files(&book.source_dir(), &mut quiz_files)?;
+  for path in quiz_files {
+    if !seen.contains(&path.canonicalize()?) {
+      report.unreferenced.push(book::display_path(&path));
+    }
+  }
+
+  Ok(report)
+}
+
+/// Recursively collects every Markdown quiz under `dir`, and every TOML, JSON, or YAML file that
+/// looks like a quiz, i.e. has a top-level `questions` key.
+fn find_quiz_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+  let mut entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+  entries.sort_by_key(|entry| entry.path());
+  for entry in entries {
+    let path = entry.path();
+    if entry.file_type()?.is_dir() {
+      find_quiz_files(&path, files)?;
+    } else if mdbook_quiz_validate::is_markdown_quiz(&path) {
+      files.push(path);
+    } else if path
+      .extension()
+      .is_some_and(|ext| ["toml", "json", "yaml", "yml"].iter().any(|e| ext == *e))
+    {
+      let Ok(contents) = fs::read_to_string(&path) else {
+        continue;
+      };
+      let is_quiz = QuizFormat::from_path(&path)
+        .to_toml(&contents)
+        .is_ok_and(|toml| {
+          toml
+            .parse::<toml::Value>()
+            .is_ok_and(|value| value.get("questions").is_some())
+        });
+      if is_quiz {
+        files.push(path);
+      }
+    }
+  }
+  Ok(())
+}
+
+pub fn run(args: CheckArgs) -> Result<()> {
+  let report = check_book(&args.dir)?;
+
+  match args.format {
+    OutputFormat::Human => {
+      for quiz_report in &report.reports {
+        if !quiz_report.diagnostics().is_empty() {
+          eprintln!("{quiz_report:?}");
+        }
+      }
+
+      for path in &report.unreferenced {
+        eprintln!(
+          "Warning: quiz file is not referenced by any chapter: {}",
+          path.display()
+        );
+      }
+
+      for (_, failure) in &report.failures {
+        eprintln!("Error: {failure:#}");
+      }
+    }
+    OutputFormat::Json => {
+      println!("{}", serde_json::to_string_pretty(&report.diagnostics())?);
+    }
+    OutputFormat::Sarif => {
+      let log = sarif::log(&report.diagnostics());
+      println!("{}", serde_json::to_string_pretty(&log)?);
+    }
+  }
+
+  anyhow::ensure!(
+    !report.has_errors(),
+    "Quizzes failed to validate ({} checked)",
+    report.quizzes.len()
+  );
+
+  if let OutputFormat::Human = args.format {
+    eprintln!("Validated {} quizzes", report.quizzes.len());
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::check_book;
+  use anyhow::Result;
+  use mdbook_preprocessor_utils::testing::MdbookTestHarness;
+  use std::fs;
+
+  #[test]
+  fn test_check_book() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let src_dir = harness.root().join("src");
+    let quiz = r#"
+    [[questions]]
+    type = "ShortAnswer"
+    prompt.prompt = "Hello world"
+    answer.answer = "No"
+    "#;
+    fs::write(src_dir.join("quiz.toml"), quiz)?;
+    fs::write(src_dir.join("unused.toml"), quiz)?;
+    fs::write(
+      src_dir.join("bad.toml"),
+      r#"
+    [[questions]]
+    type = "ShortAnswer"
+    "#,
+    )?;
+    fs::write(
+      src_dir.join("chapter_1.md"),
+      "{{#quiz quiz.toml}}\n\n{{#quiz bad.toml}}\n\n{{#quiz quiz.toml ids=nope}}\n\n\
+       {{#quiz missing.toml}}\n\n\
+       ```quiz\n[[questions]]\ntype = \"ShortAnswer\"\nprompt.prompt = \"Hi\"\nanswer.answer = 1\n```",
+    )?;
+
+    let report = check_book(harness.root())?;
+    assert_eq!(report.quizzes.len(), 3);
+    assert!(report.has_errors());
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.unreferenced.len(), 1);
+    assert!(report.unreferenced[0].ends_with("unused.toml"));
+
+    let rules = report
+      .diagnostics()
+      .into_iter()
+      .map(|d| d.rule.unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      rules,
+      [
+        "quiz::parse",
+        "quiz::parse",
+        "quiz::unknown_selection",
+        "quiz::unreadable",
+        "quiz::unreferenced"
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_sarif_output() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let src_dir = harness.root().join("src");
+    fs::write(
+      src_dir.join("quiz.toml"),
+      "[[questions]]\ntype = \"Numeric\"\nprompt.prompt = \"Pick\"\nanswer.range = [3, 1]\n",
+    )?;
+    fs::write(src_dir.join("chapter_1.md"), "{{#quiz quiz.toml}}")?;
+
+    let report = check_book(harness.root())?;
+    let log = crate::sarif::log(&report.diagnostics());
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    let result = &results[0];
+    assert_eq!(result["ruleId"], "numeric::range");
+    assert_eq!(result["level"], "error");
+    let region = &result["locations"][0]["physicalLocation"]["region"];
+    assert_eq!(region["startLine"], 4);
+    assert_eq!(region["startColumn"], 16);
+    assert_eq!(
+      log["runs"][0]["tool"]["driver"]["rules"][0]["id"],
+      "numeric::range"
+    );
+
+    Ok(())
+  }
+}
@@ -0,0 +1,201 @@
+//! Runs the quiz preprocessor over every chapter of a book piped from mdBook.
+//!
+//! This follows the driver in `mdbook_preprocessor_utils`, except that the preprocessor is told
+//! which file each chapter was read from, so that diagnostics and IDs for inline quizzes name
+//! the right chapter. Its logging and version check match the upstream driver.
+
+use anyhow::Result;
+use chrono::Local;
+use env_logger::Builder;
+use log::LevelFilter;
+use mdbook::{
+  book::Book,
+  preprocess::{CmdPreprocessor, Preprocessor, PreprocessorContext},
+  BookItem,
+};
+use mdbook_preprocessor_utils::rayon::prelude::*;
+use semver::{Version, VersionReq};
+use std::{
+  env, fs,
+  io::{self, Write},
+  path::{Path, PathBuf},
+};
+
+use crate::QuizPreprocessor;
+
+/// A chapter of the book to preprocess.
+struct ChapterSource<'a> {
+  /// The chapter's source file.
+  path: PathBuf,
+
+  /// The directory that paths in the chapter are relative to.
+  dir: PathBuf,
+
+  content: &'a mut String,
+}
+
+/// Collects every chapter in `items` with a source file, including nested chapters.
+fn collect_chapters<'a>(
+  src_dir: &Path,
+  chapters: &mut Vec<ChapterSource<'a>>,
+  items: impl IntoIterator<Item = &'a mut BookItem>,
+) {
+  for item in items {
+    let BookItem::Chapter(chapter) = item else {
+      continue;
+    };
+    if let Some(path) = &chapter.path {
+      let source_path = chapter.source_path.as_ref().unwrap_or(path);
+      chapters.push(ChapterSource {
+        path: src_dir.join(source_path),
+        dir: src_dir.join(path).parent().unwrap().to_path_buf(),
+        content: &mut chapter.content,
+      });
+    }
+    collect_chapters(src_dir, chapters, &mut chapter.sub_items);
+  }
+}
+
+fn process_chapter(
+  preprocessor: &QuizPreprocessor,
+  src_dir: &Path,
+  chapter: ChapterSource,
+) -> Result<()> {
+  let replacements = preprocessor.replacements(&chapter.dir, &chapter.path, chapter.content)?;
+  if replacements.is_empty() {
+    return Ok(());
+  }
+  for (range, html) in replacements.into_iter().rev() {
+    chapter.content.replace_range(range, &html);
+  }
+
+  // A chapter at foo/bar/the_chapter.md is rendered to foo/bar/the_chapter.html,
+  // so it links to the assets at ../../quiz/<asset>.
+  let depth = chapter
+    .dir
+    .strip_prefix(src_dir)
+    .unwrap()
+    .components()
+    .count();
+  let prefix = vec![".."; depth].into_iter().collect::<PathBuf>();
+
+  // Ensure there's space between existing markdown and inserted HTML.
+  chapter.content.push_str("\n\n");
+
+  for asset in preprocessor.linked_assets() {
+    let asset_rel = prefix.join(QuizPreprocessor::NAME).join(asset.name);
+    let asset_str = asset_rel.display().to_string();
+    let link = match &*asset_rel.extension().unwrap().to_string_lossy() {
+      "js" => format!(r#"<script type="text/javascript" src="{asset_str}"></script>"#),
+      "mjs" => format!(r#"<script type="module" src="{asset_str}"></script>"#),
+      "css" => format!(r#"<link rel="stylesheet" type="text/css" href="{asset_str}">"#),
+      _ => continue,
+    };
+    chapter.content.push_str(&link);
+  }
+  Ok(())
+}
+
+pub(crate) struct QuizDriver;
+
+impl Preprocessor for QuizDriver {
+  fn name(&self) -> &str {
+    QuizPreprocessor::NAME
+  }
+
+  fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book> {
+    let src_dir = ctx.root.join(&ctx.config.book.src);
+    let preprocessor = QuizPreprocessor::build(ctx)?;
+
+    // Rather than copying directly to the build directory, we instead copy to the book source
+    // since mdBook will clean the build-dir after preprocessing. See mdBook#1087 for more.
+    let assets_dir = src_dir.join(QuizPreprocessor::NAME);
+    fs::create_dir_all(&assets_dir)?;
+    for asset in preprocessor.all_assets() {
+      fs::write(assets_dir.join(asset.name), asset.contents)?;
+    }
+
+    let mut chapters = Vec::new();
+    collect_chapters(&src_dir, &mut chapters, &mut book.sections);
+    chapters
+      .into_par_iter()
+      .map(|chapter| process_chapter(&preprocessor, &src_dir, chapter))
+      .collect::<Result<Vec<_>>>()?;
+
+    Ok(book)
+  }
+}
+
+/// Logs in the same format as mdBook, as the upstream driver does.
+fn init_logger() {
+  let mut builder = Builder::new();
+
+  builder.format(|formatter, record| {
+    writeln!(
+      formatter,
+      "{} [{}] ({}): {}",
+      Local::now().format("%Y-%m-%d %H:%M:%S"),
+      record.level(),
+      record.target(),
+      record.args()
+    )
+  });
+
+  if let Ok(var) = env::var("RUST_LOG") {
+    builder.parse_filters(&var);
+  } else {
+    // If no RUST_LOG is provided, default to logging at the Info level.
+    builder.filter(None, LevelFilter::Info);
+    // Filter extraneous html5ever not-implemented messages.
+    builder.filter(Some("html5ever"), LevelFilter::Error);
+  }
+
+  builder.init();
+}
+
+/// Preprocesses the book that mdBook writes to stdin, writing the result to stdout.
+pub(crate) fn run() -> Result<()> {
+  init_logger();
+
+  let (ctx, book) = CmdPreprocessor::parse_input(io::stdin())?;
+  let book_version = Version::parse(&ctx.mdbook_version)?;
+  let version_req = VersionReq::parse(mdbook::MDBOOK_VERSION)?;
+  if !version_req.matches(&book_version) {
+    eprintln!(
+      "Warning: The {} plugin was built against version {} of mdbook, \
+       but we're being called from version {}",
+      QuizPreprocessor::NAME,
+      mdbook::MDBOOK_VERSION,
+      ctx.mdbook_version
+    );
+  }
+
+  let book = QuizDriver.run(&ctx, book)?;
+  serde_json::to_writer(io::stdout(), &book)?;
+  Ok(())
+}
+
+/// Runs the preprocessor on the book in `harness` with the given `[preprocessor.quiz]` config.
+#[cfg(test)]
+pub(crate) fn compile(
+  harness: &mdbook_preprocessor_utils::testing::MdbookTestHarness,
+  config: serde_json::Value,
+) -> Result<Book> {
+  let book = mdbook::book::load_book(
+    harness.root().join("src"),
+    &mdbook::config::BuildConfig::default(),
+  )?;
+  let input = serde_json::json!([
+    {
+      "root": harness.root().display().to_string(),
+      "config": { "preprocessor": { QuizPreprocessor::NAME: config } },
+      "renderer": "html",
+      "mdbook_version": mdbook::MDBOOK_VERSION,
+    },
+    book,
+  ]);
+
+  std::env::set_current_dir(harness.root())?;
+  let (ctx, book) = CmdPreprocessor::parse_input(serde_json::to_string(&input)?.as_bytes())?;
+  QuizDriver.run(&ctx, book)
+}
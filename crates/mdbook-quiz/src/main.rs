@@ -1,18 +1,38 @@
 use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
 use mdbook_preprocessor_utils::{
-  mdbook::preprocess::PreprocessorContext, Asset, SimplePreprocessor,
+  mdbook::{
+    config::{Config, RustEdition},
+    preprocess::PreprocessorContext,
+  },
+  Asset,
 };
 
-use mdbook_quiz_validate::IdSet;
+use mdbook_quiz_validate::{
+  DictionarySettings, Draw, IdSet, IdStrategy, InlineQuiz, QuestionSelection, QuizFormat,
+  ResourceLimits, RustSettings, ValidationConfig,
+};
 use regex::Regex;
 use std::{
+  collections::HashSet,
   env,
   fmt::Write,
   fs,
+  ops::Range,
   path::{Path, PathBuf},
-  sync::OnceLock,
+  process,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, OnceLock,
+  },
 };
-use uuid::Uuid;
+
+mod add_ids;
+mod book;
+mod check;
+mod driver;
+mod migrate;
+mod sarif;
 
 mdbook_preprocessor_utils::asset_generator!("../js/");
 
@@ -34,6 +54,9 @@ const SOURCE_MAP_ASSETS: [Asset; 1] = [
 #[cfg(not(feature = "source-map"))]
 const SOURCE_MAP_ASSETS: [Asset; 0] = [];
 
+/// The default directory for caching validation results, relative to the build directory.
+const DEFAULT_CACHE_DIR: &str = "quiz-cache";
+
 struct QuizConfig {
   /// If true, then a quiz will take up the web page's full screen during use.
   fullscreen: Option<bool>,
@@ -50,15 +73,261 @@ struct QuizConfig {
   /// You can add a custom dictionary via the `more-words` key.
   spellcheck: Option<bool>,
 
-  /// Path to a .dic file containing words to include in the spellcheck dictionary.
-  more_words: Option<PathBuf>,
+  /// Whether the spellchecker's languages were set with `spellcheck-language`, rather than
+  /// taken from the book's language.
+  explicit_spellcheck_language: bool,
+
+  /// Which dictionaries the spellchecker uses. Paths are relative to the book root.
+  dictionary: DictionarySettings,
+
+  /// If true, then compile and run the Rust code blocks in all Markdown strings.
+  compile_code_blocks: Option<bool>,
+
+  /// What to do with questions that do not have an `id`.
+  missing_ids: MissingIds,
+
+  /// How to generate IDs for questions that do not have one.
+  id_strategy: IdStrategy,
+
+  /// How to compile Rust programs. Paths are relative to the book root.
+  rust: RustSettings,
+
+  /// Limits on running Rust programs.
+  limits: ResourceLimits,
+
+  /// Where to cache the results of running programs, relative to the book root.
+  /// Defaults to a directory in the book's build directory.
+  cache_dir: PathBuf,
 
   dev_mode: bool,
 }
 
+/// How the preprocessor handles questions without an `id`.
+///
+/// Quiz files are never modified during a build. Use `mdbook-quiz add-ids` to
+/// permanently add IDs to the source files.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MissingIds {
+  /// Generate an ID with the configured [`IdStrategy`], without writing it to disk.
+  Generate,
+
+  /// Fail validation.
+  Error,
+}
+
+impl QuizConfig {
+  fn new(config: &Config) -> Result<Self> {
+    let empty = toml::value::Table::new();
+    let config_toml = config
+      .get_preprocessor(QuizPreprocessor::NAME)
+      .unwrap_or(&empty);
+    let parse_bool = |key: &str| config_toml.get(key).map(|value| value.as_bool().unwrap());
+
+    let missing_ids = match config_toml
+      .get("missing-ids")
+      .map(|value| value.as_str().unwrap())
+    {
+      None | Some("generate") => MissingIds::Generate,
+      Some("error") => MissingIds::Error,
+      Some(other) => {
+        anyhow::bail!("Invalid value for missing-ids: `{other}`. Expected `generate` or `error`.")
+      }
+    };
+
+    let id_strategy = match config_toml.get("id-strategy") {
+      Some(value) => value
+        .as_str()
+        .unwrap()
+        .parse::<IdStrategy>()
+        .map_err(anyhow::Error::msg)?,
+      None => IdStrategy::default(),
+    };
+
+    let mut rust = match config_toml.get("rust") {
+      Some(value) => value
+        .clone()
+        .try_into::<RustSettings>()
+        .context("Invalid value for rust")?,
+      None => RustSettings::default(),
+    };
+    if rust.edition.is_none() {
+      rust.edition = config.rust.edition.map(|edition| {
+        match edition {
+          RustEdition::E2015 => "2015",
+          RustEdition::E2018 => "2018",
+          RustEdition::E2021 => "2021",
+        }
+        .to_string()
+      });
+    }
+
+    let limits = match config_toml.get("limits") {
+      Some(value) => value
+        .clone()
+        .try_into::<ResourceLimits>()
+        .context("Invalid value for limits")?,
+      None => ResourceLimits::default(),
+    };
+
+    // Keys that accept either a single string or an array of strings.
+    let parse_strings = |key: &str| -> Result<Option<Vec<String>>> {
+      match config_toml.get(key) {
+        None => Ok(None),
+        Some(toml::Value::String(s)) => Ok(Some(vec![s.clone()])),
+        Some(value) => value
+          .clone()
+          .try_into()
+          .map(Some)
+          .with_context(|| format!("Invalid value for {key}")),
+      }
+    };
+    let explicit_languages = parse_strings("spellcheck-language")?;
+    let explicit_spellcheck_language = explicit_languages.is_some();
+    let languages = explicit_languages.unwrap_or_else(|| {
+      // Dictionaries are named by the primary language subtag, e.g. `en` for `en-US`.
+      let language = config.book.language.as_deref().unwrap_or("en");
+      let primary = language.split(['-', '_']).next().unwrap_or_default();
+      vec![primary.to_lowercase()]
+    });
+    let dictionary = DictionarySettings {
+      languages,
+      dictionary_dir: config_toml
+        .get("dictionary-dir")
+        .map(|value| value.as_str().unwrap().into()),
+      more_words: parse_strings("more-words")?
+        .unwrap_or_default()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect(),
+    };
+
+    Ok(QuizConfig {
+      fullscreen: parse_bool("fullscreen"),
+      cache_answers: parse_bool("cache-answers"),
+      default_language: config_toml
+        .get("default-language")
+        .map(|value| value.as_str().unwrap().into()),
+      explicit_spellcheck_language,
+      dictionary,
+      spellcheck: parse_bool("spellcheck"),
+      compile_code_blocks: parse_bool("compile-code-blocks"),
+      missing_ids,
+      id_strategy,
+      rust,
+      limits,
+      cache_dir: match config_toml.get("cache-dir") {
+        Some(value) => value
+          .as_str()
+          .context("Invalid value for cache-dir: expected a path")?
+          .into(),
+        None => config.build.build_dir.join(DEFAULT_CACHE_DIR),
+      },
+      dev_mode: env::var("QUIZ_DEV_MODE").is_ok(),
+    })
+  }
+
+  /// Loads the spellchecker's dictionaries if spellchecking is enabled, so that a misconfigured
+  /// dictionary is reported once up front.
+  ///
+  /// If there is no dictionary for the book's language, spellchecking is disabled with a warning,
+  /// unless the language was set explicitly with `spellcheck-language`.
+  fn preload_dictionary(&mut self, book_root: &Path) -> Result<()> {
+    if self.spellcheck != Some(true) {
+      return Ok(());
+    }
+
+    let dictionary = self.validation_config(book_root).dictionary;
+    if !self.explicit_spellcheck_language {
+      let missing =
+        (dictionary.languages.iter()).find(|language| !dictionary.has_dictionary(language));
+      if let Some(language) = missing {
+        eprintln!(
+          "Warning: Not spellchecking quizzes, since there is no dictionary for the book's \
+           language `{language}`. Set `dictionary-dir` to a directory containing \
+           `{language}/index.aff` and `{language}/index.dic`, or set `spellcheck-language`."
+        );
+        self.spellcheck = Some(false);
+        return Ok(());
+      }
+    }
+
+    dictionary.preload()
+  }
+
+  fn validation_config(&self, book_root: &Path) -> ValidationConfig {
+    ValidationConfig {
+      spellcheck: self.spellcheck.unwrap_or(false),
+      compile_code_blocks: self.compile_code_blocks.unwrap_or(false),
+      require_ids: self.missing_ids == MissingIds::Error,
+      id_strategy: self.id_strategy,
+      book_root: Some(book_root.to_owned()),
+      rust: RustSettings {
+        crates: self
+          .rust
+          .crates
+          .as_ref()
+          .map(|crates| book_root.join(crates)),
+        ..self.rust.clone()
+      },
+      limits: self.limits.clone(),
+      dictionary: DictionarySettings {
+        dictionary_dir: self
+          .dictionary
+          .dictionary_dir
+          .as_ref()
+          .map(|dir| book_root.join(dir)),
+        more_words: self
+          .dictionary
+          .more_words
+          .iter()
+          .map(|path| book_root.join(path))
+          .collect(),
+        ..self.dictionary.clone()
+      },
+      cache_dir: Some(book_root.join(&self.cache_dir)),
+    }
+  }
+}
+
+/// A `{{#quiz ...}}` directive in a chapter.
+struct QuizDirective<'a> {
+  /// The range of the whole directive in the chapter.
+  range: Range<usize>,
+
+  /// The path to the quiz file, relative to the chapter.
+  path: &'a str,
+
+  /// The range in the chapter of the `key=value` arguments after the path, which select some of
+  /// the quiz's questions. Empty if every question is embedded.
+  args: Range<usize>,
+}
+
+/// Finds every `{{#quiz ...}}` directive in a chapter.
+fn quiz_directives(content: &str) -> impl Iterator<Item = QuizDirective<'_>> {
+  static REGEX: OnceLock<Regex> = OnceLock::new();
+  let regex =
+    REGEX.get_or_init(|| Regex::new(r"\{\{#quiz ([^}]+?)((?:\s+[\w-]+=[^}\s]*)*)\s*\}\}").unwrap());
+  regex.captures_iter(content).map(|captures| {
+    let args = captures.get(2).unwrap();
+    let trimmed = args.as_str().trim_start();
+    QuizDirective {
+      range: captures.get(0).unwrap().range(),
+      path: captures.get(1).unwrap().as_str(),
+      args: args.end() - trimmed.len()..args.end(),
+    }
+  })
+}
+
 struct QuizPreprocessor {
   config: QuizConfig,
+  book_root: PathBuf,
   question_ids: IdSet,
+  /// Quiz files that have already been validated, since a quiz can be embedded in several
+  /// places but its IDs should only be counted once.
+  validated_quizzes: Mutex<HashSet<PathBuf>>,
+  /// Whether the build has warned that it generated IDs with a different strategy than the
+  /// configured one.
+  warned_id_strategy: AtomicBool,
   #[cfg(feature = "aquascope")]
   aquascope: mdbook_aquascope::AquascopePreprocessor,
 }
@@ -97,54 +366,202 @@ impl QuizPreprocessor {
     Ok(())
   }
 
-  fn auto_id(&self, path: &Path, contents: &str) -> Result<bool> {
-    use toml_edit::{Document, Formatted, Item, Value};
-    let mut doc = contents.parse::<Document>()?;
-    let qs = doc
+  /// Fills in an ID for each question without one.
+  ///
+  /// Random IDs would change on every build, so [`IdStrategy::PathIndex`] is used instead
+  /// of a non-deterministic strategy, with a warning the first time it happens.
+  fn generate_ids(&self, quiz_key: &str, content: &mut toml::Value) -> Result<()> {
+    let strategy = match self.config.id_strategy {
+      strategy if strategy.is_deterministic() => strategy,
+      _ => IdStrategy::PathIndex,
+    };
+    let ids = strategy.generate(quiz_key, &content.clone().try_into()?);
+
+    let questions = content
       .get_mut("questions")
-      .unwrap()
-      .as_array_of_tables_mut()
-      .unwrap();
-    let mut changed = false;
-    for q in qs.iter_mut() {
-      if !q.contains_key("id") {
-        changed = true;
-        let id = Uuid::new_v4().to_string();
-        q.insert("id", Item::Value(Value::String(Formatted::new(id))));
+      .and_then(|questions| questions.as_array_mut())
+      .context("Must contain questions")?;
+    for (question, id) in questions.iter_mut().zip(ids) {
+      let question = question.as_table_mut().unwrap();
+      if !question.contains_key("id") {
+        question.insert("id".into(), toml::Value::String(id));
+        if strategy != self.config.id_strategy
+          && !self.warned_id_strategy.swap(true, Ordering::Relaxed)
+        {
+          eprintln!(
+            "Warning: Some questions in {quiz_key} have no ID, so they were given `{strategy}` IDs \
+             for this build, since `{}` IDs would change on every build. \
+             Run `mdbook-quiz add-ids` to give them permanent IDs.",
+            self.config.id_strategy
+          );
+        }
       }
     }
-    if changed {
-      fs::write(path, doc.to_string())?;
+    Ok(())
+  }
+
+  /// Removes the hidden lines from the programs of Tracing questions, which are only
+  /// needed to compile them during validation.
+  fn strip_hidden_lines(&self, content: &mut toml::Value) -> Result<()> {
+    let questions = content
+      .get_mut("questions")
+      .and_then(|questions| questions.as_array_mut())
+      .context("Must contain questions")?;
+    for question in questions {
+      if question.get("type").and_then(|ty| ty.as_str()) != Some("Tracing") {
+        continue;
+      }
+      let Some(prompt) = question.get_mut("prompt").and_then(|p| p.as_table_mut()) else {
+        continue;
+      };
+      let strip = |program: &mut toml::Value| {
+        if let Some(contents) = program.as_str() {
+          *program = toml::Value::String(mdbook_quiz_validate::visible_program(contents));
+        }
+      };
+      if let Some(files) = prompt
+        .get_mut("files")
+        .and_then(|files| files.as_table_mut())
+      {
+        files.iter_mut().for_each(|(_, program)| strip(program));
+      }
+      if let Some(program) = prompt.get_mut("program") {
+        strip(program);
+      }
     }
-    Ok(changed)
+    Ok(())
   }
 
-  fn process_quiz(&self, chapter_dir: &Path, quiz_path: &str) -> Result<String> {
-    let quiz_path_rel = Path::new(quiz_path);
+  fn process_quiz(
+    &self,
+    chapter_dir: &Path,
+    chapter_path: &Path,
+    content: &str,
+    directive: &QuizDirective,
+  ) -> Result<String> {
+    let quiz_path_rel = Path::new(directive.path);
     let quiz_path_abs = chapter_dir.join(quiz_path_rel);
 
-    let mut content_toml = fs::read_to_string(&quiz_path_abs)
+    let contents = fs::read_to_string(&quiz_path_abs)
       .with_context(|| format!("Failed to read quiz file: {}", quiz_path_abs.display()))?;
 
-    mdbook_quiz_validate::validate(
-      &quiz_path_abs,
-      &content_toml,
+    let is_new = self
+      .validated_quizzes
+      .lock()
+      .unwrap()
+      .insert(quiz_path_abs.canonicalize()?);
+    if is_new {
+      mdbook_quiz_validate::validate_with_config(
+        &quiz_path_abs,
+        &contents,
+        &self.question_ids,
+        &self.config.validation_config(&self.book_root),
+      )?;
+    }
+
+    // Quizzes in other formats and older quizzes are sent to the frontend in the current
+    // TOML format.
+    let content_toml = QuizFormat::from_path(&quiz_path_abs).to_toml(&contents)?;
+    let content_toml = mdbook_quiz_validate::migrate(&content_toml)?.unwrap_or(content_toml);
+
+    let quiz_name = quiz_path_rel.file_stem().unwrap().to_string_lossy();
+    // A quiz in `intro.quiz.md` is named `intro`, like one in `intro.toml`.
+    let quiz_name = match quiz_name.strip_suffix(".quiz") {
+      Some(name) if mdbook_quiz_validate::is_markdown_quiz(quiz_path_rel) => name,
+      _ => &quiz_name,
+    };
+    let quiz_key = mdbook_quiz_validate::quiz_key(&self.book_root, &quiz_path_abs);
+
+    let args = &content[directive.args.clone()];
+    if args.is_empty() {
+      return self.render_quiz(quiz_name, &quiz_key, &content_toml, None);
+    }
+
+    let (quiz, _) = mdbook_quiz_validate::parse_quiz(&content_toml)?;
+    mdbook_quiz_validate::validate_selection(chapter_path, content, directive.args.clone(), &quiz)?;
+    let selection = args.parse::<QuestionSelection>()?;
+
+    // Each selection from a quiz needs its own name, since the frontend stores answers by name.
+    let mut selection_name = String::new();
+    for c in args.chars() {
+      if c.is_alphanumeric() {
+        selection_name.push(c);
+      } else if !selection_name.ends_with('-') {
+        selection_name.push('-');
+      }
+    }
+    let quiz_name = format!("{quiz_name}-{}", selection_name.trim_end_matches('-'));
+    self.render_quiz(&quiz_name, &quiz_key, &content_toml, Some(&selection))
+  }
+
+  fn process_inline_quiz(&self, quiz: &InlineQuiz) -> Result<String> {
+    mdbook_quiz_validate::validate_inline(
+      quiz,
       &self.question_ids,
-      self.config.spellcheck.unwrap_or(false),
+      &self.config.validation_config(&self.book_root),
     )?;
 
-    let changed = self.auto_id(&quiz_path_abs, &content_toml)?;
-    if changed {
-      content_toml = fs::read_to_string(&quiz_path_abs)?;
-    }
+    let content_toml = mdbook_quiz_validate::migrate(&quiz.contents)?;
+    let content_toml = content_toml.as_deref().unwrap_or(&quiz.contents);
+
+    let chapter_name = quiz.chapter_path.file_stem().unwrap().to_string_lossy();
+    let quiz_name = format!("{chapter_name}-quiz-{}", quiz.index);
+    self.render_quiz(
+      &quiz_name,
+      &quiz.quiz_key(&self.book_root),
+      content_toml,
+      None,
+    )
+  }
+
+  /// Renders a quiz with TOML-format `content_toml` in the current version of the quiz format
+  /// as a placeholder for the frontend, keeping only the questions chosen by `selection` if given.
+  fn render_quiz(
+    &self,
+    quiz_name: &str,
+    quiz_key: &str,
+    content_toml: &str,
+    selection: Option<&QuestionSelection>,
+  ) -> Result<String> {
+    let (quiz, _) = mdbook_quiz_validate::parse_quiz(content_toml)?;
+    let selected = match selection {
+      Some(selection) => selection.select(&quiz),
+      None => (0..quiz.questions.len()).collect(),
+    };
+    let draws = match selection.and_then(|selection| selection.draw) {
+      Some(draw) if draw < selected.len() => vec![Draw {
+        questions: (0..selected.len()).collect(),
+        draw,
+      }],
+      Some(_) => Vec::new(),
+      None => mdbook_quiz_validate::quiz_draws(&quiz, &selected),
+    };
 
-    #[allow(unused_mut)]
     let mut content = content_toml.parse::<toml::Value>()?;
 
+    if self.config.missing_ids == MissingIds::Generate {
+      self.generate_ids(quiz_key, &mut content)?;
+    }
+
+    // Questions are selected after generating IDs so that a question's ID is the same no matter
+    // which directive embeds it.
+    if selection.is_some() {
+      let questions = content
+        .get_mut("questions")
+        .and_then(|questions| questions.as_array_mut())
+        .context("Must contain questions")?;
+      let mut index = 0..;
+      questions.retain(|_| selected.contains(&index.next().unwrap()));
+    }
+
+    // The frontend is sent the pools resolved into draws instead.
+    content.as_table_mut().unwrap().remove("pools");
+
+    self.strip_hidden_lines(&mut content)?;
+
     #[cfg(feature = "aquascope")]
     self.add_aquascope_blocks(&mut content)?;
 
-    let quiz_name = quiz_path_rel.file_stem().unwrap().to_string_lossy();
     let content_json = serde_json::to_string(&content)?;
 
     let mut html = String::from("<div class=\"quiz-placeholder\"");
@@ -157,8 +574,11 @@ impl QuizPreprocessor {
         html_escape::encode_double_quoted_attribute(v)
       )
     };
-    add_data("quiz-name", &quiz_name)?;
+    add_data("quiz-name", quiz_name)?;
     add_data("quiz-questions", &content_json)?;
+    if !draws.is_empty() {
+      add_data("quiz-pools", &serde_json::to_string(&draws)?)?;
+    }
     if let Some(true) = self.config.fullscreen {
       add_data("quiz-fullscreen", "")?;
     }
@@ -177,60 +597,52 @@ impl QuizPreprocessor {
   }
 }
 
-impl SimplePreprocessor for QuizPreprocessor {
-  fn name() -> &'static str {
-    "quiz"
-  }
+impl QuizPreprocessor {
+  const NAME: &'static str = "quiz";
 
   fn build(ctx: &PreprocessorContext) -> Result<Self> {
     log::info!("Running the mdbook-quiz preprocessor");
 
-    let config_toml = ctx.config.get_preprocessor(Self::name()).unwrap();
-    let parse_bool = |key: &str| config_toml.get(key).map(|value| value.as_bool().unwrap());
-
-    let dev_mode = env::var("QUIZ_DEV_MODE").is_ok();
-    let config = QuizConfig {
-      fullscreen: parse_bool("fullscreen"),
-      cache_answers: parse_bool("cache-answers"),
-      default_language: config_toml
-        .get("default-language")
-        .map(|value| value.as_str().unwrap().into()),
-      more_words: config_toml
-        .get("more-words")
-        .map(|value| value.as_str().unwrap().into()),
-      spellcheck: parse_bool("spellcheck"),
-      dev_mode,
-    };
-
-    if let Some(more_words) = &config.more_words {
-      mdbook_quiz_validate::register_more_words(more_words)?;
-    }
+    let mut config = QuizConfig::new(&ctx.config)?;
+    config.preload_dictionary(&ctx.root)?;
 
     Ok(QuizPreprocessor {
       config,
+      book_root: ctx.root.canonicalize()?,
       question_ids: IdSet::default(),
+      validated_quizzes: Mutex::default(),
+      warned_id_strategy: AtomicBool::new(false),
       #[cfg(feature = "aquascope")]
       aquascope: mdbook_aquascope::AquascopePreprocessor::new()
         .context("Aquascope failed to initialize")?,
     })
   }
 
+  /// Returns the HTML to replace each quiz in `content`, the Markdown of the chapter read from
+  /// `chapter_path`, whose relative paths are resolved against `chapter_dir`.
   fn replacements(
     &self,
     chapter_dir: &Path,
+    chapter_path: &Path,
     content: &str,
-  ) -> Result<Vec<(std::ops::Range<usize>, String)>> {
-    static REGEX: OnceLock<Regex> = OnceLock::new();
-    let regex = REGEX.get_or_init(|| Regex::new(r"\{\{#quiz ([^}]+)\}\}").unwrap());
-    regex
-      .captures_iter(content)
-      .map(|captures| {
-        let range = captures.get(0).unwrap().range();
-        let quiz_path = captures.get(1).unwrap().as_str();
-        let html = self.process_quiz(chapter_dir, quiz_path)?;
-        Ok((range, html))
+  ) -> Result<Vec<(Range<usize>, String)>> {
+    let mut replacements = quiz_directives(content)
+      .map(|directive| {
+        let html = self.process_quiz(chapter_dir, chapter_path, content, &directive)?;
+        Ok((directive.range, html))
       })
-      .collect()
+      .collect::<Result<Vec<_>>>()?;
+
+    let inline_quizzes = InlineQuiz::find_all(chapter_path, content);
+    if !inline_quizzes.is_empty() {
+      for quiz in inline_quizzes {
+        let html = self.process_inline_quiz(&quiz)?;
+        replacements.push((quiz.range, html));
+      }
+      replacements.sort_by_key(|(range, _)| range.start);
+    }
+
+    Ok(replacements)
   }
 
   fn all_assets(&self) -> Vec<Asset> {
@@ -247,13 +659,48 @@ impl SimplePreprocessor for QuizPreprocessor {
   }
 }
 
+#[derive(Parser)]
+#[clap(author, version, about)]
+struct Args {
+  #[clap(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Check whether a renderer is supported by this preprocessor.
+  Supports { renderer: String },
+
+  /// Validate every quiz in a book without building it.
+  Check(check::CheckArgs),
+
+  /// Add an ID to every question in a book that does not have one.
+  AddIds(add_ids::AddIdsArgs),
+
+  /// Upgrade every quiz in a book to the current version of the quiz format.
+  Migrate(migrate::MigrateArgs),
+}
+
 fn main() {
-  mdbook_preprocessor_utils::main::<QuizPreprocessor>()
+  let args = Args::parse();
+  let result = match args.command {
+    Some(Command::Check(args)) => check::run(args),
+    Some(Command::AddIds(args)) => add_ids::run(args),
+    Some(Command::Migrate(args)) => migrate::run(args),
+    // Every renderer is supported.
+    Some(Command::Supports { .. }) => Ok(()),
+    None => driver::run(),
+  };
+
+  if let Err(e) = result {
+    eprintln!("Error: {e:#}");
+    process::exit(1);
+  }
 }
 
 #[cfg(test)]
 mod test {
-  use super::QuizPreprocessor;
+  use super::{driver::compile, QuizConfig};
   use anyhow::Result;
   use mdbook_preprocessor_utils::{mdbook::BookItem, testing::MdbookTestHarness};
   use mdbook_quiz_schema::{Question, Quiz};
@@ -284,7 +731,7 @@ mod test {
     )?;
 
     let config = serde_json::json!({});
-    let mut book = harness.compile::<QuizPreprocessor>(config)?;
+    let mut book = compile(&harness, config)?;
 
     let contents = match book.sections.remove(0) {
       BookItem::Chapter(chapter) => chapter.content,
@@ -293,12 +740,279 @@ mod test {
     assert!(!contents.contains("{{#quiz"));
     assert!(contents.contains("<script"));
 
+    assert!(
+      contents.contains("&quot;id&quot;"),
+      "ID not automatically generated"
+    );
+
     let quiz_contents = fs::read_to_string(quiz_path)?;
     let quiz: Quiz = toml::from_str(&quiz_contents)?;
     let Question::ShortAnswer(q) = &quiz.questions[0] else {
       panic!("Invalid quiz")
     };
-    assert!(q.0.id.is_some(), "ID not automatically inserted");
+    assert!(q.0.id.is_none(), "Quiz file should not be modified");
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_hidden_lines() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    fs::write(
+      harness.root().join("src").join("quiz.toml"),
+      r#"
+[[questions]]
+type = "Tracing"
+prompt.program = """
+# #![allow(unused)]
+fn main() {
+  println!("Hello world");
+}
+"""
+answer.doesCompile = true
+answer.stdout = "Hello world"
+"#,
+    )?;
+    fs::write(
+      harness.root().join("src").join("chapter_1.md"),
+      "{{#quiz quiz.toml}}",
+    )?;
+
+    let mut book = compile(&harness, serde_json::json!({}))?;
+    let contents = match book.sections.remove(0) {
+      BookItem::Chapter(chapter) => chapter.content,
+      _ => unreachable!(),
+    };
+    assert!(contents.contains("Hello world"));
+    assert!(!contents.contains("allow(unused)"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_markdown_quiz() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    fs::write(
+      harness.root().join("src").join("intro.quiz.md"),
+      r#"# MultipleChoice
+
+What is 1 + 1?
+
+- [x] 2
+- [ ] 3
+"#,
+    )?;
+    fs::write(
+      harness.root().join("src").join("chapter_1.md"),
+      "{{#quiz intro.quiz.md}}",
+    )?;
+
+    let mut book = compile(&harness, serde_json::json!({}))?;
+    let contents = match book.sections.remove(0) {
+      BookItem::Chapter(chapter) => chapter.content,
+      _ => unreachable!(),
+    };
+    assert!(contents.contains("data-quiz-name=\"intro\""));
+    assert!(contents.contains("&quot;distractors&quot;:[&quot;3&quot;]"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_json_and_yaml_quizzes() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    fs::write(
+      harness.root().join("src").join("first.json"),
+      r#"{"questions": [{"type": "ShortAnswer", "prompt": {"prompt": "JSON?"}, "answer": {"answer": "yes"}}]}"#,
+    )?;
+    fs::write(
+      harness.root().join("src").join("second.yaml"),
+      "questions:\n  - type: ShortAnswer\n    prompt: {prompt: YAML?}\n    answer: {answer: 'yes'}\n",
+    )?;
+    fs::write(
+      harness.root().join("src").join("chapter_1.md"),
+      "{{#quiz first.json}}\n\n{{#quiz second.yaml}}",
+    )?;
+
+    let mut book = compile(&harness, serde_json::json!({}))?;
+    let contents = match book.sections.remove(0) {
+      BookItem::Chapter(chapter) => chapter.content,
+      _ => unreachable!(),
+    };
+    assert!(contents.contains("data-quiz-name=\"first\""));
+    assert!(contents.contains("&quot;prompt&quot;:&quot;JSON?&quot;"));
+    assert!(contents.contains("data-quiz-name=\"second\""));
+    assert!(contents.contains("&quot;prompt&quot;:&quot;YAML?&quot;"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_question_selection() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let question = |id: &str, tag: &str| {
+      format!(
+        "[[questions]]\nid = \"{id}\"\ntags = [\"{tag}\"]\ntype = \"ShortAnswer\"\n\
+         prompt.prompt = \"Question {id}\"\nanswer.answer = \"{id}\"\n"
+      )
+    };
+    fs::write(
+      harness.root().join("src").join("bank.toml"),
+      question("a", "ownership") + &question("b", "traits") + &question("c", "ownership"),
+    )?;
+    fs::write(
+      harness.root().join("src").join("chapter_1.md"),
+      "{{#quiz bank.toml ids=a,b}}\n\n{{#quiz bank.toml tags=ownership range=1..}}",
+    )?;
+
+    let mut book = compile(&harness, serde_json::json!({}))?;
+    let contents = match book.sections.remove(0) {
+      BookItem::Chapter(chapter) => chapter.content,
+      _ => unreachable!(),
+    };
+    let (first, second) = contents.split_once("</div>").unwrap();
+    assert!(first.contains("data-quiz-name=\"bank-ids-a-b\""));
+    assert!(first.contains("Question a") && first.contains("Question b"));
+    assert!(!first.contains("Question c"));
+    assert!(second.contains("data-quiz-name=\"bank-tags-ownership-range-1\""));
+    assert!(second.contains("Question c"));
+    assert!(!second.contains("Question a") && !second.contains("Question b"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_question_pools() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let question = |id: &str| {
+      format!(
+        "[[questions]]\nid = \"{id}\"\ntype = \"ShortAnswer\"\n\
+         prompt.prompt = \"Question {id}\"\nanswer.answer = \"{id}\"\n"
+      )
+    };
+    let questions = ["a", "b", "c", "d"].map(question).concat();
+    fs::write(
+      harness.root().join("src").join("pooled.toml"),
+      format!("pools = [{{ ids = [\"b\", \"c\", \"d\"], draw = 2 }}]\n{questions}"),
+    )?;
+    fs::write(
+      harness.root().join("src").join("bank.toml"),
+      ["e", "f", "g", "h"].map(question).concat(),
+    )?;
+    fs::write(
+      harness.root().join("src").join("chapter_1.md"),
+      "{{#quiz pooled.toml}}\n\n{{#quiz bank.toml range=1.. draw=1}}",
+    )?;
+
+    let mut book = compile(&harness, serde_json::json!({}))?;
+    let contents = match book.sections.remove(0) {
+      BookItem::Chapter(chapter) => chapter.content,
+      _ => unreachable!(),
+    };
+    let (first, second) = contents.split_once("</div>").unwrap();
+    assert!(
+      first.contains("data-quiz-pools=\"[{&quot;questions&quot;:[1,2,3],&quot;draw&quot;:2}]\"")
+    );
+    assert!(!first.contains("&quot;pools&quot;"));
+    assert!(
+      second.contains("data-quiz-pools=\"[{&quot;questions&quot;:[0,1,2],&quot;draw&quot;:1}]\"")
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_inline_quiz() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let chapter = r#"Some prose.
+
+```quiz
+[[questions]]
+type = "ShortAnswer"
+prompt.prompt = "What is the keyword for declaring a variable?"
+answer.answer = "let"
+```
+
+More prose.
+"#;
+    // Identical chapters are still told apart by their source files.
+    let src_dir = harness.root().join("src");
+    fs::write(src_dir.join("chapter_1.md"), chapter)?;
+    fs::write(src_dir.join("chapter_2.md"), chapter)?;
+    fs::write(
+      src_dir.join("SUMMARY.md"),
+      "# Summary\n\n- [Chapter 1](./chapter_1.md)\n- [Chapter 2](./chapter_2.md)\n",
+    )?;
+
+    let book = compile(&harness, serde_json::json!({}))?;
+    let contents = book
+      .sections
+      .into_iter()
+      .filter_map(|item| match item {
+        BookItem::Chapter(chapter) => Some(chapter.content),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    assert!(!contents[0].contains("```quiz"));
+    assert!(contents[0].contains("data-quiz-name=\"chapter_1-quiz-0\""));
+    assert!(contents[0].contains("chapter_1.md#quiz-0#0"));
+    assert!(contents[0].contains("More prose."));
+    assert!(contents[1].contains("chapter_2.md#quiz-0#0"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_missing_ids_error() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    fs::write(
+      harness.root().join("src").join("quiz.toml"),
+      r#"
+    [[questions]]
+    type = "ShortAnswer"
+    prompt.prompt = "Hello world"
+    answer.answer = "No"
+    "#,
+    )?;
+    fs::write(
+      harness.root().join("src").join("chapter_1.md"),
+      "{{#quiz quiz.toml}}",
+    )?;
+
+    let config = serde_json::json!({ "missing-ids": "error" });
+    assert!(compile(&harness, config).is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_cache_dir() -> Result<()> {
+    let config = "[preprocessor.quiz]\ncache-dir = 1".parse()?;
+    let error = QuizConfig::new(&config).err().unwrap();
+    assert!(error.to_string().contains("cache-dir"));
+    Ok(())
+  }
+
+  #[test]
+  fn test_spellcheck_language() -> Result<()> {
+    let root = std::path::Path::new(".");
+    let config = |toml: &str| -> Result<QuizConfig> {
+      QuizConfig::new(&format!("{toml}\n[preprocessor.quiz]\nspellcheck = true").parse()?)
+    };
+
+    let mut english = config("[book]\nlanguage = \"en-US\"")?;
+    assert_eq!(english.dictionary.languages, ["en"]);
+    english.preload_dictionary(root)?;
+    assert_eq!(english.spellcheck, Some(true));
+
+    let mut german = config("[book]\nlanguage = \"de-DE\"")?;
+    assert_eq!(german.dictionary.languages, ["de"]);
+    german.preload_dictionary(root)?;
+    assert_eq!(german.spellcheck, Some(false));
+
+    let explicit = "[preprocessor.quiz]\nspellcheck = true\nspellcheck-language = \"de\"";
+    let mut explicit = QuizConfig::new(&explicit.parse()?)?;
+    assert!(explicit.preload_dictionary(root).is_err());
 
     Ok(())
   }
@@ -0,0 +1,122 @@
+//! The `mdbook-quiz migrate` command, which upgrades every quiz to the current quiz format.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use mdbook_quiz_validate::QuizFormat;
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use crate::book;
+
+#[derive(Args)]
+pub struct MigrateArgs {
+  /// Root directory of the book, i.e. the directory containing `book.toml`.
+  #[clap(default_value = ".")]
+  dir: PathBuf,
+
+  /// Don't write any files, and exit with an error if any quiz is not in the current format.
+  #[clap(long)]
+  check: bool,
+}
+
+/// Migrates every quiz in the book at `root`, returning the paths of the quizzes
+/// that were (or, if `check` is true, would have been) changed.
+fn migrate_book(root: &Path, check: bool) -> Result<Vec<PathBuf>> {
+  let book = book::load(root)?;
+  let mut seen = HashSet::new();
+  let mut changed = Vec::new();
+  for reference in book::quiz_references(&book) {
+    let contents = fs::read_to_string(&reference.path).with_context(|| {
+      format!(
+        "Failed to read quiz file referenced by {}: {}",
+        reference.chapter.display(),
+        reference.path.display()
+      )
+    })?;
+    // Only TOML quizzes are rewritten, and quizzes in other formats are migrated when the book is built.
+    if !seen.insert(reference.path.canonicalize()?)
+      || QuizFormat::from_path(&reference.path) != QuizFormat::Toml
+    {
+      continue;
+    }
+
+    let new_contents = mdbook_quiz_validate::migrate(&contents)
+      .with_context(|| format!("Failed to migrate quiz: {}", reference.path.display()))?;
+    if let Some(new_contents) = new_contents {
+      if !check {
+        fs::write(&reference.path, new_contents)?;
+      }
+      changed.push(book::display_path(&reference.path));
+    }
+  }
+  Ok(changed)
+}
+
+pub fn run(args: MigrateArgs) -> Result<()> {
+  let changed = migrate_book(&args.dir, args.check)?;
+
+  let verb = if args.check { "Outdated" } else { "Migrated" };
+  for path in &changed {
+    eprintln!("{verb} {}", path.display());
+  }
+
+  if args.check {
+    anyhow::ensure!(
+      changed.is_empty(),
+      "{} quizzes are not in the current format. Run `mdbook-quiz migrate` to upgrade them.",
+      changed.len()
+    );
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::migrate_book;
+  use anyhow::Result;
+  use mdbook_preprocessor_utils::testing::MdbookTestHarness;
+  use mdbook_quiz_schema::{MultipleChoiceAnswerFormat, Question, CURRENT_VERSION};
+  use std::fs;
+
+  #[test]
+  fn test_migrate() -> Result<()> {
+    let harness = MdbookTestHarness::new()?;
+    let src_dir = harness.root().join("src");
+    let quiz_path = src_dir.join("quiz.toml");
+    let legacy = r#"
+    # A comment that should be preserved
+    [[questions]]
+    type = "MultipleChoice"
+    prompt.prompt = "Hello world"
+    prompt.choices = [
+      "Yes",
+      "No",
+    ]
+    answer.answer = 1
+    "#;
+    fs::write(&quiz_path, legacy)?;
+    fs::write(src_dir.join("chapter_1.md"), "{{#quiz quiz.toml}}")?;
+
+    assert_eq!(migrate_book(harness.root(), true)?.len(), 1);
+    assert_eq!(fs::read_to_string(&quiz_path)?, legacy);
+
+    migrate_book(harness.root(), false)?;
+    let contents = fs::read_to_string(&quiz_path)?;
+    assert!(contents.contains("# A comment that should be preserved"));
+    let quiz: mdbook_quiz_schema::Quiz = toml::from_str(&contents)?;
+    assert_eq!(quiz.version, Some(CURRENT_VERSION));
+    let Question::MultipleChoice(q) = &quiz.questions[0] else {
+      panic!("Invalid quiz")
+    };
+    assert!(matches!(&q.0.answer.answer, MultipleChoiceAnswerFormat::Single(a) if a.0 == "No"));
+    assert_eq!(q.0.prompt.answer_index, Some(1));
+
+    assert!(migrate_book(harness.root(), true)?.is_empty());
+
+    Ok(())
+  }
+}
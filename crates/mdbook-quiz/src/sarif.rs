@@ -0,0 +1,72 @@
+//! Conversion of validation diagnostics into the [SARIF 2.1.0] format,
+//! which is understood by most code-review tools.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use mdbook_quiz_validate::{Severity, ValidationDiagnostic};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn result(diagnostic: &ValidationDiagnostic) -> Value {
+  let level = match diagnostic.severity {
+    Severity::Error => "error",
+    Severity::Warning => "warning",
+  };
+
+  // SARIF URIs always use forward slashes.
+  let uri = diagnostic.file.to_string_lossy().replace('\\', "/");
+  let mut location = json!({ "artifactLocation": { "uri": uri } });
+  if let Some(span) = &diagnostic.span {
+    location["region"] = json!({
+      "startLine": span.start.line,
+      "startColumn": span.start.column,
+      "endLine": span.end.line,
+      "endColumn": span.end.column,
+      "byteOffset": span.offset,
+      "byteLength": span.length,
+    });
+  }
+
+  let mut result = json!({
+    "level": level,
+    "message": { "text": diagnostic.message },
+    "locations": [{ "physicalLocation": location }],
+  });
+  if let Some(rule) = &diagnostic.rule {
+    result["ruleId"] = json!(rule);
+  }
+  if let Some(id) = &diagnostic.question_id {
+    result["properties"] = json!({ "questionId": id });
+  }
+  result
+}
+
+/// Builds a SARIF log containing a single run of mdbook-quiz.
+pub fn log(diagnostics: &[ValidationDiagnostic]) -> Value {
+  let rules = diagnostics
+    .iter()
+    .filter_map(|d| d.rule.as_deref())
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .map(|rule| json!({ "id": rule }))
+    .collect::<Vec<_>>();
+
+  json!({
+    "$schema": SARIF_SCHEMA,
+    "version": "2.1.0",
+    "runs": [{
+      "tool": {
+        "driver": {
+          "name": "mdbook-quiz",
+          "version": env!("CARGO_PKG_VERSION"),
+          "informationUri": env!("CARGO_PKG_REPOSITORY"),
+          "rules": rules,
+        }
+      },
+      "columnKind": "unicodeCodePoints",
+      "results": diagnostics.iter().map(result).collect::<Vec<_>>(),
+    }]
+  })
+}
@@ -1,4 +1,9 @@
-import { type Quiz, QuizView, renderIde } from "@wcrichto/quiz";
+import {
+  type QuestionDraw,
+  type Quiz,
+  QuizView,
+  renderIde
+} from "@wcrichto/quiz";
 import * as rustEditor from "@wcrichto/rust-editor";
 import React from "react";
 import * as ReactDOM from "react-dom/client";
@@ -32,6 +37,10 @@ let initQuizzes = () => {
     let divEl = el as HTMLDivElement;
     let name = divEl.dataset.quizName!;
     let quiz: Quiz = JSON.parse(divEl.dataset.quizQuestions!);
+    let pools: QuestionDraw[] | undefined =
+      divEl.dataset.quizPools !== undefined
+        ? JSON.parse(divEl.dataset.quizPools)
+        : undefined;
     let root = ReactDOM.createRoot(el);
     let fullscreen = divEl.dataset.quizFullscreen !== undefined;
     let cacheAnswers = divEl.dataset.quizCacheAnswers !== undefined;
@@ -40,6 +49,7 @@ let initQuizzes = () => {
         <QuizView
           name={name}
           quiz={quiz}
+          pools={pools}
           fullscreen={fullscreen}
           cacheAnswers={cacheAnswers}
           allowRetry
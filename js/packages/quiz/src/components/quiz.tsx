@@ -26,6 +26,7 @@ interface StoredAnswers {
   quizHash: string;
   attempt: number;
   wrongAnswers?: number[];
+  drawnQuestions?: number[];
 }
 
 declare global {
@@ -48,14 +49,16 @@ class AnswerStorage {
     answers: TaggedAnswer[],
     confirmedDone: boolean,
     attempt: number,
-    wrongAnswers?: number[]
+    wrongAnswers?: number[],
+    drawnQuestions?: number[]
   ) {
     let storedAnswers: StoredAnswers = {
       answers,
       confirmedDone,
       attempt,
       quizHash: this.quizHash,
-      wrongAnswers
+      wrongAnswers,
+      drawnQuestions
     };
     localStorage.setItem(this.storageKey(), JSON.stringify(storedAnswers));
   }
@@ -98,6 +101,34 @@ let ExitExplanation = ({
   );
 };
 
+/**
+ * Questions of a quiz of which `draw` are asked in each attempt, as given by
+ * the `data-quiz-pools` attribute of the quiz placeholder.
+ */
+export interface QuestionDraw {
+  questions: number[];
+  draw: number;
+}
+
+/**
+ * Randomly picks the questions of a quiz with `nQuestions` questions that are
+ * asked in an attempt, returning their indices in order. Questions that aren't
+ * in a pool are always asked.
+ */
+export let drawQuestions = (
+  nQuestions: number,
+  pools: QuestionDraw[]
+): number[] => {
+  let skipped = new Set<number>();
+  for (let pool of pools) {
+    let drawn = _.sampleSize(pool.questions, pool.draw);
+    pool.questions
+      .filter(i => !drawn.includes(i))
+      .forEach(i => skipped.add(i));
+  }
+  return _.range(nQuestions).filter(i => !skipped.has(i));
+};
+
 interface QuizState {
   started: boolean;
   index: number;
@@ -105,14 +136,17 @@ interface QuizState {
   attempt: number;
   answers: TaggedAnswer[];
   wrongAnswers?: number[];
+  drawnQuestions?: number[];
 }
 
 let loadState = ({
   quiz,
+  pools,
   answerStorage,
   cacheAnswers
 }: {
   quiz: Quiz;
+  pools?: QuestionDraw[];
   answerStorage: AnswerStorage;
   cacheAnswers?: boolean;
 }): QuizState => {
@@ -128,16 +162,18 @@ let loadState = ({
     !stored.wrongAnswers;
 
   if (cacheAnswers && stored && !badSchema) {
+    let nQuestions = stored.drawnQuestions?.length ?? quiz.questions.length;
     return {
       started: true,
-      index: quiz.questions.length,
+      index: nQuestions,
       answers: stored.answers,
       // note: need to provide defaults if schema changes
       confirmedDone: stored.confirmedDone || false,
       attempt: stored.attempt || 0,
       wrongAnswers:
         stored.wrongAnswers ||
-        (stored.attempt > 0 ? _.range(quiz.questions.length) : undefined)
+        (stored.attempt > 0 ? _.range(nQuestions) : undefined),
+      drawnQuestions: stored.drawnQuestions
     };
   } else {
     return {
@@ -145,7 +181,8 @@ let loadState = ({
       index: 0,
       attempt: 0,
       confirmedDone: false,
-      answers: []
+      answers: [],
+      drawnQuestions: pools && drawQuestions(quiz.questions.length, pools)
     };
   }
 };
@@ -296,6 +333,7 @@ export let useCaptureMdbookShortcuts = (capture: boolean) => {
 export interface QuizViewProps {
   name: string;
   quiz: Quiz;
+  pools?: QuestionDraw[];
   fullscreen?: boolean;
   cacheAnswers?: boolean;
   allowRetry?: boolean;
@@ -332,9 +370,34 @@ export let generateQuestionTitles = (quiz: Quiz): string[] => {
 };
 
 export let QuizView: React.FC<QuizViewProps> = observer(
-  ({ quiz, name, fullscreen, cacheAnswers, allowRetry, onFinish }) => {
-    let [quizHash] = useState(() => hash.MD5(quiz));
+  ({
+    quiz: fullQuiz,
+    pools,
+    name,
+    fullscreen,
+    cacheAnswers,
+    allowRetry,
+    onFinish
+  }) => {
+    let [quizHash] = useState(() => hash.MD5(fullQuiz));
     let answerStorage = new AnswerStorage(name, quizHash);
+    let state = useLocalObservable(() =>
+      loadState({ quiz: fullQuiz, pools, answerStorage, cacheAnswers })
+    );
+
+    // If the quiz has pools, then only the drawn questions are asked.
+    let drawnQuestions = state.drawnQuestions;
+    let quiz = useMemo(
+      () =>
+        drawnQuestions
+          ? {
+              ...fullQuiz,
+              questions: drawnQuestions.map(i => fullQuiz.questions[i])
+            }
+          : fullQuiz,
+      [fullQuiz, drawnQuestions]
+    );
+
     let questionStates = useMemo(
       () =>
         quiz.questions.map(q => {
@@ -343,9 +406,6 @@ export let QuizView: React.FC<QuizViewProps> = observer(
         }),
       [quiz]
     );
-    let state = useLocalObservable(() =>
-      loadState({ quiz, answerStorage, cacheAnswers })
-    );
 
     let saveToCache = () => {
       if (cacheAnswers)
@@ -353,7 +413,8 @@ export let QuizView: React.FC<QuizViewProps> = observer(
           state.answers,
           state.confirmedDone,
           state.attempt,
-          state.wrongAnswers
+          state.wrongAnswers,
+          state.drawnQuestions
         );
     };
 
@@ -396,7 +457,8 @@ export let QuizView: React.FC<QuizViewProps> = observer(
         quizName: name,
         quizHash,
         answers: state.answers,
-        attempt: state.attempt
+        attempt: state.attempt,
+        drawnQuestions: state.drawnQuestions
       });
 
       if (state.index === quiz.questions.length) {
@@ -474,6 +536,9 @@ export let QuizView: React.FC<QuizViewProps> = observer(
       state.started = false;
       state.index = 0;
       state.answers = [];
+      if (pools) {
+        state.drawnQuestions = drawQuestions(fullQuiz.questions.length, pools);
+      }
     });
     let exitButton = (
       <div className="exit" onClick={onExit}>
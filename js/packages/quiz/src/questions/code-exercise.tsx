@@ -0,0 +1,54 @@
+import React from "react";
+
+import type { CodeExerciseAnswer } from "../bindings/CodeExerciseAnswer";
+import type { CodeExercisePrompt } from "../bindings/CodeExercisePrompt";
+import { MarkdownView } from "../components/markdown";
+import { Snippet } from "../components/snippet";
+import type { QuestionMethods } from "./types";
+
+// Programs can't be run in the browser, so a response is only graded
+// automatically if it is the reference solution up to whitespace.
+let normalize = (program: string) => program.replace(/\s+/g, "");
+
+export let CodeExerciseMethods: QuestionMethods<
+  CodeExercisePrompt,
+  CodeExerciseAnswer
+> = {
+  PromptView: ({ prompt }) => (
+    <MarkdownView
+      markdown={prompt.prompt}
+      snippetOptions={{ lineNumbers: true }}
+    />
+  ),
+
+  ResponseView: ({ prompt, formValidators: { required } }) => (
+    <textarea
+      {...required("solution")}
+      className="code-exercise-editor"
+      defaultValue={prompt.starter.trim()}
+      spellCheck={false}
+      rows={Math.max(5, prompt.starter.trim().split("\n").length + 2)}
+    />
+  ),
+
+  getAnswerFromDOM(data) {
+    // The tests are hidden from users, so they are never part of a response.
+    return { solution: data.solution, tests: "" };
+  },
+
+  compareAnswers(provided, user) {
+    return normalize(provided.solution) === normalize(user.solution);
+  },
+
+  AnswerView: ({ answer, baseline, prompt }) => (
+    <div
+      className={
+        CodeExerciseMethods.compareAnswers!(baseline, answer, prompt)
+          ? "correct"
+          : "incorrect"
+      }
+    >
+      <Snippet snippet={answer.solution} />
+    </div>
+  )
+};
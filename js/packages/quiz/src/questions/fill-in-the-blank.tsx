@@ -0,0 +1,175 @@
+import classNames from "classnames";
+import React from "react";
+
+import type { BlankAnswer } from "../bindings/BlankAnswer";
+import type { FillInTheBlankAnswer } from "../bindings/FillInTheBlankAnswer";
+import type { FillInTheBlankPrompt } from "../bindings/FillInTheBlankPrompt";
+import { MarkdownView } from "../components/markdown";
+import type { QuestionMethods } from "./types";
+
+let BLANK_PATTERN = /\{\{(\d+)\}\}/g;
+
+// Splits the text into alternating literal text and blank numbers,
+// e.g. "let {{1}} x" becomes ["let ", "1", " x"].
+let splitBlanks = (text: string): string[] => text.split(BLANK_PATTERN);
+
+let clean = (s: string) => s.trim();
+
+let matchesBlank = (accepted: BlankAnswer, response: string): boolean =>
+  typeof accepted === "string"
+    ? clean(accepted) === clean(response)
+    : new RegExp(`^(?:${accepted.regex})$`).test(clean(response));
+
+let blankIsCorrect = (
+  baseline: FillInTheBlankAnswer,
+  blank: string,
+  response: string
+): boolean =>
+  (baseline.blanks[blank] || []).some(accepted =>
+    matchesBlank(accepted, response)
+  );
+
+let showBlankAnswer = (answer: BlankAnswer): string =>
+  typeof answer === "string" ? answer : `/${answer.regex}/`;
+
+let escapeHtml = (s: string) =>
+  s.replace(
+    /[&<>"]/g,
+    c => ({ "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;" })[c]!
+  );
+
+// Renders the text with each blank replaced by `renderBlank`. Code is rendered
+// with React elements, while Markdown is rendered with raw HTML for each blank.
+let BlankText: React.FC<{
+  prompt: FillInTheBlankPrompt;
+  renderBlank: (blank: string, occurrence: number) => React.ReactElement;
+  renderBlankHtml: (blank: string, occurrence: number) => string;
+}> = ({ prompt, renderBlank, renderBlankHtml }) => {
+  let parts = splitBlanks(prompt.text.trim());
+  let occurrences: { [blank: string]: number } = {};
+  let nextOccurrence = (blank: string) => {
+    let n = occurrences[blank] || 0;
+    occurrences[blank] = n + 1;
+    return n;
+  };
+
+  if (prompt.format === "code") {
+    return (
+      <pre className="fill-in-the-blank-text">
+        <code>
+          {parts.map((part, i) =>
+            i % 2 === 0 ? (
+              <React.Fragment key={i}>{part}</React.Fragment>
+            ) : (
+              <React.Fragment key={i}>
+                {renderBlank(part, nextOccurrence(part))}
+              </React.Fragment>
+            )
+          )}
+        </code>
+      </pre>
+    );
+  } else {
+    let markdown = parts
+      .map((part, i) =>
+        i % 2 === 0 ? part : renderBlankHtml(part, nextOccurrence(part))
+      )
+      .join("");
+    return (
+      <div className="fill-in-the-blank-text">
+        <MarkdownView markdown={markdown} />
+      </div>
+    );
+  }
+};
+
+export let FillInTheBlankMethods: QuestionMethods<
+  FillInTheBlankPrompt,
+  FillInTheBlankAnswer
+> = {
+  PromptView: ({ prompt }) => (
+    <MarkdownView
+      markdown={prompt.prompt}
+      snippetOptions={{ lineNumbers: true }}
+    />
+  ),
+
+  ResponseView: ({ prompt }) => (
+    <BlankText
+      prompt={prompt}
+      renderBlank={blank => (
+        <input
+          type="text"
+          className="blank"
+          data-blank={blank}
+          aria-label={`Praznina ${blank}`}
+          required
+        />
+      )}
+      renderBlankHtml={blank =>
+        `<input type="text" class="blank" data-blank="${blank}" aria-label="Praznina ${blank}" required />`
+      }
+    />
+  ),
+
+  getAnswerFromDOM(_data, container) {
+    let blanks: FillInTheBlankAnswer["blanks"] = {};
+    container
+      .querySelectorAll<HTMLInputElement>("input.blank")
+      .forEach(input => {
+        let blank = input.dataset.blank!;
+        blanks[blank] = (blanks[blank] || []).concat([input.value]);
+      });
+    return { blanks };
+  },
+
+  AnswerView: ({ answer, baseline, prompt }) => {
+    let response = (blank: string, occurrence: number) => {
+      // The correct answer lists alternatives for each blank rather than
+      // responses for each occurrence, so show its first alternative.
+      let answers = answer.blanks[blank] || [];
+      let index =
+        answer === baseline ? 0 : Math.min(occurrence, answers.length - 1);
+      let response = answers[index];
+      return response === undefined ? "" : showBlankAnswer(response);
+    };
+    let isCorrect = (blank: string, occurrence: number) =>
+      blankIsCorrect(baseline, blank, response(blank, occurrence)) ||
+      answer === baseline;
+    return (
+      <BlankText
+        prompt={prompt}
+        renderBlank={(blank, occurrence) => (
+          <span
+            className={classNames(
+              "blank",
+              isCorrect(blank, occurrence) ? "correct" : "incorrect"
+            )}
+          >
+            {response(blank, occurrence)}
+          </span>
+        )}
+        renderBlankHtml={(blank, occurrence) =>
+          `<code class="blank ${
+            isCorrect(blank, occurrence) ? "correct" : "incorrect"
+          }">${escapeHtml(response(blank, occurrence))}</code>`
+        }
+      />
+    );
+  },
+
+  compareAnswers(
+    providedAnswer: FillInTheBlankAnswer,
+    userAnswer: FillInTheBlankAnswer
+  ): boolean {
+    return Object.keys(providedAnswer.blanks).every(blank => {
+      let responses = userAnswer.blanks[blank] || [];
+      return (
+        responses.length > 0 &&
+        responses.every(response =>
+          blankIsCorrect(providedAnswer, blank, response)
+        )
+      );
+    });
+  }
+};
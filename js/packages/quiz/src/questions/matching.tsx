@@ -0,0 +1,127 @@
+import classNames from "classnames";
+import _ from "lodash";
+import React from "react";
+
+import type { Markdown } from "../bindings/Markdown";
+import type { MatchingAnswer } from "../bindings/MatchingAnswer";
+import type { MatchingPrompt } from "../bindings/MatchingPrompt";
+import { MarkdownView } from "../components/markdown";
+import type { QuestionMethods } from "./types";
+
+interface MatchingState {
+  right: Markdown[];
+}
+
+// Right items are labeled A, B, C, ... so that they can be rendered as
+// Markdown while being selected in a plain <select>.
+let label = (i: number) => String.fromCharCode("A".charCodeAt(0) + i);
+
+let matchFor = (
+  answer: MatchingAnswer,
+  left: Markdown
+): Markdown | undefined => answer.pairs.find(([l]) => l === left)?.[1];
+
+export let MatchingMethods: QuestionMethods<
+  MatchingPrompt,
+  MatchingAnswer,
+  MatchingState
+> = {
+  PromptView: ({ prompt }) => (
+    <MarkdownView
+      markdown={prompt.prompt}
+      snippetOptions={{ lineNumbers: true }}
+    />
+  ),
+
+  questionState(prompt) {
+    return { right: _.shuffle(prompt.right) };
+  },
+
+  ResponseView: ({ prompt, state, formValidators: { required } }) => (
+    <div className="matching-columns">
+      <table className="matching-left">
+        <tbody>
+          {prompt.left.map((left, i) => (
+            <tr key={i}>
+              <td>
+                <MarkdownView markdown={left} />
+              </td>
+              <td>
+                <select
+                  {...required(`match${i}`)}
+                  aria-label={`Par ${i + 1}`}
+                >
+                  <option value="">Odaberite...</option>
+                  {state!.right.map((right, j) => (
+                    <option key={j} value={JSON.stringify([left, right])}>
+                      {label(j)}
+                    </option>
+                  ))}
+                </select>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      <ol className="matching-right" type="A">
+        {state!.right.map((right, j) => (
+          <li key={j}>
+            <MarkdownView markdown={right} />
+          </li>
+        ))}
+      </ol>
+    </div>
+  ),
+
+  getAnswerFromDOM(data) {
+    // Each option's value is the JSON of the pair it would create.
+    let pairs = Object.keys(data)
+      .filter(key => key.startsWith("match"))
+      .sort(
+        (a, b) =>
+          parseInt(a.slice("match".length)) - parseInt(b.slice("match".length))
+      )
+      .map(key => JSON.parse(data[key]));
+    return { pairs };
+  },
+
+  compareAnswers(provided, user) {
+    let toMap = (answer: MatchingAnswer) => _.fromPairs(answer.pairs);
+    return _.isEqual(toMap(provided), toMap(user));
+  },
+
+  AnswerView: ({ answer, baseline, prompt }) => (
+    <div
+      className={
+        MatchingMethods.compareAnswers!(baseline, answer, prompt)
+          ? "correct"
+          : "incorrect"
+      }
+    >
+      <table className="matching-answer">
+        <tbody>
+          {prompt.left.map((left, i) => {
+            let right = matchFor(answer, left);
+            return (
+              <tr
+                key={i}
+                className={classNames({
+                  mismatch:
+                    right === undefined || right !== matchFor(baseline, left)
+                })}
+              >
+                <td>
+                  <MarkdownView markdown={left} />
+                </td>
+                <td>→</td>
+                <td>
+                  {right !== undefined && <MarkdownView markdown={right} />}
+                </td>
+              </tr>
+            );
+          })}
+        </tbody>
+      </table>
+    </div>
+  )
+};
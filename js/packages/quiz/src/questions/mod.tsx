@@ -8,19 +8,34 @@ import type { Quiz } from "../bindings/Quiz";
 import { MarkdownView } from "../components/markdown";
 import { MoreInfo } from "../components/more-info";
 import { useCaptureMdbookShortcuts } from "../lib";
+import { CodeExerciseMethods } from "./code-exercise";
+import { FillInTheBlankMethods } from "./fill-in-the-blank";
+import { MatchingMethods } from "./matching";
 import { MultipleChoiceMethods } from "./multiple-choice";
+import { NumericMethods } from "./numeric";
+import { OrderingMethods } from "./ordering";
 import { ShortAnswerMethods } from "./short-answer";
 import { TracingMethods } from "./tracing";
 import type { QuestionMethods } from "./types";
 
+export { CodeExerciseMethods } from "./code-exercise";
+export { FillInTheBlankMethods } from "./fill-in-the-blank";
+export { MatchingMethods } from "./matching";
 export { MultipleChoiceMethods } from "./multiple-choice";
+export { NumericMethods } from "./numeric";
+export { OrderingMethods } from "./ordering";
 export { ShortAnswerMethods } from "./short-answer";
 export { TracingMethods } from "./tracing";
 
 let methodMapping = {
   ShortAnswer: ShortAnswerMethods,
   Tracing: TracingMethods,
-  MultipleChoice: MultipleChoiceMethods
+  MultipleChoice: MultipleChoiceMethods,
+  FillInTheBlank: FillInTheBlankMethods,
+  Ordering: OrderingMethods,
+  Matching: MatchingMethods,
+  CodeExercise: CodeExerciseMethods,
+  Numeric: NumericMethods
 };
 
 export let getQuestionMethods = (
@@ -183,7 +198,7 @@ export let QuestionView: React.FC<QuestionViewProps> = ({
       ? methods.getAnswerFromDOM(data, ref.current!)
       : data;
     let comparator = methods.compareAnswers || _.isEqual;
-    let correct = comparator(question.answer, answer);
+    let correct = comparator(question.answer, answer, question.prompt);
     onSubmit({
       answer,
       correct,
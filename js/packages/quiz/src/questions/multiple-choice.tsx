@@ -80,11 +80,11 @@ export let MultipleChoiceMethods: QuestionMethods<
     return _.isEqual(toList(provided.answer), toList(user.answer));
   },
 
-  AnswerView: ({ answer, baseline }) => (
+  AnswerView: ({ answer, baseline, prompt }) => (
     <div
       className={classNames(
         "md-flex",
-        MultipleChoiceMethods.compareAnswers!(baseline, answer)
+        MultipleChoiceMethods.compareAnswers!(baseline, answer, prompt)
           ? "correct"
           : "incorrect"
       )}
@@ -0,0 +1,113 @@
+import React from "react";
+
+import type { NumericAnswer } from "../bindings/NumericAnswer";
+import type { NumericPrompt } from "../bindings/NumericPrompt";
+import type { NumericRepresentation } from "../bindings/NumericRepresentation";
+import { MarkdownView } from "../components/markdown";
+import type { QuestionMethods } from "./types";
+
+let DECIMAL = /^[+-]?(\d+\.?\d*|\.\d+)(e[+-]?\d+)?$/i;
+let PREFIXED: { [R in NumericRepresentation]: [RegExp, number] } = {
+  hex: [/^([+-]?)0x([0-9a-f]+)$/i, 16],
+  binary: [/^([+-]?)0b([01]+)$/i, 2]
+};
+
+// Parses a response, ignoring whitespace and `_` separators as in Rust literals.
+// Returns undefined if the response isn't a number written in an accepted way.
+let parseNumber = (
+  input: string,
+  representations: NumericRepresentation[],
+  integer: boolean
+): number | undefined => {
+  let s = input.trim().replace(/_/g, "");
+  let n: number | undefined;
+  if (DECIMAL.test(s)) {
+    n = Number(s);
+  } else {
+    for (let repr of representations) {
+      let [pattern, radix] = PREFIXED[repr];
+      let match = s.match(pattern);
+      if (match) {
+        n = parseInt(match[2], radix) * (match[1] === "-" ? -1 : 1);
+      }
+    }
+  }
+  if (n === undefined || (integer && !Number.isInteger(n))) return undefined;
+  return n;
+};
+
+// The inclusive bounds of correct responses, after applying tolerances.
+let bounds = (answer: NumericAnswer): [number, number] => {
+  let [min, max] = answer.range ?? [answer.value!, answer.value!];
+  let tolerance = (x: number) =>
+    Math.max(
+      answer.tolerance ?? 0,
+      (answer.relativeTolerance ?? 0) * Math.abs(x)
+    );
+  return [min - tolerance(min), max + tolerance(max)];
+};
+
+let showAnswer = (answer: NumericAnswer): string => {
+  if (answer.range) return `${answer.range[0]} – ${answer.range[1]}`;
+  let tolerance = answer.tolerance
+    ? ` ± ${answer.tolerance}`
+    : answer.relativeTolerance
+    ? ` ± ${answer.relativeTolerance * 100}%`
+    : "";
+  return `${answer.value}${tolerance}`;
+};
+
+export let NumericMethods: QuestionMethods<NumericPrompt, NumericAnswer> = {
+  PromptView: ({ prompt }) => (
+    <MarkdownView
+      markdown={prompt.prompt}
+      snippetOptions={{ lineNumbers: true }}
+    />
+  ),
+
+  ResponseView: ({ prompt, answer, submit, formValidators: { required } }) => (
+    <div className="numeric-response">
+      <input
+        {...required("answer", {
+          validate: value =>
+            parseNumber(
+              value,
+              answer.representations ?? [],
+              answer.integer ?? false
+            ) !== undefined
+        })}
+        type="text"
+        inputMode={answer.representations ? "text" : "decimal"}
+        placeholder="Ovdje upišite broj..."
+        onKeyDown={e => {
+          if (e.key === "Enter") submit();
+        }}
+      />
+      {prompt.units && <span className="units">{prompt.units}</span>}
+    </div>
+  ),
+
+  getAnswerFromDOM(data) {
+    // The response was already checked against the accepted representations.
+    return { value: parseNumber(data.answer, ["hex", "binary"], false) };
+  },
+
+  compareAnswers(provided, user) {
+    let [min, max] = bounds(provided);
+    return user.value !== undefined && min <= user.value && user.value <= max;
+  },
+
+  AnswerView: ({ answer, baseline, prompt }) => (
+    <code
+      className={
+        answer === baseline ||
+        NumericMethods.compareAnswers!(baseline, answer, prompt)
+          ? "correct"
+          : "incorrect"
+      }
+    >
+      {answer === baseline ? showAnswer(answer) : answer.value}
+      {prompt.units && ` ${prompt.units}`}
+    </code>
+  )
+};
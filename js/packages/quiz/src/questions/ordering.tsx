@@ -0,0 +1,213 @@
+import classNames from "classnames";
+import _ from "lodash";
+import React, { useState } from "react";
+
+import type { OrderingAnswer } from "../bindings/OrderingAnswer";
+import type { OrderingPrompt } from "../bindings/OrderingPrompt";
+import { MarkdownView } from "../components/markdown";
+import { Snippet } from "../components/snippet";
+import type { QuestionMethods } from "./types";
+
+interface OrderingState {
+  fragments: string[];
+}
+
+interface PlacedFragment {
+  fragment: string;
+  indent: number;
+}
+
+let leadingSpaces = (s: string) => s.length - s.trimStart().length;
+
+// Each level of indentation in a user's answer is this many spaces.
+let USER_INDENT = 2;
+
+// The indentation of each fragment in levels, relative to the least indented
+// fragment. If not given, the size of a level is the smallest difference in
+// indentation between any fragment and the least indented one.
+let indentLevels = (fragments: string[], unit?: number): number[] => {
+  let spaces = fragments.map(leadingSpaces);
+  let base = _.min(spaces) || 0;
+  unit = unit || _.min(spaces.map(n => n - base).filter(n => n > 0)) || 1;
+  return spaces.map(n => Math.round((n - base) / unit!));
+};
+
+let Fragment: React.FC<{ prompt: OrderingPrompt; fragment: string }> = ({
+  prompt,
+  fragment
+}) =>
+  prompt.format === "code" ? (
+    <Snippet snippet={fragment} />
+  ) : (
+    <MarkdownView markdown={fragment} />
+  );
+
+export let OrderingMethods: QuestionMethods<
+  OrderingPrompt,
+  OrderingAnswer,
+  OrderingState
+> = {
+  PromptView: ({ prompt }) => (
+    <MarkdownView
+      markdown={prompt.prompt}
+      snippetOptions={{ lineNumbers: true }}
+    />
+  ),
+
+  questionState(prompt, answer) {
+    let fragments = _.shuffle([
+      ...answer.fragments,
+      ...(prompt.distractors || [])
+    ]).map(fragment => fragment.trim());
+    return { fragments };
+  },
+
+  ResponseView: ({ prompt, state, formValidators: { register } }) => {
+    let [placed, setPlaced] = useState<PlacedFragment[]>([]);
+    let [pool, setPool] = useState<string[]>(state!.fragments);
+
+    let place = (i: number) => {
+      setPlaced([...placed, { fragment: pool[i], indent: 0 }]);
+      setPool(pool.filter((_f, j) => j !== i));
+    };
+    let unplace = (i: number) => {
+      setPool([...pool, placed[i].fragment]);
+      setPlaced(placed.filter((_f, j) => j !== i));
+    };
+    let move = (i: number, delta: number) => {
+      let j = i + delta;
+      if (j < 0 || j >= placed.length) return;
+      let next = [...placed];
+      [next[i], next[j]] = [next[j], next[i]];
+      setPlaced(next);
+    };
+    let indent = (i: number, delta: number) => {
+      let next = [...placed];
+      next[i] = { ...next[i], indent: Math.max(0, next[i].indent + delta) };
+      setPlaced(next);
+    };
+
+    let fragments = placed.map(
+      ({ fragment, indent }) => " ".repeat(indent * USER_INDENT) + fragment
+    );
+
+    return (
+      <>
+        <input
+          type="hidden"
+          {...register("fragments", {
+            validate: value => JSON.parse(value).length > 0
+          })}
+          value={JSON.stringify(fragments)}
+        />
+        <p>Poredajte dijelove odgovora:</p>
+        <ol className="ordering-answer">
+          {placed.map(({ fragment, indent: level }, i) => (
+            <li key={fragment + i}>
+              <div
+                className="fragment"
+                style={{ marginLeft: `${level * 2}em` }}
+              >
+                <Fragment prompt={prompt} fragment={fragment} />
+              </div>
+              <div className="fragment-controls">
+                {prompt.gradeIndentation && (
+                  <>
+                    <button type="button" onClick={() => indent(i, -1)}>
+                      ⇤
+                    </button>
+                    <button type="button" onClick={() => indent(i, 1)}>
+                      ⇥
+                    </button>
+                  </>
+                )}
+                <button type="button" onClick={() => move(i, -1)}>
+                  ↑
+                </button>
+                <button type="button" onClick={() => move(i, 1)}>
+                  ↓
+                </button>
+                <button type="button" onClick={() => unplace(i)}>
+                  ✕
+                </button>
+              </div>
+            </li>
+          ))}
+        </ol>
+        {pool.length > 0 && (
+          <>
+            <p>Ponuđeni dijelovi:</p>
+            <ul className="ordering-pool">
+              {pool.map((fragment, i) => (
+                <li key={fragment + i}>
+                  <button
+                    type="button"
+                    className="fragment"
+                    onClick={() => place(i)}
+                  >
+                    <Fragment prompt={prompt} fragment={fragment} />
+                  </button>
+                </li>
+              ))}
+            </ul>
+          </>
+        )}
+      </>
+    );
+  },
+
+  getAnswerFromDOM(data) {
+    return { fragments: JSON.parse(data.fragments) };
+  },
+
+  compareAnswers(
+    providedAnswer: OrderingAnswer,
+    userAnswer: OrderingAnswer,
+    prompt: OrderingPrompt
+  ): boolean {
+    let clean = (fragments: string[]) => fragments.map(s => s.trim());
+    let sameOrder = _.isEqual(
+      clean(providedAnswer.fragments),
+      clean(userAnswer.fragments)
+    );
+    let sameIndentation =
+      !prompt.gradeIndentation ||
+      _.isEqual(
+        indentLevels(providedAnswer.fragments),
+        indentLevels(userAnswer.fragments, USER_INDENT)
+      );
+    return sameOrder && sameIndentation;
+  },
+
+  AnswerView: ({ answer, baseline, prompt }) => {
+    let levels = indentLevels(
+      answer.fragments,
+      answer === baseline ? undefined : USER_INDENT
+    );
+    return (
+      <ol
+        className={classNames(
+          "ordering-answer",
+          OrderingMethods.compareAnswers!(baseline, answer, prompt)
+            ? "correct"
+            : "incorrect"
+        )}
+      >
+        {answer.fragments.map((fragment, i) => (
+          <li key={i}>
+            <div
+              className="fragment"
+              style={
+                prompt.gradeIndentation
+                  ? { marginLeft: `${levels[i] * 2}em` }
+                  : undefined
+              }
+            >
+              <Fragment prompt={prompt} fragment={fragment.trim()} />
+            </div>
+          </li>
+        ))}
+      </ol>
+    );
+  }
+};
@@ -37,10 +37,10 @@ export let ShortAnswerMethods: QuestionMethods<
     );
   },
 
-  AnswerView: ({ answer, baseline }) => (
+  AnswerView: ({ answer, baseline, prompt }) => (
     <code
       className={
-        ShortAnswerMethods.compareAnswers!(baseline, answer)
+        ShortAnswerMethods.compareAnswers!(baseline, answer, prompt)
           ? "correct"
           : "incorrect"
       }
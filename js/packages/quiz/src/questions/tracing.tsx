@@ -21,6 +21,15 @@ import type { QuestionMethods } from "./types";
 // Here, that would be line 4. (Since without line 4, this program would compile!)
 // `;
 
+let ProgramFile = ({ path, contents }: { path: string; contents: string }) => (
+  <div className="tracing-file">
+    <div className="tracing-file-path">
+      <code>{path}</code>
+    </div>
+    <Snippet snippet={contents} lineNumbers />
+  </div>
+);
+
 export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
   PromptView: ({ prompt }) => (
     <>
@@ -30,7 +39,19 @@ export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
         {/* If the program does not pass, indicate the last line number involved in the
         compiler error. */}
       </p>
-      <Snippet snippet={prompt.program} lineNumbers />
+      {prompt.files ? (
+        <>
+          <ProgramFile
+            path={prompt.entrypoint ?? "main.rs"}
+            contents={prompt.program}
+          />
+          {Object.entries(prompt.files).map(([path, contents]) => (
+            <ProgramFile key={path} path={path} contents={contents} />
+          ))}
+        </>
+      ) : (
+        <Snippet snippet={prompt.program} lineNumbers />
+      )}
     </>
   ),
 
@@ -38,16 +59,22 @@ export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
     // prompt,
     formValidators: {
       required,
+      register,
       formState: { errors }
     }
   }) => {
     let [doesCompile, setDoesCompile] = useState<boolean | undefined>(
       undefined
     );
+    let [panics, setPanics] = useState(false);
     // let lineNumbers = _.range(prompt.program.trim().split("\n").length).map(
     //   i => i + 1
     // );
-    let [doesCompileTrueId, doesCompileFalseId] = [useId(), useId()];
+    let [doesCompileTrueId, doesCompileFalseId, panicsId] = [
+      useId(),
+      useId(),
+      useId()
+    ];
     return (
       <>
         <div className="response-block">
@@ -79,9 +106,20 @@ export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
           <div>
             <p>Ovaj će program ispisati:</p>
             <textarea
-              {...required("stdout")}
+              {...(panics ? register("stdout") : required("stdout"))}
               placeholder="Napišite ovdje što će program ispisati..."
             />
+            <p>
+              <input
+                type="checkbox"
+                {...register("panics")}
+                id={panicsId}
+                onClick={e => setPanics(e.currentTarget.checked)}
+              />{" "}
+              <label htmlFor={panicsId}>
+                Program će nakon toga <strong>paničariti</strong>
+              </label>
+            </p>
           </div> /*<div>
           <p>
             The error occurs on the line number:{" "}
@@ -106,7 +144,9 @@ export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
     let doesCompile = data.doesCompile === "true";
     if (doesCompile) {
       let stdout = data.stdout;
-      return { doesCompile, stdout };
+      return data.panics
+        ? { doesCompile, stdout, panic: {} }
+        : { doesCompile, stdout };
     } else {
       return { doesCompile };
     }
@@ -125,8 +165,7 @@ export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
           <>
             <p
               className={
-                baseline.stdout &&
-                answer.stdout!.trim() === baseline.stdout!.trim()
+                (answer.stdout ?? "").trim() === (baseline.stdout ?? "").trim()
                   ? "correct"
                   : "incorrect"
               }
@@ -134,6 +173,31 @@ export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
               Ovaj će program ispisati:
             </p>
             <pre>{answer.stdout}</pre>
+            {(answer.panic || baseline.panic) && (
+              <p
+                className={
+                  !answer.panic === !baseline.panic ? "correct" : "incorrect"
+                }
+              >
+                {answer.panic ? (
+                  <>
+                    Program zatim <strong>paničari</strong>
+                    {answer.panic.message ? (
+                      <>
+                        {" "}
+                        s porukom: <code>{answer.panic.message}</code>
+                      </>
+                    ) : (
+                      "."
+                    )}
+                  </>
+                ) : (
+                  <>
+                    Program <strong>ne paničari</strong>.
+                  </>
+                )}
+              </p>
+            )}
           </>
         ) : /*<p className={correctnessClass("lineNumber")}>
             The last line number in the error is:{" "}
@@ -148,11 +212,13 @@ export let TracingMethods: QuestionMethods<TracingPrompt, TracingAnswer> = {
     providedAnswer: TracingAnswer,
     userAnswer: TracingAnswer
   ): boolean {
-    let clean = (s: string) => s.trim();
+    // A program that panics before printing anything may omit its stdout.
+    let clean = (s: string | undefined) => (s ?? "").trim();
     return (
       providedAnswer.doesCompile === userAnswer.doesCompile &&
       (providedAnswer.doesCompile
-        ? clean(userAnswer.stdout!) === clean(providedAnswer.stdout!)
+        ? clean(userAnswer.stdout) === clean(providedAnswer.stdout) &&
+          !userAnswer.panic === !providedAnswer.panic
         : true)
       // : userAnswer.lineNumber! == providedAnswer.lineNumber!)
     );
@@ -30,7 +30,11 @@ export interface QuestionMethods<Prompt, Answer, State = any> {
 
   AnswerView: React.FC<{ answer: Answer; baseline: Answer; prompt: Prompt }>;
 
-  compareAnswers?(providedAnswer: Answer, userAnswer: Answer): boolean;
+  compareAnswers?(
+    providedAnswer: Answer,
+    userAnswer: Answer,
+    prompt: Prompt
+  ): boolean;
 
   validate?(prompt: Prompt, answer: Answer): boolean;
 }
@@ -0,0 +1,57 @@
+import { render, screen, waitFor } from "@testing-library/react";
+import user from "@testing-library/user-event";
+import React from "react";
+import { beforeEach, describe, expect, it } from "vitest";
+
+import type { CodeExercise } from "../src/bindings/CodeExercise";
+import { QuestionView } from "../src/lib";
+import { submitButton } from "./utils";
+
+describe("CodeExercise", () => {
+  let question: CodeExercise & { type: "CodeExercise" } = {
+    type: "CodeExercise",
+    prompt: {
+      prompt: "Hello world",
+      starter: "fn add(a: i32, b: i32) -> i32 { todo!() }"
+    },
+    answer: {
+      solution: "fn add(a: i32, b: i32) -> i32 { a + b }",
+      tests: "#[test] fn test_add() { assert_eq!(add(1, 2), 3); }"
+    }
+  };
+
+  let submitted: any | null = null;
+  beforeEach(async () => {
+    submitted = null;
+    render(
+      <QuestionView
+        quizName={"Foobar"}
+        question={question}
+        multipart={{}}
+        index={1}
+        title="1"
+        attempt={0}
+        onSubmit={answer => {
+          submitted = answer;
+        }}
+      />
+    );
+    await waitFor(() => screen.getByText("Hello world"));
+  });
+
+  it("starts with the starter program", () => {
+    let input = screen.getByRole("textbox") as HTMLTextAreaElement;
+    expect(input.value).toBe(question.prompt.starter);
+  });
+
+  it("accepts the solution up to whitespace", async () => {
+    let input = screen.getByRole("textbox");
+    await user.clear(input);
+    await user.type(input, "fn add(a: i32, b: i32) -> i32 {{ a+b }");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({
+      answer: { tests: "" },
+      correct: true
+    });
+  });
+});
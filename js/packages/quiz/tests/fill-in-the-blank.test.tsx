@@ -0,0 +1,62 @@
+import { render, screen, waitFor } from "@testing-library/react";
+import user from "@testing-library/user-event";
+import React from "react";
+import { beforeEach, describe, expect, it } from "vitest";
+
+import type { FillInTheBlank } from "../src/bindings/FillInTheBlank";
+import { QuestionView } from "../src/lib";
+import { submitButton } from "./utils";
+
+describe("FillInTheBlank", () => {
+  let question: FillInTheBlank & { type: "FillInTheBlank" } = {
+    type: "FillInTheBlank",
+    prompt: {
+      prompt: "Hello world",
+      text: "let {{1}} x = {{2}};",
+      format: "code"
+    },
+    answer: { blanks: { "1": ["mut"], "2": ["1", { regex: "\\d+" }] } }
+  };
+
+  let submitted: any | null = null;
+  beforeEach(async () => {
+    submitted = null;
+    render(
+      <QuestionView
+        quizName={"Foobar"}
+        question={question}
+        multipart={{}}
+        index={1}
+        title="1"
+        attempt={0}
+        onSubmit={answer => {
+          submitted = answer;
+        }}
+      />
+    );
+    await waitFor(() => screen.getByText("Hello world"));
+  });
+
+  it("initially renders", () => {
+    expect(screen.getAllByRole("textbox")).toHaveLength(2);
+  });
+
+  it("accepts exact and regex answers", async () => {
+    let [first, second] = screen.getAllByRole("textbox");
+    await user.type(first, " mut");
+    await user.type(second, "42");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({
+      answer: { blanks: { "1": [" mut"], "2": ["42"] } },
+      correct: true
+    });
+  });
+
+  it("rejects incorrect answers", async () => {
+    let [first, second] = screen.getAllByRole("textbox");
+    await user.type(first, "const");
+    await user.type(second, "1");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({ correct: false });
+  });
+});
@@ -0,0 +1,76 @@
+import { render, screen, waitFor } from "@testing-library/react";
+import user from "@testing-library/user-event";
+import React from "react";
+import { beforeEach, describe, expect, it } from "vitest";
+
+import type { Matching } from "../src/bindings/Matching";
+import { QuestionView } from "../src/lib";
+import { submitButton } from "./utils";
+
+describe("Matching", () => {
+  let question: Matching & { type: "Matching" } = {
+    type: "Matching",
+    prompt: {
+      prompt: "Hello world",
+      left: ["a", "b"],
+      right: ["c", "d", "e"]
+    },
+    answer: {
+      pairs: [
+        ["a", "c"],
+        ["b", "d"]
+      ]
+    }
+  };
+
+  let submitted: any | null = null;
+  beforeEach(async () => {
+    submitted = null;
+    render(
+      <QuestionView
+        quizName={"Foobar"}
+        question={question}
+        multipart={{}}
+        index={1}
+        title="1"
+        attempt={0}
+        questionState={{ right: ["c", "d", "e"] }}
+        onSubmit={answer => {
+          submitted = answer;
+        }}
+      />
+    );
+    await waitFor(() => screen.getByText("Hello world"));
+  });
+
+  it("initially renders", () => {});
+
+  it("validates input", async () => {
+    await user.click(submitButton());
+    expect(submitted).toBe(null);
+  });
+
+  it("accepts the correct matching", async () => {
+    let [first, second] = screen.getAllByRole("combobox");
+    await user.selectOptions(first, "A");
+    await user.selectOptions(second, "B");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({
+      answer: {
+        pairs: [
+          ["a", "c"],
+          ["b", "d"]
+        ]
+      },
+      correct: true
+    });
+  });
+
+  it("rejects distractors", async () => {
+    let [first, second] = screen.getAllByRole("combobox");
+    await user.selectOptions(first, "A");
+    await user.selectOptions(second, "C");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({ correct: false });
+  });
+});
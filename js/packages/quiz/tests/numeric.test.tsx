@@ -0,0 +1,76 @@
+import { render, screen, waitFor } from "@testing-library/react";
+import user from "@testing-library/user-event";
+import React from "react";
+import { beforeEach, describe, expect, it } from "vitest";
+
+import type { Numeric } from "../src/bindings/Numeric";
+import { QuestionView } from "../src/lib";
+import { submitButton } from "./utils";
+
+describe("Numeric", () => {
+  let question: Numeric & { type: "Numeric" } = {
+    type: "Numeric",
+    prompt: { prompt: "Hello world", units: "bytes" },
+    answer: {
+      value: 255,
+      tolerance: 1,
+      integer: true,
+      representations: ["hex"]
+    }
+  };
+
+  let submitted: any | null = null;
+  beforeEach(async () => {
+    submitted = null;
+    render(
+      <QuestionView
+        quizName={"Foobar"}
+        question={question}
+        multipart={{}}
+        index={1}
+        title="1"
+        attempt={0}
+        onSubmit={answer => {
+          submitted = answer;
+        }}
+      />
+    );
+    await waitFor(() => screen.getByText("Hello world"));
+  });
+
+  it("initially renders", () => {});
+
+  it("validates input", async () => {
+    let input = screen.getByRole("textbox");
+    await user.type(input, "2.5");
+    await user.click(submitButton());
+    expect(submitted).toBe(null);
+  });
+
+  it("accepts alternate representations", async () => {
+    let input = screen.getByRole("textbox");
+    await user.type(input, "0xff");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({
+      answer: { value: 255 },
+      correct: true
+    });
+  });
+
+  it("accepts answers within the tolerance", async () => {
+    let input = screen.getByRole("textbox");
+    await user.type(input, "256");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({ correct: true });
+  });
+
+  it("rejects answers outside the tolerance", async () => {
+    let input = screen.getByRole("textbox");
+    await user.type(input, "1_000");
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({
+      answer: { value: 1000 },
+      correct: false
+    });
+  });
+});
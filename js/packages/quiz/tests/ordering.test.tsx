@@ -0,0 +1,86 @@
+import { render, screen, waitFor } from "@testing-library/react";
+import user from "@testing-library/user-event";
+import React from "react";
+import { beforeEach, describe, expect, it } from "vitest";
+
+import type { Ordering } from "../src/bindings/Ordering";
+import { QuestionView } from "../src/lib";
+import { OrderingMethods } from "../src/questions/mod";
+import { submitButton } from "./utils";
+
+describe("Ordering", () => {
+  let question: Ordering & { type: "Ordering" } = {
+    type: "Ordering",
+    prompt: { prompt: "Hello world", distractors: ["Distractor"] },
+    answer: { fragments: ["First", "Second"] }
+  };
+
+  let submitted: any | null = null;
+  beforeEach(async () => {
+    submitted = null;
+    render(
+      <QuestionView
+        quizName={"Foobar"}
+        question={question}
+        multipart={{}}
+        index={1}
+        title="1"
+        attempt={0}
+        questionState={{ fragments: ["Second", "Distractor", "First"] }}
+        onSubmit={answer => {
+          submitted = answer;
+        }}
+      />
+    );
+    await waitFor(() => screen.getByText("Hello world"));
+  });
+
+  it("initially renders", () => {});
+
+  it("validates input", async () => {
+    await user.click(submitButton());
+    expect(submitted).toBe(null);
+  });
+
+  it("accepts the correct order", async () => {
+    await user.click(screen.getByRole("button", { name: "First" }));
+    await user.click(screen.getByRole("button", { name: "Second" }));
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({
+      answer: { fragments: ["First", "Second"] },
+      correct: true
+    });
+  });
+
+  it("rejects distractors", async () => {
+    await user.click(screen.getByRole("button", { name: "First" }));
+    await user.click(screen.getByRole("button", { name: "Distractor" }));
+    await user.click(submitButton());
+    expect(submitted).toMatchObject({ correct: false });
+  });
+});
+
+describe("Ordering indentation", () => {
+  let prompt = { prompt: "", format: "code" as const, gradeIndentation: true };
+  let provided = { fragments: ["if x {", "    y();", "}"] };
+
+  it("compares relative indentation", () => {
+    let userAnswer = { fragments: ["if x {", "  y();", "}"] };
+    expect(OrderingMethods.compareAnswers!(provided, userAnswer, prompt)).toBe(
+      true
+    );
+  });
+
+  it("rejects wrong indentation", () => {
+    let userAnswer = { fragments: ["if x {", "y();", "}"] };
+    expect(OrderingMethods.compareAnswers!(provided, userAnswer, prompt)).toBe(
+      false
+    );
+    expect(
+      OrderingMethods.compareAnswers!(provided, userAnswer, {
+        ...prompt,
+        gradeIndentation: false
+      })
+    ).toBe(true);
+  });
+});
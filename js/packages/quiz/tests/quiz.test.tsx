@@ -5,7 +5,7 @@ import { beforeEach, describe, expect, it } from "vitest";
 
 import type { Question } from "../src/bindings/Question";
 import type { Quiz } from "../src/bindings/Quiz";
-import { QuizView, generateQuestionTitles } from "../src/lib";
+import { QuizView, drawQuestions, generateQuestionTitles } from "../src/lib";
 import { startButton, submitButton } from "./utils";
 
 let quiz: Quiz = {
@@ -141,3 +141,21 @@ describe("generateQuestionTitles", () => {
     ]);
   });
 });
+
+describe("drawQuestions", () => {
+  it("draws from each pool and keeps other questions", () => {
+    let pools = [
+      { questions: [1, 2, 3], draw: 2 },
+      { questions: [5, 6], draw: 1 }
+    ];
+    for (let i = 0; i < 10; i++) {
+      let drawn = drawQuestions(7, pools);
+      expect(drawn).toHaveLength(5);
+      expect(drawn).toContain(0);
+      expect(drawn).toContain(4);
+      expect(drawn.filter(j => [1, 2, 3].includes(j))).toHaveLength(2);
+      expect(drawn.filter(j => [5, 6].includes(j))).toHaveLength(1);
+      expect([...drawn].sort((a, b) => a - b)).toStrictEqual(drawn);
+    }
+  });
+});
@@ -94,4 +94,19 @@ describe("Tracing", () => {
       correct: false
     });
   });
+
+  it("distinguishes programs that panic", async () => {
+    let checkbox = getCheckbox();
+    await user.click(checkbox);
+
+    let input = screen.getByRole("textbox");
+    await user.type(input, "Yes");
+    await user.click(screen.getByRole("checkbox"));
+    await user.click(submitButton());
+
+    expect(submitted).toMatchObject({
+      answer: { doesCompile: true, stdout: "Yes", panic: {} },
+      correct: false
+    });
+  });
 });